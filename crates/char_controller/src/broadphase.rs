@@ -0,0 +1,102 @@
+//! A shared broadphase snapshot for crowds of kinematic characters.
+//!
+//! See the documentation of [`BroadphaseSnapshot`] for more information.
+use avian2d::math::Vector;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+/// The AABBs of every collider overlapping [`BroadphaseSnapshot::region`], captured once per fixed tick.
+///
+/// When this resource exists, [`MoveAndSlide::intersections`](super::move_and_slide::MoveAndSlide::intersections)
+/// answers AABB queries that fall entirely inside the region from the snapshot instead of the [`SpatialQueryPipeline`].
+/// Queries reaching outside the region fall back to the pipeline, so a stale or small region only costs performance.
+///
+/// This pays off when many characters move in the same area, as each of them runs several AABB queries per tick.
+/// Characters spread far apart would make the region huge and every lookup scan it, so regions larger than
+/// [`BroadphaseSnapshot::max_extent`] aren't captured and everything goes through the pipeline instead.
+///
+/// Nothing inserts this resource, add it to opt in.
+#[derive(Resource, Clone, Debug)]
+pub struct BroadphaseSnapshot {
+    /// The area covered by the snapshot. Nothing is known about colliders outside of it.
+    pub region: Option<ColliderAabb>,
+    /// Every collider overlapping `region` along with its AABB at the time of the snapshot.
+    pub entries: Vec<(Entity, ColliderAabb)>,
+    /// The largest region size worth capturing, on either axis.
+    pub max_extent: Vector,
+}
+
+impl Default for BroadphaseSnapshot {
+    fn default() -> Self {
+        Self {
+            region: None,
+            entries: Vec::new(),
+            max_extent: Vector::splat(512.0),
+        }
+    }
+}
+
+impl BroadphaseSnapshot {
+    /// Creates an empty snapshot that captures regions up to `max_extent` in size.
+    pub fn new(max_extent: Vector) -> Self {
+        Self {
+            max_extent,
+            ..default()
+        }
+    }
+
+    /// Replaces the snapshot with the colliders overlapping `region`.
+    ///
+    /// Clears it instead if `region` is larger than [`BroadphaseSnapshot::max_extent`].
+    pub fn rebuild(
+        &mut self,
+        region: ColliderAabb,
+        query_pipeline: &SpatialQueryPipeline,
+        aabbs: &Query<&ColliderAabb>,
+    ) {
+        if (region.max - region.min).cmpgt(self.max_extent).any() {
+            self.clear();
+            return;
+        }
+        self.entries.clear();
+        self.entries.extend(
+            query_pipeline
+                .aabb_intersections_with_aabb(region)
+                .into_iter()
+                .filter_map(|entity| aabbs.get(entity).ok().map(|aabb| (entity, *aabb))),
+        );
+        self.region = Some(region);
+    }
+
+    /// Forgets the current snapshot, so every query goes through the pipeline again.
+    pub fn clear(&mut self) {
+        self.region = None;
+        self.entries.clear();
+    }
+
+    /// Whether `aabb` lies entirely inside the snapshot region.
+    pub fn covers(&self, aabb: ColliderAabb) -> bool {
+        self.region.is_some_and(|region| {
+            region.min.cmple(aabb.min).all() && region.max.cmpge(aabb.max).all()
+        })
+    }
+
+    /// Returns the entities whose AABBs overlap `aabb`.
+    ///
+    /// Only meaningful if [`BroadphaseSnapshot::covers`] returned `true` for `aabb`.
+    pub fn aabb_intersections_with_aabb(&self, aabb: ColliderAabb) -> Vec<Entity> {
+        self.entries
+            .iter()
+            .filter(|(_, other)| other.intersects(&aabb))
+            .map(|(entity, _)| *entity)
+            .collect()
+    }
+}
+
+/// Returns the smallest AABB containing both `a` and `b`.
+pub fn merge_aabbs(a: ColliderAabb, b: ColliderAabb) -> ColliderAabb {
+    ColliderAabb {
+        min: Vector::min(a.min, b.min),
+        max: Vector::max(a.max, b.max),
+    }
+}
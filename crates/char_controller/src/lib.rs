@@ -0,0 +1,16 @@
+//! Utilities for implementing character controllers.
+//! temp here until avian merges this and I dont have to patch it in
+//!
+//! Kept in its own crate with no dependencies on the game, so it can be reused elsewhere.
+
+pub mod broadphase;
+pub mod move_and_slide;
+
+/// Re-exports common types related to character controller functionality.
+pub mod prelude {
+    pub use super::broadphase::BroadphaseSnapshot;
+    pub use super::move_and_slide::{
+        CeilingBonk, CeilingHitData, CeilingMode, ContactCache, GroundSnap, GroundStick,
+        MoveAndSlide, MoveAndSlideConfig, MoveAndSlideOutput, Surface,
+    };
+}
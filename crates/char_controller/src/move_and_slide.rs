@@ -0,0 +1,1628 @@
+//! Contains the *move and slide* algorithm and utilities for kinematic character controllers.
+//!
+//! See the documentation of [`MoveAndSlide`] for more information.
+use super::broadphase::BroadphaseSnapshot;
+use avian2d::math::Scalar;
+use avian2d::math::{AdjustPrecision as _, AsF32 as _, Vector};
+use avian2d::{collision::collider::contact_query::contact_manifolds, prelude::*};
+use bevy::{ecs::system::SystemParam, math::Dir2, prelude::*};
+use core::time::Duration;
+
+/// A [`SystemParam`] for the *move and slide* algorithm, also known as *collide and slide* or *step slide*.
+///
+/// Move and slide is the core movement and collision algorithm used by most kinematic character controllers.
+/// It attempts to move a shape along a desired velocity vector, while sliding along any colliders it hits on the way.
+///
+/// # Algorithm
+///
+/// At a high level, the algorithm works as follows:
+///
+/// 1. Sweep the shape along the desired velocity vector.
+/// 2. If no collision is detected, move the full distance.
+/// 3. If a collision is detected:
+///    - Move up to the point of collision.
+///    - Project the remaining velocity onto the contact surfaces to obtain a new sliding velocity.
+/// 4. Repeat with the new sliding velocity.
+///
+/// The algorithm also includes depenetration passes before and after movement to ensure the shape is not intersecting any colliders.
+///
+/// # Configuration
+///
+/// [`MoveAndSlideConfig`] allows configuring various aspects of the algorithm.
+/// See its documentation for more information.
+///
+/// Additionally, [`move_and_slide`](MoveAndSlide::move_and_slide) can be given a callback that is called
+/// for each contact surface that is detected during movement. This allows for custom handling of collisions,
+/// such as triggering events, or modifying movement based on specific colliders.
+///
+/// Before that, a pre-filter callback is asked about every collider hit, with the hit normal.
+/// Returning `false` ignores that collider for the rest of the move, as if it wasn't there.
+/// This is how one-way platforms are done: ignore the platform unless its top is hit from above.
+///
+/// # Resources
+///
+/// Some useful resources for learning more about the move and slide algorithm include:
+///
+/// - [*Collide And Slide - \*Actually Decent\* Character Collision From Scratch*](https://youtu.be/YR6Q7dUz2uk) by [Poke Dev](https://www.youtube.com/@poke_gamedev) (video)
+/// - [`PM_SlideMove`](https://github.com/id-Software/Quake-III-Arena/blob/dbe4ddb10315479fc00086f08e25d968b4b43c49/code/game/bg_slidemove.c#L45) in Quake III Arena (source code)
+///
+/// Note that while the high-level concepts are similar across different implementations, details may vary.
+#[derive(SystemParam)]
+#[doc(alias = "CollideAndSlide")]
+#[doc(alias = "StepSlide")]
+pub struct MoveAndSlide<'w, 's> {
+    /// The [`SpatialQueryPipeline`] used to perform spatial queries.
+    pub query_pipeline: Res<'w, SpatialQueryPipeline>,
+    /// The [`Query`] used to query colliders. Disabled colliders are ignored.
+    pub colliders: Query<
+        'w,
+        's,
+        (
+            &'static Collider,
+            &'static Position,
+            &'static Rotation,
+            Option<&'static CollisionLayers>,
+        ),
+        Without<ColliderDisabled>,
+    >,
+    /// A units-per-meter scaling factor that adjusts some thresholds and tolerances
+    /// to the scale of the world for better behavior.
+    pub length_unit: Res<'w, PhysicsLengthUnit>,
+    /// An optional snapshot of the broadphase shared by every character moved this tick.
+    pub broadphase: Option<Res<'w, BroadphaseSnapshot>>,
+}
+
+impl<'w, 's> MoveAndSlide<'w, 's> {
+    #[must_use]
+    #[doc(alias = "collide_and_slide")]
+    #[doc(alias = "step_slide")]
+    pub fn move_and_slide(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        velocity: Vector,
+        delta_time: Duration,
+        config: &MoveAndSlideConfig,
+        filter: &SpatialQueryFilter,
+        pre_filter: impl FnMut(Entity, Dir2) -> bool,
+        on_hit: impl FnMut(MoveAndSlideHitData) -> bool,
+    ) -> MoveAndSlideOutput {
+        self.move_and_slide_inner(
+            shape,
+            shape_position,
+            shape_rotation,
+            velocity,
+            delta_time,
+            config,
+            filter,
+            None,
+            pre_filter,
+            on_hit,
+        )
+    }
+
+    /// [`MoveAndSlide::move_and_slide`], but reusing the contacts from the character's last move
+    /// where neither it nor the collider it touched has moved since, see [`ContactCache`].
+    #[must_use]
+    pub fn move_and_slide_cached(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        velocity: Vector,
+        delta_time: Duration,
+        config: &MoveAndSlideConfig,
+        filter: &SpatialQueryFilter,
+        cache: &mut ContactCache,
+        pre_filter: impl FnMut(Entity, Dir2) -> bool,
+        on_hit: impl FnMut(MoveAndSlideHitData) -> bool,
+    ) -> MoveAndSlideOutput {
+        cache.begin();
+        self.move_and_slide_inner(
+            shape,
+            shape_position,
+            shape_rotation,
+            velocity,
+            delta_time,
+            config,
+            filter,
+            Some(cache),
+            pre_filter,
+            on_hit,
+        )
+    }
+
+    /// Splits the move into substeps, see [`MoveAndSlideConfig::max_substep_distance`].
+    fn move_and_slide_inner(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        velocity: Vector,
+        delta_time: Duration,
+        config: &MoveAndSlideConfig,
+        filter: &SpatialQueryFilter,
+        mut cache: Option<&mut ContactCache>,
+        mut pre_filter: impl FnMut(Entity, Dir2) -> bool,
+        mut on_hit: impl FnMut(MoveAndSlideHitData) -> bool,
+    ) -> MoveAndSlideOutput {
+        let substeps = config.max_substep_distance.map_or(1, |relative| {
+            let size = shape.aabb(Vector::ZERO, shape_rotation).size().min_element();
+            let max_distance = relative * size;
+            let distance = velocity.length() * delta_time.as_secs_f32().adjust_precision();
+            if max_distance <= 0.0 {
+                1
+            } else {
+                ((distance / max_distance).ceil() as usize).clamp(1, config.max_substeps.max(1))
+            }
+        });
+        let step_time = delta_time / substeps as u32;
+        let mut output = self.move_and_slide_step(
+            shape,
+            shape_position,
+            shape_rotation,
+            velocity,
+            step_time,
+            config,
+            filter,
+            cache.as_deref_mut(),
+            &mut pre_filter,
+            &mut on_hit,
+        );
+        for _ in 1..substeps {
+            if output.projected_velocity == Vector::ZERO {
+                break;
+            }
+            let step = self.move_and_slide_step(
+                shape,
+                output.position,
+                shape_rotation,
+                output.projected_velocity,
+                step_time,
+                config,
+                filter,
+                cache.as_deref_mut(),
+                &mut pre_filter,
+                &mut on_hit,
+            );
+            // Where it ended up is the last step's, anything touched on the way counts.
+            output = MoveAndSlideOutput {
+                iterations: output.iterations + step.iterations,
+                planes_hit: output.planes_hit + step.planes_hit,
+                substeps: output.substeps + 1,
+                ceiling_hit: step.ceiling_hit.or(output.ceiling_hit),
+                on_floor: output.on_floor || step.on_floor,
+                on_wall: output.on_wall || step.on_wall,
+                on_ceiling: output.on_ceiling || step.on_ceiling,
+                crushed: output.crushed || step.crushed,
+                ..step
+            };
+        }
+        output
+    }
+
+    fn move_and_slide_step(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        mut velocity: Vector,
+        delta_time: Duration,
+        config: &MoveAndSlideConfig,
+        filter: &SpatialQueryFilter,
+        mut cache: Option<&mut ContactCache>,
+        mut pre_filter: impl FnMut(Entity, Dir2) -> bool,
+        mut on_hit: impl FnMut(MoveAndSlideHitData) -> bool,
+    ) -> MoveAndSlideOutput {
+        // High level overview:
+        // 1. Initial Gauss-Seidel depenetration pass
+        // 2. For each iteration, until movement is done or max iterations reached:
+        //    - Sweep the shape along the velocity vector
+        //    - If we hit something, move up to the hit point
+        //    - Collect contact planes
+        //    - Depenetrate based on intersections
+        //    - Project velocity to be parallel to all contact planes
+        let mut position = shape_position;
+        let original_velocity = velocity;
+        let mut time_left = delta_time.as_secs_f32();
+        let mut iterations = 0;
+        let mut planes_hit = 0;
+        let mut ground_normal = None;
+        let mut ceiling_hit = None;
+        let mut steep_normal: Option<Dir2> = None;
+        let mut floor_normal: Option<Dir2> = None;
+        let (mut on_floor, mut on_wall, mut on_ceiling) = (false, false, false);
+        let mut crushed = false;
+        let up = config.up();
+        // Colliders the pre-filter ignored are left out of every query after.
+        let mut filter = filter.clone();
+
+        // Initial depenetration pass
+        let mut intersections = Vec::new();
+        let mut ignored = Vec::new();
+        self.intersections_inner(
+            shape,
+            position,
+            shape_rotation,
+            config.skin_width,
+            &filter,
+            cache.as_deref_mut(),
+            config.contact_cache_tolerance * self.length_unit.0,
+            |entity, contact_point, normal| {
+                if !pre_filter(entity, normal) {
+                    ignored.push(entity);
+                    return true;
+                }
+                // TODO: Should we call on_hit here?
+                intersections.push((normal, contact_point.penetration + config.skin_width));
+                true
+            },
+        );
+        filter.excluded_entities.extend(ignored.drain(..));
+        let (depenetration_offset, resolved) =
+            self.depenetrate_inner(&config.into(), &intersections);
+        position += depenetration_offset;
+        crushed |= !resolved;
+
+        // Only a character that starts the move standing on the ground gets snapped back onto it.
+        let started_grounded = config.ground_snap.as_ref().is_some_and(|snap| {
+            self.ground_below(
+                shape,
+                position,
+                shape_rotation,
+                snap.up,
+                snap.min_up_dot,
+                config.skin_width * 2.0,
+                config,
+                &filter,
+            )
+            .is_some_and(|ground| velocity.dot(ground.normal1) <= snap.max_lift)
+        });
+        let walkable_dot = config.max_slope_angle.cos();
+        let started_on_step = config.step_down.as_ref().is_some_and(|step| {
+            self.ground_below(
+                shape,
+                position,
+                shape_rotation,
+                step.up,
+                walkable_dot,
+                config.skin_width * 2.0,
+                config,
+                &filter,
+            )
+            .is_some_and(|ground| velocity.dot(ground.normal1) <= step.max_lift)
+        });
+
+        // Main move and slide loop:
+        // 1. Sweep the shape along the velocity vector
+        // 2. If we hit something, move up to the hit point
+        // 3. Collect contact planes
+        // 4. Depenetrate based on intersections
+        // 5. Project velocity to be parallel to all contact planes
+        // 6. Repeat until we run out of iterations or time
+        'outer: for iteration in 0..config.move_and_slide_iterations {
+            iterations = iteration + 1;
+            let sweep = time_left * velocity;
+            let Some((vel_dir, distance)) = Dir2::new_and_length(sweep.f32()).ok() else {
+                // No movement left
+                break;
+            };
+            let distance = distance.adjust_precision();
+            const MIN_DISTANCE: Scalar = 1e-4;
+            if distance < MIN_DISTANCE {
+                break;
+            }
+
+            // Sweep the shape along the velocity vector.
+            let Some(sweep_hit) = self.cast_move(
+                shape,
+                position,
+                shape_rotation,
+                sweep,
+                config.skin_width,
+                &filter,
+            ) else {
+                // No collision, move the full distance.
+                position += sweep;
+                break;
+            };
+
+            // Sweep again without it, before even checking if it traps us.
+            let hit_normal = Dir2::new(sweep_hit.normal1.f32()).unwrap_or(-vel_dir);
+            if !pre_filter(sweep_hit.entity, hit_normal) {
+                filter.excluded_entities.insert(sweep_hit.entity);
+                continue;
+            }
+
+            if sweep_hit.intersects() {
+                // The entity is completely trapped in another solid.
+                velocity = Vector::ZERO;
+                crushed = true;
+                break 'outer;
+            }
+
+            // Move up to the hit point.
+            time_left -= time_left * (sweep_hit.distance / distance);
+            position += vel_dir.adjust_precision() * sweep_hit.distance;
+
+            // Bonk against ceilings instead of sliding along them, unless just clipping a corner.
+            if let CeilingMode::Bonk(bonk) = &config.ceiling {
+                let up = bonk.up.adjust_precision();
+                let speed = velocity.dot(up);
+                if speed > 0.0 && sweep_hit.normal1.dot(-up) >= bonk.min_down_dot {
+                    if let Some(nudge) = self.corner_nudge(
+                        shape,
+                        position,
+                        shape_rotation,
+                        &sweep_hit,
+                        bonk,
+                        config.skin_width,
+                        &filter,
+                    ) {
+                        position += nudge;
+                        continue;
+                    }
+                    velocity -= up * speed;
+                    ceiling_hit = Some(CeilingHitData {
+                        entity: sweep_hit.entity,
+                        point: sweep_hit.point1,
+                        normal: Dir2::new_unchecked(sweep_hit.normal1.f32()),
+                        speed,
+                    });
+                }
+            } else {
+                // Sliding along a ceiling still bumps the head, the planes below clip the speed.
+                let up = config.up();
+                let speed = velocity.dot(up.adjust_precision());
+                if speed > 0.0 && config.classify(hit_normal, up) == Surface::Ceiling {
+                    ceiling_hit = Some(CeilingHitData {
+                        entity: sweep_hit.entity,
+                        point: sweep_hit.point1,
+                        normal: hit_normal,
+                        speed,
+                    });
+                }
+            }
+
+            // Initialize velocity clipping planes with the user-defined planes.
+            // This often includes a ground plane.
+            let mut planes = config.planes.clone();
+
+            // Store penetrating contacts for depenetration.
+            let mut intersections = Vec::new();
+
+            // Collect contact planes.
+            self.intersections_inner(
+                shape,
+                position,
+                shape_rotation,
+                // Use a slightly larger skin width to ensure we catch all contacts for velocity clipping.
+                // Depenetration still uses just the normal skin width.
+                config.skin_width * 2.0,
+                &filter,
+                cache.as_deref_mut(),
+                config.contact_cache_tolerance * self.length_unit.0,
+                |entity, contact_point, mut normal| {
+                    if planes.len() >= config.max_planes {
+                        return false;
+                    }
+
+                    if !pre_filter(entity, normal) {
+                        ignored.push(entity);
+                        return true;
+                    }
+
+                    if !on_hit(MoveAndSlideHitData {
+                        entity,
+                        point: contact_point.point,
+                        normal: &mut normal,
+                        collision_distance: sweep_hit.collision_distance,
+                        distance: sweep_hit.distance,
+                        position: &mut position,
+                        velocity: &mut velocity,
+                    }) {
+                        return false;
+                    }
+
+                    // Add the contact plane for velocity clipping.
+                    planes.push(normal);
+
+                    // Store penetrating contacts for depenetration.
+                    let total_penetration = contact_point.penetration + config.skin_width;
+                    if total_penetration > 0.0 {
+                        intersections.push((normal, total_penetration));
+                    }
+
+                    true
+                },
+            );
+            filter.excluded_entities.extend(ignored.drain(..));
+
+            planes_hit += planes.len() - config.planes.len();
+            if let Some(stick) = &config.ground_stick {
+                ground_normal = stick
+                    .ground_normal(&planes)
+                    .filter(|normal| config.is_walkable(*normal, stick.up))
+                    .or(ground_normal);
+            }
+            // Sort what was hit, keeping the most upward floor and steep wall.
+            let more_upward = |current: Option<Dir2>, normal: Dir2| match current {
+                Some(current) if current.dot(*up) >= normal.dot(*up) => Some(current),
+                _ => Some(normal),
+            };
+            for &normal in &planes[config.planes.len()..] {
+                match config.classify(normal, up) {
+                    Surface::Floor => {
+                        on_floor = true;
+                        floor_normal = more_upward(floor_normal, normal);
+                    }
+                    Surface::Wall => {
+                        on_wall = true;
+                        if normal.dot(*up) > 0.0 {
+                            steep_normal = more_upward(steep_normal, normal);
+                        }
+                    }
+                    Surface::Ceiling => on_ceiling = true,
+                }
+            }
+
+            // Depenetrate based on intersections.
+            let (depenetration_offset, resolved) =
+                self.depenetrate_inner(&config.into(), &intersections);
+            position += depenetration_offset;
+            crushed |= !resolved;
+
+            // Project velocity to be parallel to all contact planes.
+            velocity = Self::project_velocity(velocity, &planes);
+
+            // If the original velocity is against the original velocity, stop dead
+            // to avoid tiny occilations in sloping corners.
+            if velocity.dot(original_velocity) <= -DOT_EPSILON {
+                velocity = Vector::ZERO;
+                break 'outer;
+            }
+        }
+
+        let mut snapped = false;
+        if let Some(snap) = config.ground_snap.as_ref().filter(|_| started_grounded)
+            && let Some(hit) = self.ground_below(
+                shape,
+                position,
+                shape_rotation,
+                snap.up,
+                snap.min_up_dot,
+                snap.max_distance * self.length_unit.0,
+                config,
+                &filter,
+            )
+        {
+            position -= snap.up.adjust_precision() * hit.distance;
+            let normal = Dir2::new_unchecked(hit.normal1.f32());
+            velocity = snap.apply(velocity, normal);
+            ground_normal = Some(normal);
+            snapped = true;
+        }
+
+        // Further down than the snap reaches, step down onto the next stair.
+        if let Some(step) = config.step_down.as_ref().filter(|_| started_on_step && !snapped)
+            && let Some(hit) = self.ground_below(
+                shape,
+                position,
+                shape_rotation,
+                step.up,
+                walkable_dot,
+                step.max_step_height * self.length_unit.0,
+                config,
+                &filter,
+            )
+        {
+            let up = step.up.adjust_precision();
+            position -= up * hit.distance;
+            velocity -= up * velocity.dot(up).min(0.0);
+            ground_normal = Some(Dir2::new_unchecked(hit.normal1.f32()));
+        }
+
+        if let Some(stick) = &config.ground_stick {
+            ground_normal = ground_normal.or_else(|| {
+                stick
+                    .ground_normal(&config.planes)
+                    .filter(|normal| config.is_walkable(*normal, stick.up))
+            });
+            if let Some(normal) = ground_normal {
+                velocity = stick.apply(velocity, normal);
+            }
+        }
+
+        // Too steep to stand on or walk up, slide down it at no more than the slide speed.
+        // Jumping at it is left alone.
+        if ground_normal.is_none()
+            && let Some(normal) = steep_normal
+            && original_velocity.dot(up.adjust_precision()) <= 0.0
+        {
+            let normal = normal.adjust_precision();
+            let down = -up.adjust_precision();
+            let downhill = (down - down.dot(normal) * normal).normalize_or_zero();
+            let speed = velocity.dot(downhill).max(0.0);
+            velocity = downhill * speed.min(config.steep_slide_speed * self.length_unit.0);
+        }
+
+        let ground_normal = ground_normal.or(floor_normal);
+        MoveAndSlideOutput {
+            position,
+            projected_velocity: velocity,
+            iterations,
+            planes_hit,
+            substeps: 1,
+            ground_normal,
+            ceiling_hit,
+            on_floor: on_floor || ground_normal.is_some(),
+            on_wall,
+            on_ceiling: on_ceiling || ceiling_hit.is_some(),
+            crushed,
+        }
+    }
+
+    /// The ground within `distance` below the shape along `-up`, if there's any that counts as
+    /// ground: walkable and facing up at least `min_up_dot`.
+    fn ground_below(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        up: Dir2,
+        min_up_dot: Scalar,
+        distance: Scalar,
+        config: &MoveAndSlideConfig,
+        filter: &SpatialQueryFilter,
+    ) -> Option<MoveHitData> {
+        let hit = self.cast_move(
+            shape,
+            shape_position,
+            shape_rotation,
+            -up.adjust_precision() * distance,
+            config.skin_width,
+            filter,
+        )?;
+        let normal = Dir2::new(hit.normal1.f32()).ok()?;
+        (!hit.intersects()
+            && hit.normal1.dot(up.adjust_precision()) >= min_up_dot
+            && config.is_walkable(normal, up))
+        .then_some(hit)
+    }
+
+    /// How far to move the shape sideways so that it clears the ceiling corner it hit, if that's
+    /// within [`CeilingBonk::corner_nudge`] and nothing is in the way.
+    fn corner_nudge(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        hit: &MoveHitData,
+        bonk: &CeilingBonk,
+        skin_width: Scalar,
+        filter: &SpatialQueryFilter,
+    ) -> Option<Vector> {
+        if bonk.corner_nudge <= 0.0 {
+            return None;
+        }
+        let up = bonk.up.adjust_precision();
+        let side = Vector::new(up.y, -up.x);
+        let aabb = shape.aabb(shape_position, shape_rotation);
+        let half_width = ((aabb.max - aabb.min) / 2.0).dot(side).abs();
+        let offset = (hit.point1 - shape_position).dot(side);
+        // Move away from the side the corner is on until the edge of the shape is past it.
+        let direction = if offset > 0.0 { -side } else { side };
+        let distance = half_width - offset.abs() + skin_width * 2.0;
+        if distance > bonk.corner_nudge * self.length_unit.0 {
+            return None;
+        }
+        let nudge = direction * distance;
+        if self
+            .cast_move(shape, shape_position, shape_rotation, nudge, skin_width, filter)
+            .is_some()
+        {
+            return None;
+        }
+        self.cast_move(
+            shape,
+            shape_position + nudge,
+            shape_rotation,
+            up * skin_width * 4.0,
+            skin_width,
+            filter,
+        )
+        .is_none()
+        .then_some(nudge)
+    }
+
+    #[must_use]
+    #[doc(alias = "sweep")]
+    pub fn cast_move(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        movement: Vector,
+        skin_width: Scalar,
+        filter: &SpatialQueryFilter,
+    ) -> Option<MoveHitData> {
+        let (direction, distance) = Dir2::new_and_length(movement.f32()).unwrap_or((Dir2::X, 0.0));
+        let distance = distance.adjust_precision();
+        let shape_hit = self.query_pipeline.cast_shape(
+            shape,
+            shape_position,
+            shape_rotation,
+            direction,
+            &ShapeCastConfig::from_max_distance(distance),
+            filter,
+        )?;
+        let safe_distance = if distance == 0.0 {
+            0.0
+        } else {
+            Self::pull_back(shape_hit, direction, skin_width)
+        };
+        Some(MoveHitData {
+            distance: safe_distance,
+            collision_distance: distance,
+            entity: shape_hit.entity,
+            point1: shape_hit.point1,
+            point2: shape_hit.point2,
+            normal1: shape_hit.normal1,
+            normal2: shape_hit.normal2,
+        })
+    }
+
+    /// Returns a [`ShapeHitData::distance`] that is reduced such that the hit distance is at least `skin_width`.
+    /// The result will never be negative, so if the hit is already closer than `skin_width`, the returned distance will be zero.
+    #[must_use]
+    fn pull_back(hit: ShapeHitData, dir: Dir2, skin_width: Scalar) -> Scalar {
+        let dot = dir.adjust_precision().dot(-hit.normal1).max(DOT_EPSILON);
+        let skin_distance = skin_width / dot;
+        (hit.distance - skin_distance).max(0.0)
+    }
+    #[allow(unused)]
+    pub fn depenetrate_all(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        config: &DepenetrationConfig,
+        filter: &SpatialQueryFilter,
+    ) -> Vector {
+        let mut intersections = Vec::new();
+        self.intersections(
+            shape,
+            shape_position,
+            shape_rotation,
+            config.skin_width,
+            filter,
+            |contact_point, normal| {
+                intersections.push((normal, contact_point.penetration + config.skin_width));
+                true
+            },
+        );
+        self.depenetrate(config, &intersections)
+    }
+
+    /// An [intersection test](spatial_query#intersection-tests) that calls a callback for each [`Collider`] found
+    /// that is closer to the given `shape` with a given position and rotation than `prediction_distance`.
+    ///
+    /// # Arguments
+    ///
+    /// - `shape`: The shape that intersections are tested against represented as a [`Collider`].
+    /// - `shape_position`: The position of the shape.
+    /// - `shape_rotation`: The rotation of the shape.
+    /// - `filter`: A [`SpatialQueryFilter`] that determines which colliders are taken into account in the query.
+    /// - `prediction_distance`: An extra margin applied to the [`Collider`].
+    /// - `callback`: A callback that is called for each intersection found. The callback receives the deepest contact point and the contact normal.
+    ///
+    /// # Example
+    ///
+    /// See [`MoveAndSlide::depenetrate`] for a typical usage scenario.
+    ///
+    /// # Related methods
+    ///
+    /// - [`MoveAndSlide::depenetrate`]
+    /// - [`MoveAndSlide::depenetrate_all`]
+    pub fn intersections(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        prediction_distance: Scalar,
+        filter: &SpatialQueryFilter,
+        mut callback: impl FnMut(&ContactPoint, Dir2) -> bool,
+    ) {
+        self.intersections_inner(
+            shape,
+            shape_position,
+            shape_rotation,
+            prediction_distance,
+            filter,
+            None,
+            0.0,
+            |_, contact_point, normal| callback(contact_point, normal),
+        );
+    }
+
+    /// Whether the given `shape` would be clear of every [`Collider`] passing `filter` at a given
+    /// position and rotation. Touching counts as clear, only overlapping doesn't.
+    ///
+    /// Stops at the first overlap found. Check this before teleporting a character somewhere,
+    /// like a respawn, a warp or standing back up from a crouch.
+    ///
+    /// # Arguments
+    ///
+    /// - `shape`: The shape being tested represented as a [`Collider`].
+    /// - `shape_position`: The position the shape would be at.
+    /// - `shape_rotation`: The rotation the shape would have.
+    /// - `filter`: A [`SpatialQueryFilter`] that determines which colliders are taken into account in the query.
+    ///   Usually excludes the character itself.
+    ///
+    /// # Related methods
+    ///
+    /// - [`MoveAndSlide::intersections`]
+    pub fn fits_at(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        filter: &SpatialQueryFilter,
+    ) -> bool {
+        let mut blocked = false;
+        self.intersections(shape, shape_position, shape_rotation, 0.0, filter, |contact, _| {
+            blocked = contact.penetration > 0.0;
+            !blocked
+        });
+        !blocked
+    }
+
+    fn intersections_inner(
+        &self,
+        shape: &Collider,
+        shape_position: Vector,
+        shape_rotation: Scalar,
+        prediction_distance: Scalar,
+        filter: &SpatialQueryFilter,
+        mut cache: Option<&mut ContactCache>,
+        cache_tolerance: Scalar,
+        mut callback: impl FnMut(Entity, &ContactPoint, Dir2) -> bool,
+    ) {
+        let expanded_aabb = shape
+            .aabb(shape_position, shape_rotation)
+            .grow(Vector::splat(prediction_distance));
+        let aabb_intersections = match &self.broadphase {
+            Some(snapshot) if snapshot.covers(expanded_aabb) => {
+                snapshot.aabb_intersections_with_aabb(expanded_aabb)
+            }
+            _ => self
+                .query_pipeline
+                .aabb_intersections_with_aabb(expanded_aabb),
+        };
+        for intersection_entity in aabb_intersections {
+            let Ok((intersection_collider, intersection_pos, intersection_rot, layers)) =
+                self.colliders.get(intersection_entity)
+            else {
+                continue;
+            };
+            let layers = layers.copied().unwrap_or_default();
+            if !filter.test(intersection_entity, layers) {
+                continue;
+            }
+            let key = ContactKey {
+                entity: intersection_entity,
+                position: *intersection_pos,
+                rotation: *intersection_rot,
+                shape_rotation,
+                prediction_distance,
+            };
+            if let Some(cache) = cache.as_deref_mut()
+                && let Some(pair) = cache.get(&key, shape_position, cache_tolerance)
+            {
+                // The character barely moved, shift the old contacts by however much it did.
+                let offset = shape_position - pair.shape_position;
+                for (contact, normal) in &pair.contacts {
+                    let mut contact = *contact;
+                    contact.penetration -= offset.dot(normal.adjust_precision());
+                    if !callback(intersection_entity, &contact, *normal) {
+                        return;
+                    }
+                }
+                continue;
+            }
+            let mut manifolds = Vec::new();
+            contact_manifolds(
+                shape,
+                shape_position,
+                shape_rotation,
+                intersection_collider,
+                *intersection_pos,
+                *intersection_rot,
+                prediction_distance,
+                &mut manifolds,
+            );
+            let mut contacts = Vec::new();
+            for manifold in manifolds {
+                let Some(deepest) = manifold.find_deepest_contact() else {
+                    continue;
+                };
+
+                let normal = Dir2::new_unchecked(-manifold.normal.f32());
+                contacts.push((*deepest, normal));
+            }
+            // Every contact is cached even if the callback stops early, the next move may want them.
+            let stopped = contacts
+                .iter()
+                .any(|(contact, normal)| !callback(intersection_entity, contact, *normal));
+            if let Some(cache) = cache.as_deref_mut() {
+                cache.insert(key, shape_position, contacts);
+            }
+            if stopped {
+                return;
+            }
+        }
+    }
+
+    /// Manual version of [`MoveAndSlide::depenetrate_all`].
+    ///
+    /// Moves a collider so that it no longer intersects any other collider and keeps a minimum distance of [`DepenetrationConfig::skin_width`] to all.
+    /// The intersections should be provided as a list of contact plane normals and penetration distances, which can be obtained via [`MoveAndSlide::intersections`].
+    ///
+    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit, until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`]
+    /// or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`]. If the max iterations were reached before the error was below the threshold,
+    /// the current best attempt is returned, in which case the collider may still be intersecting with other colliders.
+    ///
+    /// # Arguments
+    ///
+    /// - `config`: A [`DepenetrationConfig`] that determines the behavior of the depenetration. [`DepenetrationConfig::default()`] should be a good start for most cases.
+    ///
+    /// # Returns
+    ///
+    /// A displacement vector that can be added to the `shape_position` to resolve the intersections,
+    /// or the best attempt if the max iterations were reached before a solution was found.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use bevy::prelude::*;
+    /// fn depenetrate_player_manually(
+    ///     player: Single<(Entity, &Collider, &mut Transform)>,
+    ///     move_and_slide: MoveAndSlide,
+    ///     time: Res<Time>
+    /// ) {
+    ///     let (entity, collider, mut transform) = player.into_inner();
+    ///     let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+    ///     let config = DepenetrationConfig::default();
+    ///
+    ///     let mut intersections = Vec::new();
+    ///     move_and_slide.intersections(
+    ///         collider,
+    #[must_use]
+    pub fn depenetrate(
+        &self,
+        config: &DepenetrationConfig,
+        intersections: &[(Dir2, Scalar)],
+    ) -> Vector {
+        self.depenetrate_inner(config, intersections).0
+    }
+
+    /// [`MoveAndSlide::depenetrate`], also telling whether the error got below
+    /// [`DepenetrationConfig::max_depenetration_error`] before running out of iterations.
+    fn depenetrate_inner(
+        &self,
+        config: &DepenetrationConfig,
+        intersections: &[(Dir2, Scalar)],
+    ) -> (Vector, bool) {
+        if intersections.is_empty() {
+            return (Vector::ZERO, true);
+        }
+
+        let mut fixup = Vector::ZERO;
+        for _ in 0..config.depenetration_iterations {
+            let mut total_error = 0.0;
+            for (normal, dist) in intersections {
+                if *dist > self.length_unit.0 * config.penetration_rejection_threshold {
+                    continue;
+                }
+                let normal = normal.adjust_precision();
+                let error = (dist - fixup.dot(normal)).max(0.0);
+                total_error += error;
+                fixup += error * normal;
+            }
+            if total_error < self.length_unit.0 * config.max_depenetration_error {
+                return (fixup, true);
+            }
+        }
+        (fixup, false)
+    }
+
+    /// Projects input velocity `v` onto the convex cone defined by the provided contact `normals`.
+    /// This ensures that `velocity` does not point into any of the given `planes`, but along them.
+    ///
+    /// Returns the projected velocity. If there are no planes, the velocity is returned unchanged.
+    /// The returned vector will have some numerical errors. For example, if your vertical velocity was 0.0 before calling
+    /// this method on a ground plane intersection, the returned velocity might point very slightly upwards.
+    /// As such, it is recommended to set invariants such as `velocity.y = 0.0;` again after calling this method,
+    /// which [`MoveAndSlideConfig::ground_stick`] does for [`MoveAndSlide::move_and_slide`].
+    ///
+    /// Often used after [`MoveAndSlide::cast_move`] to ensure a character moved that way does not try to continue moving into colliding geometry.
+    /// See that method for example usage.
+    pub fn project_velocity(v: Vector, normals: &[Dir2]) -> Vector {
+        // Case 1: Check if v is inside the cone
+        if normals
+            .iter()
+            .all(|n| v.dot(n.adjust_precision()) >= -DOT_EPSILON)
+        {
+            return v;
+        }
+
+        // Best candidate so far
+        let mut best_projection = Vector::ZERO;
+        let mut best_distance_sq = Scalar::INFINITY;
+
+        // Helper to test halfspace validity
+        let is_valid = |projection: Vector| {
+            normals
+                .iter()
+                .all(|n| projection.dot(n.adjust_precision()) >= -DOT_EPSILON)
+        };
+
+        // Case 2a: Face projections (single-plane active set)
+        for n in normals {
+            let n = n.adjust_precision();
+            let v_dot_n = v.dot(n);
+            if v_dot_n < 0.0 {
+                // Project v onto the plane defined by n:
+                // projection = v - (v·n) n
+                let projection = v - v_dot_n * n;
+
+                // Check if better than previous best and valid
+                let distance_sq = v.distance_squared(projection);
+                if distance_sq < best_distance_sq && is_valid(projection) {
+                    best_distance_sq = distance_sq;
+                    best_projection = projection;
+                }
+            }
+        }
+
+        // Case 3: If no candidate is found, the projection is at the apex (the origin)
+        if best_distance_sq.is_infinite() {
+            Vector::ZERO
+        } else {
+            best_projection
+        }
+    }
+}
+
+/// Needed to not accidentally explode when `n.dot(dir)` happens to be very close to zero.
+const DOT_EPSILON: Scalar = 0.005;
+
+/// Data related to a hit during a [`MoveAndSlide::move_and_slide`].
+#[derive(Debug, PartialEq)]
+pub struct MoveAndSlideHitData<'a> {
+    /// The entity of the collider that was hit by the shape.
+    pub entity: Entity,
+
+    /// The maximum distance that is safe to move in the given direction so that the collider still keeps a distance of `skin_width` to the other colliders.
+    /// Is 0.0 when
+    /// - The collider started off intersecting another collider.
+    /// - The collider is moving toward another collider that is already closer than `skin_width`.
+    ///
+    /// If you want to know the real distance to the next collision, use [`Self::collision_distance`].
+    pub distance: Scalar,
+
+    /// The hit point point on the shape that was hit, expressed in world space.
+    pub point: Vector,
+
+    /// The outward surface normal on the hit shape at `point`, expressed in world space.
+    pub normal: &'a mut Dir2,
+
+    /// The position of the collider at the point of the move and slide iteration.
+    pub position: &'a mut Vector,
+
+    /// The velocity of the collider at the point of the move and slide iteration.
+    pub velocity: &'a mut Vector,
+
+    /// The raw distance to the next collision, not respecting skin width.
+    /// To move the shape, use [`Self::distance`] instead.
+    #[doc(alias = "time_of_impact")]
+    pub collision_distance: Scalar,
+}
+
+impl<'a> MoveAndSlideHitData<'a> {
+    /// Whether the collider started off already intersecting another collider when it was cast.
+    /// Note that this will be `false` if the collider was closer than `skin_width`, but not physically intersecting.
+    pub fn intersects(&self) -> bool {
+        self.collision_distance == 0.0
+    }
+}
+
+/// Data related to a hit during a [`MoveAndSlide::cast_move`].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Serialize, Deserialize)]
+#[reflect(Debug, PartialEq)]
+pub struct MoveHitData {
+    /// The entity of the collider that was hit by the shape.
+    pub entity: Entity,
+
+    /// The maximum distance that is safe to move in the given direction so that the collider still keeps a distance of `skin_width` to the other colliders.
+    /// Is 0.0 when
+    /// - The collider started off intersecting another collider.
+    /// - The collider is moving toward another collider that is already closer than `skin_width`.
+    ///
+    /// If you want to know the real distance to the next collision, use [`Self::collision_distance`].
+    #[doc(alias = "time_of_impact")]
+    pub distance: Scalar,
+
+    /// The closest point on the shape that was hit, expressed in world space.
+    ///
+    /// If the shapes are penetrating or the target distance is greater than zero,
+    /// this will be different from `point2`.
+    pub point1: Vector,
+
+    /// The closest point on the shape that was cast, expressed in world space.
+    ///
+    /// If the shapes are penetrating or the target distance is greater than zero,
+    /// this will be different from `point1`.
+    pub point2: Vector,
+
+    /// The outward surface normal on the hit shape at `point1`, expressed in world space.
+    pub normal1: Vector,
+
+    /// The outward surface normal on the cast shape at `point2`, expressed in world space.
+    pub normal2: Vector,
+
+    /// The raw distance to the next collision, not respecting skin width.
+    /// To move the shape, use [`Self::distance`] instead.
+    #[doc(alias = "time_of_impact")]
+    pub collision_distance: Scalar,
+}
+
+impl MoveHitData {
+    /// Whether the collider started off already intersecting another collider when it was cast.
+    /// Note that this will be `false` if the collider was closer than `skin_width`, but not physically intersecting.
+    pub fn intersects(self) -> bool {
+        self.collision_distance == 0.0
+    }
+}
+
+/// Configuration for a [`MoveAndSlide::move_and_slide`].
+///
+/// Missing fields are filled in from [`MoveAndSlideConfig::default`] when deserializing.
+#[derive(Clone, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoveAndSlideConfig {
+    /// How many iterations to use when moving the character. A single iteration consists of
+    /// - Performing depenetration
+    /// - Moving the character as far as possible in the desired velocity
+    /// - Modifying the velocity to slide along any colliding planes
+    pub move_and_slide_iterations: usize,
+
+    /// How many iterations to use when performing depenetration.
+    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
+    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub depenetration_iterations: usize,
+
+    /// The target error to achieve when performing depenetration.
+    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
+    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub max_depenetration_error: Scalar,
+
+    /// The maximum penetration depth that is allowed for a contact to be resolved during depenetration.
+    ///
+    /// This is used to reject invalid contacts that have an excessively high penetration depth,
+    /// which can lead to clipping through geometry. This may be removed in the future once the
+    /// collision errors in the underlying collision detection system are fixed.
+    pub penetration_rejection_threshold: Scalar,
+
+    /// A minimal distance to always keep between the collider and any other colliders.
+    /// This is here to ensure that the collider never intersects anything, even when numeric errors accumulate.
+    /// Set this to a very small value.
+    ///
+    /// Increase the value if you notice your character getting stuck in geometry.
+    /// Decrease it when you notice jittering, especially around V-shaped walls.
+    pub skin_width: Scalar,
+
+    /// The initial planes to consider for a move-and-slide operation. This will be expanded during the algorithm with
+    /// the colliding planes, but you can also initialize it with some planes you want to make sure the algorithm will never move against.
+    ///
+    /// A good use-case for this is adding the ground plane when a character controller is standing or walking on the ground.
+    pub planes: Vec<Dir2>,
+
+    /// The maximum number of planes to solve while performing move-and-slide. If the collided planes exceed this number, the move is aborted and the velocity is set to zero.
+    /// Realistically, this will probably never be reached, unless you have very exotic geometry and very high velocity.
+    pub max_planes: usize,
+
+    /// How far the character may move from where a contact was computed before
+    /// [`MoveAndSlide::move_and_slide_cached`] computes it again instead of reusing it.
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub contact_cache_tolerance: Scalar,
+
+    /// Keeps the character glued to the ground it slid along, see [`GroundStick`].
+    /// `None` leaves the projected velocity as is.
+    pub ground_stick: Option<GroundStick>,
+
+    /// Pulls the character back down onto the ground after walking over a crest or off a small
+    /// step, see [`GroundSnap`]. `None` lets it fly off.
+    pub ground_snap: Option<GroundSnap>,
+
+    /// Moves the character down onto the next stair after walking off one, see [`StepDown`].
+    /// `None` lets it drop down each step on its own.
+    pub step_down: Option<StepDown>,
+
+    /// What happens when moving up into a ceiling.
+    pub ceiling: CeilingMode,
+
+    /// Which way is up for slopes and for telling floors, walls and ceilings apart.
+    /// Use [`MoveAndSlideConfig::with_up`] to turn the whole config at once.
+    pub up: Dir2,
+
+    /// The steepest slope in radians that can be stood on. Anything steeper is treated as a wall
+    /// for grounding, and a character on it slides down instead of sticking to it.
+    ///
+    /// Defaults to 90°, so every surface facing up at all can be stood on and nothing slides.
+    pub max_slope_angle: Scalar,
+
+    /// The fastest a character slides down a slope steeper than [`MoveAndSlideConfig::max_slope_angle`].
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub steep_slide_speed: Scalar,
+
+    /// The longest a single step of a move may be, relative to the smallest side of the shape's
+    /// bounding box. Longer moves are split into equal steps, each with all of
+    /// [`MoveAndSlideConfig::move_and_slide_iterations`], so very fast or very small characters
+    /// don't tunnel or use up every iteration on one long sweep. `None` always moves in one step.
+    pub max_substep_distance: Option<Scalar>,
+
+    /// The most steps a move is split into, see [`MoveAndSlideConfig::max_substep_distance`].
+    pub max_substeps: usize,
+}
+
+impl MoveAndSlideConfig {
+    /// Which way is up for slopes.
+    pub fn up(&self) -> Dir2 {
+        self.up
+    }
+
+    /// The same config with [`MoveAndSlideConfig::up`] and the up of the ground stick, ground snap
+    /// and ceiling bonk all pointing at `up`, for characters whose gravity is turned.
+    pub fn with_up(mut self, up: Dir2) -> Self {
+        self.up = up;
+        if let Some(stick) = &mut self.ground_stick {
+            stick.up = up;
+        }
+        if let Some(snap) = &mut self.ground_snap {
+            snap.up = up;
+        }
+        if let Some(step) = &mut self.step_down {
+            step.up = up;
+        }
+        if let CeilingMode::Bonk(bonk) = &mut self.ceiling {
+            bonk.up = up;
+        }
+        self
+    }
+
+    /// Whether a surface with this normal is flat enough to stand on, see [`MoveAndSlideConfig::max_slope_angle`].
+    pub fn is_walkable(&self, normal: Dir2, up: Dir2) -> bool {
+        let dot = normal.dot(*up).adjust_precision();
+        // Sideways is a wall even at 90°, where the cosine rounds to just below zero.
+        dot > 0.0 && dot >= self.max_slope_angle.cos()
+    }
+
+    /// What kind of surface a plane with this normal is. Ceilings are as steep as floors, upside down.
+    pub fn classify(&self, normal: Dir2, up: Dir2) -> Surface {
+        if self.is_walkable(normal, up) {
+            Surface::Floor
+        } else if self.is_walkable(normal, -up) {
+            Surface::Ceiling
+        } else {
+            Surface::Wall
+        }
+    }
+}
+
+/// A surface the character touched, see [`MoveAndSlideConfig::classify`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Reflect)]
+pub enum Surface {
+    Floor,
+    /// Also slopes too steep to stand on.
+    Wall,
+    Ceiling,
+}
+
+/// How a character moving up reacts to hitting a ceiling.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+pub enum CeilingMode {
+    /// Keep sliding along it like along any other plane. The hit is still reported in
+    /// [`MoveAndSlideOutput::ceiling_hit`].
+    #[default]
+    Slide,
+    /// Lose all upward speed at once and report it in [`MoveAndSlideOutput::ceiling_hit`].
+    Bonk(CeilingBonk),
+}
+
+/// Settings for [`CeilingMode::Bonk`].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CeilingBonk {
+    /// Which way is up for this character.
+    pub up: Dir2,
+    /// The smallest dot product of a plane normal with down that still counts as ceiling.
+    pub min_down_dot: Scalar,
+    /// How far the character may be pushed sideways to slip past the corner of a ceiling
+    /// instead of bonking on it. `0.0` disables the nudge.
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub corner_nudge: Scalar,
+}
+
+impl Default for CeilingBonk {
+    fn default() -> Self {
+        Self {
+            up: Dir2::Y,
+            min_down_dot: 0.7,
+            corner_nudge: 0.0,
+        }
+    }
+}
+
+/// Cleans up the velocity of a character that ends a move on the ground.
+///
+/// [`MoveAndSlide::project_velocity`] leaves some numerical error behind, so a character walking
+/// along the ground often ends up with a tiny velocity off it. With this set on the config,
+/// any velocity into the ground plane or off it slower than [`GroundStick::max_lift`] is removed.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroundStick {
+    /// Which way is up for this character.
+    pub up: Dir2,
+    /// The smallest dot product of a plane normal with `up` that still counts as ground.
+    pub min_up_dot: Scalar,
+    /// Speeds off the ground below this are treated as numerical error, anything faster is a jump.
+    pub max_lift: Scalar,
+}
+
+impl Default for GroundStick {
+    fn default() -> Self {
+        Self {
+            up: Dir2::Y,
+            min_up_dot: 0.7,
+            max_lift: 0.01,
+        }
+    }
+}
+
+impl GroundStick {
+    /// The most upward facing of `planes` that counts as ground.
+    pub fn ground_normal(&self, planes: &[Dir2]) -> Option<Dir2> {
+        planes
+            .iter()
+            .copied()
+            .filter(|normal| normal.dot(*self.up).adjust_precision() >= self.min_up_dot)
+            .max_by(|a, b| a.dot(*self.up).total_cmp(&b.dot(*self.up)))
+    }
+
+    /// Removes the part of `velocity` into or barely off the ground with the given normal.
+    pub fn apply(&self, velocity: Vector, ground_normal: Dir2) -> Vector {
+        let normal = ground_normal.adjust_precision();
+        let along = velocity.dot(normal);
+        if along > self.max_lift {
+            return velocity;
+        }
+        velocity - along * normal
+    }
+}
+
+/// Keeps a character that was standing on the ground at the start of a move from launching
+/// off the crest of a slope or a small step down.
+///
+/// After the move the shape is cast down up to [`GroundSnap::max_distance`]. If that finds ground,
+/// the character is moved down onto it and its velocity is turned to run along it, keeping its speed.
+/// Nothing happens when the move starts off the ground faster than [`GroundSnap::max_lift`], so jumps still work.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GroundSnap {
+    /// Which way is up for this character.
+    pub up: Dir2,
+    /// How far down the ground may be to still snap onto it.
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub max_distance: Scalar,
+    /// The smallest dot product of a plane normal with `up` that still counts as ground.
+    pub min_up_dot: Scalar,
+    /// Starting speeds off the ground above this are a jump and never snapped.
+    pub max_lift: Scalar,
+}
+
+impl Default for GroundSnap {
+    fn default() -> Self {
+        Self {
+            up: Dir2::Y,
+            max_distance: 0.4,
+            min_up_dot: 0.7,
+            max_lift: 0.01,
+        }
+    }
+}
+
+impl GroundSnap {
+    /// Turns `velocity` to run along the ground with the given normal, at the same speed.
+    pub fn apply(&self, velocity: Vector, ground_normal: Dir2) -> Vector {
+        let normal = ground_normal.adjust_precision();
+        let along = velocity - velocity.dot(normal) * normal;
+        along.normalize_or_zero() * velocity.length()
+    }
+}
+
+/// Keeps a character that was standing on the ground at the start of a move on it when walking
+/// down stairs or off a ledge, so running down a tile staircase doesn't hop from step to step.
+///
+/// After the move, if [`GroundSnap`] didn't find ground, the shape is cast down up to
+/// [`StepDown::max_step_height`] and moved onto whatever it can stand on there. Unlike the snap
+/// the velocity keeps its direction, only speed down into the ground is dropped.
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StepDown {
+    /// Which way is up for this character.
+    pub up: Dir2,
+    /// The highest step to move down.
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub max_step_height: Scalar,
+    /// Starting speeds off the ground above this are a jump and never stepped down.
+    pub max_lift: Scalar,
+}
+
+impl Default for StepDown {
+    fn default() -> Self {
+        Self {
+            up: Dir2::Y,
+            max_step_height: 1.6,
+            max_lift: 0.01,
+        }
+    }
+}
+
+/// Configuration for a [`MoveAndSlide::depenetrate`].
+#[derive(Clone, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DepenetrationConfig {
+    /// How many iterations to use when performing depenetration.
+    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
+    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
+    pub depenetration_iterations: usize,
+
+    /// The target error to achieve when performing depenetration.
+    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
+    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub max_depenetration_error: Scalar,
+
+    /// The maximum penetration depth that is allowed for a contact to be resolved during depenetration.
+    ///
+    /// This is used to reject invalid contacts that have an excessively high penetration depth,
+    /// which can lead to clipping through geometry. This may be removed in the future once the
+    /// collision errors in the underlying collision detection system are fixed.
+    ///
+    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
+    pub penetration_rejection_threshold: Scalar,
+
+    /// A minimal distance to always keep between the collider and any other colliders.
+    /// This is here to ensure that the collider never intersects anything, even when numeric errors accumulate.
+    /// Set this to a very small value.
+    ///
+    /// Increase the value if you notice your character getting stuck in geometry.
+    /// Decrease it when you notice jittering, especially around V-shaped walls.
+    pub skin_width: Scalar,
+}
+
+impl Default for DepenetrationConfig {
+    fn default() -> Self {
+        Self {
+            depenetration_iterations: 16,
+            max_depenetration_error: 0.0001,
+            penetration_rejection_threshold: 0.5,
+            skin_width: 0.002,
+        }
+    }
+}
+
+impl From<&MoveAndSlideConfig> for DepenetrationConfig {
+    fn from(config: &MoveAndSlideConfig) -> Self {
+        Self {
+            depenetration_iterations: config.depenetration_iterations,
+            max_depenetration_error: config.max_depenetration_error,
+            penetration_rejection_threshold: config.penetration_rejection_threshold,
+            skin_width: config.skin_width,
+        }
+    }
+}
+
+/// Output from a [`MoveAndSlide::move_and_slide`].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+pub struct MoveAndSlideOutput {
+    /// The final position of the character after move and slide. Set your [`Transform::translation`] to this value.
+    pub position: Vector,
+
+    /// The final velocity of the character after move and slide. This corresponds to the actual velocity, not the wished velocity.
+    /// For example, if the character is trying to move to the right and there's a ramp on its path, this vector will point up the ramp.
+    /// It is useful to store this value and apply your wish movement vectors, friction, gravity, etc. on it before handing it to [`MoveAndSlide::move_and_slide`] as the input `velocity`.
+    /// You can also ignore this value if you don't wish to preserve momentum along colliding planes.
+    ///
+    /// Do *not* set [`LinearVelocity`] to this value, as that would apply the movement twice and cause intersections. Instead, set [`Transform::translation`] to [`MoveAndSlideOutput::position`].
+    pub projected_velocity: Vector,
+
+    /// How many move and slide iterations were needed, at most [`MoveAndSlideConfig::move_and_slide_iterations`]
+    /// for each substep.
+    pub iterations: usize,
+
+    /// How many steps the move was split into, see [`MoveAndSlideConfig::max_substep_distance`].
+    pub substeps: usize,
+
+    /// How many contact planes were hit over all iterations, not counting [`MoveAndSlideConfig::planes`].
+    pub planes_hit: usize,
+
+    /// The ground the character ended up standing on: the most upward floor it touched, or where
+    /// [`MoveAndSlideConfig::ground_snap`] snapped it down to.
+    pub ground_normal: Option<Dir2>,
+
+    /// The ceiling the character hit its head on while moving up.
+    pub ceiling_hit: Option<CeilingHitData>,
+
+    /// Whether a floor was touched during the move, see [`MoveAndSlideConfig::classify`].
+    pub on_floor: bool,
+
+    /// Whether a wall or a slope too steep to stand on was touched during the move.
+    pub on_wall: bool,
+
+    /// Whether a ceiling was touched during the move.
+    pub on_ceiling: bool,
+
+    /// Whether the character got trapped between opposing planes, either starting the sweep
+    /// inside a solid or with depenetration running out of iterations before pushing it free.
+    /// Typically it's being squeezed by something moving into it, like a platform against a wall.
+    pub crushed: bool,
+}
+
+/// A ceiling that stopped a character moving up, see [`CeilingMode::Bonk`].
+#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
+#[reflect(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CeilingHitData {
+    /// The entity of the collider that was hit.
+    pub entity: Entity,
+    /// Where it was hit, in world space.
+    pub point: Vector,
+    /// The surface normal of the ceiling, pointing down at the character.
+    pub normal: Dir2,
+    /// The upward speed the character had going into it, all of it lost with [`CeilingMode::Bonk`].
+    pub speed: Scalar,
+}
+
+impl Default for MoveAndSlideConfig {
+    fn default() -> Self {
+        let default_depen_cfg = DepenetrationConfig::default();
+        Self {
+            move_and_slide_iterations: 4,
+            depenetration_iterations: default_depen_cfg.depenetration_iterations,
+            max_depenetration_error: default_depen_cfg.max_depenetration_error,
+            penetration_rejection_threshold: default_depen_cfg.penetration_rejection_threshold,
+            skin_width: default_depen_cfg.skin_width * 5.0,
+            planes: Vec::new(),
+            max_planes: 20,
+            contact_cache_tolerance: 0.005,
+            ground_stick: None,
+            ground_snap: None,
+            step_down: None,
+            ceiling: CeilingMode::Slide,
+            up: Dir2::Y,
+            max_slope_angle: Scalar::to_radians(90.0),
+            steep_slide_speed: 3.0,
+            max_substep_distance: None,
+            max_substeps: 8,
+        }
+    }
+}
+
+/// Everything a cached contact depends on besides the position of the character.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct ContactKey {
+    entity: Entity,
+    position: Position,
+    rotation: Rotation,
+    shape_rotation: Scalar,
+    prediction_distance: Scalar,
+}
+
+#[derive(Clone, Debug)]
+struct CachedContacts {
+    key: ContactKey,
+    /// where the character was when the contacts were computed
+    shape_position: Vector,
+    contacts: Vec<(ContactPoint, Dir2)>,
+    /// still wanted by the last move
+    fresh: bool,
+}
+
+/// The contacts of one character with each collider it was close to, kept between moves by
+/// [`MoveAndSlide::move_and_slide_cached`].
+///
+/// A contact is reused as long as the other collider hasn't moved at all and the character has
+/// moved less than [`MoveAndSlideConfig::contact_cache_tolerance`], which saves the
+/// [`contact_manifolds`] calls of characters standing still against walls.
+#[derive(Component, Clone, Debug, Default)]
+pub struct ContactCache {
+    pairs: Vec<CachedContacts>,
+    /// Contacts reused in the last move.
+    pub hits: usize,
+    /// Contacts computed in the last move.
+    pub misses: usize,
+}
+
+impl ContactCache {
+    /// Forgets everything, for when the character was teleported.
+    pub fn clear(&mut self) {
+        self.pairs.clear();
+    }
+
+    /// Drops everything the last move didn't touch and resets the counters.
+    fn begin(&mut self) {
+        self.pairs.retain(|pair| pair.fresh);
+        for pair in &mut self.pairs {
+            pair.fresh = false;
+        }
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    fn get(
+        &mut self,
+        key: &ContactKey,
+        shape_position: Vector,
+        tolerance: Scalar,
+    ) -> Option<&CachedContacts> {
+        let pair = self.pairs.iter_mut().find(|pair| pair.key == *key)?;
+        if pair.shape_position.distance(shape_position) > tolerance {
+            return None;
+        }
+        pair.fresh = true;
+        self.hits += 1;
+        Some(pair)
+    }
+
+    fn insert(
+        &mut self,
+        key: ContactKey,
+        shape_position: Vector,
+        contacts: Vec<(ContactPoint, Dir2)>,
+    ) {
+        self.misses += 1;
+        let pair = CachedContacts {
+            key,
+            shape_position,
+            contacts,
+            fresh: true,
+        };
+        let old = self.pairs.iter_mut().find(|pair| {
+            pair.key.entity == key.entity && pair.key.prediction_distance == key.prediction_distance
+        });
+        match old {
+            Some(old) => *old = pair,
+            None => self.pairs.push(pair),
+        }
+    }
+}
@@ -0,0 +1,19 @@
+//! Plays the first level for ten seconds without a window or renderer, then quits.
+//!
+//! `cargo run --example headless`
+
+use bevy::prelude::*;
+use projg::launch::LaunchArgs;
+use projg::AppPlugin;
+
+fn main() -> AppExit {
+    App::new()
+        .insert_resource(LaunchArgs {
+            level: Some(0),
+            skip_menu: true,
+            headless_ticks: Some(640),
+            ..default()
+        })
+        .add_plugins(AppPlugin)
+        .run()
+}
@@ -0,0 +1,274 @@
+//! Animation states and the events they send, so what happens on a given frame is data instead of
+//! code in whichever system cares.
+//!
+//! Players get an [`AnimState`] picked from how they move, and a clip per state read from
+//! `animations.ron` next to their `entity.ron`. Entering a state, leaving it and every frame of
+//! its clip trigger an [`AnimationEvent`]. The clip's cues hook effects onto those moments,
+//! [`AnimationCue::Sound`] plays a sound and [`AnimationCue::Hitbox`] arms a hitbox for a range of
+//! frames. Anything else can observe [`AnimationEvent`] itself.
+
+use crate::audio::PlaySound;
+use crate::combat::{Hitbox, Hurtbox, Team};
+use crate::definitions::{read_definition, Definition, Migrate};
+use crate::locomotion::{Crouching, LocomotionDriver};
+use crate::mario::{Char, CharBundle};
+use crate::physics::{Grounded, KinematicController, UpDirection};
+use crate::time::PausableSystems;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+
+const CLIPS_FILE: &str = "animations.ron";
+/// Slower than this on the ground counts as standing.
+const IDLE_SPEED: f32 = 1.0;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Reflect, Deserialize)]
+#[reflect(Component)]
+pub enum AnimState {
+    #[default]
+    Idle,
+    Walk,
+    Run,
+    Jump,
+    Fall,
+    Crouch,
+}
+
+/// When in a state something happens.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect, Deserialize)]
+pub enum AnimationMoment {
+    Enter,
+    Exit,
+    /// a frame of the clip starts showing, counting from 0
+    Frame(u32),
+}
+
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct AnimationEvent {
+    pub entity: Entity,
+    pub state: AnimState,
+    pub moment: AnimationMoment,
+}
+
+#[derive(Clone, Debug, Reflect, Deserialize)]
+pub enum AnimationCue {
+    /// a sound from the [`SoundLibrary`](crate::audio::SoundLibrary) by name
+    Sound(String),
+    /// a hitbox on the entity's team, active from the first frame through the last
+    Hitbox {
+        damage: u32,
+        half_size: Vec2,
+        #[serde(default)]
+        offset: Vec2,
+        frames: (u32, u32),
+    },
+}
+
+#[derive(Clone, Debug, Reflect, Deserialize)]
+#[serde(default)]
+pub struct Clip {
+    pub fps: f32,
+    pub frames: u32,
+    /// starts over after the last frame, otherwise stays on it
+    pub looping: bool,
+    pub cues: Vec<(AnimationMoment, AnimationCue)>,
+}
+
+impl Default for Clip {
+    fn default() -> Self {
+        Self {
+            fps: 10.0,
+            frames: 1,
+            looping: true,
+            cues: Vec::new(),
+        }
+    }
+}
+
+/// `animations.ron`, the clip of each state. States without one send only enter and exit.
+#[derive(Component, Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct AnimationClips {
+    pub clips: HashMap<AnimState, Clip>,
+}
+
+impl Migrate for AnimationClips {
+    const VERSION: u32 = 1;
+}
+
+/// Where the current clip is at.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct AnimationClock {
+    /// the state the clip belongs to, `None` before the first tick
+    pub state: Option<AnimState>,
+    pub frame: u32,
+    /// seconds into the current frame
+    pub elapsed: f32,
+}
+
+/// A hitbox armed by an [`AnimationCue::Hitbox`], gone when its frames are over.
+#[derive(Component)]
+struct CueHitbox {
+    state: AnimState,
+    last_frame: u32,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<AnimState>()
+        .register_type::<AnimationClock>()
+        .add_systems(
+            Update,
+            (pick_states, advance_clips)
+                .chain()
+                .in_set(PausableSystems),
+        )
+        .add_observer(load_clips)
+        .add_observer(play_cues);
+}
+
+fn load_clips(
+    add: On<Add, Char>,
+    mut commands: Commands,
+    definitions: Query<&Definition<CharBundle>>,
+) {
+    let Ok(definition) = definitions.get(add.entity) else {
+        return;
+    };
+    let path = Path::new(&definition.path).with_file_name(CLIPS_FILE);
+    let clips = read_definition::<AnimationClips>(&path.to_string_lossy()).unwrap_or_else(|e| {
+        info!("no animations: {e}");
+        AnimationClips::default()
+    });
+    commands
+        .entity(add.entity)
+        .insert((clips, AnimState::default(), AnimationClock::default()));
+}
+
+fn pick_states(
+    mut query: Query<(
+        &mut AnimState,
+        &KinematicController,
+        &LocomotionDriver,
+        Has<Grounded>,
+        Has<Crouching>,
+        Option<&UpDirection>,
+    )>,
+) {
+    for (mut state, controller, driver, grounded, crouching, up) in query.iter_mut() {
+        let up = up.copied().unwrap_or_default();
+        let sideways = controller.velocity - *up.0 * up.speed(controller.velocity);
+        let picked = match (grounded, crouching) {
+            (_, true) => AnimState::Crouch,
+            (false, _) if up.speed(controller.velocity) > 0.0 => AnimState::Jump,
+            (false, _) => AnimState::Fall,
+            _ if sideways.length() < IDLE_SPEED => AnimState::Idle,
+            _ if driver.running => AnimState::Run,
+            _ => AnimState::Walk,
+        };
+        state.set_if_neq(picked);
+    }
+}
+
+fn advance_clips(
+    mut commands: Commands,
+    mut query: Query<(Entity, &AnimState, &mut AnimationClock, &AnimationClips)>,
+    time: Res<Time>,
+) {
+    for (entity, &state, mut clock, clips) in query.iter_mut() {
+        let event = |moment| AnimationEvent {
+            entity,
+            state,
+            moment,
+        };
+        if clock.state != Some(state) {
+            if let Some(old) = clock.state {
+                commands.trigger(AnimationEvent {
+                    entity,
+                    state: old,
+                    moment: AnimationMoment::Exit,
+                });
+            }
+            *clock = AnimationClock {
+                state: Some(state),
+                ..default()
+            };
+            commands.trigger(event(AnimationMoment::Enter));
+            commands.trigger(event(AnimationMoment::Frame(0)));
+            continue;
+        }
+        let Some(clip) = clips.clips.get(&state).filter(|clip| clip.fps > 0.0) else {
+            continue;
+        };
+        let frame_time = 1.0 / clip.fps;
+        clock.elapsed += time.delta_secs();
+        while clock.elapsed >= frame_time {
+            clock.elapsed -= frame_time;
+            let next = clock.frame + 1;
+            if next < clip.frames {
+                clock.frame = next;
+            } else if clip.looping {
+                clock.frame = 0;
+            } else {
+                //held on the last frame
+                clock.elapsed = 0.0;
+                break;
+            }
+            commands.trigger(event(AnimationMoment::Frame(clock.frame)));
+        }
+    }
+}
+
+fn play_cues(
+    animation: On<AnimationEvent>,
+    mut commands: Commands,
+    entities: Query<(&AnimationClips, Option<&Hurtbox>, Option<&Children>)>,
+    hitboxes: Query<&CueHitbox>,
+) {
+    let Ok((clips, hurtbox, children)) = entities.get(animation.entity) else {
+        return;
+    };
+    //hitboxes whose frames are over, or whose state is
+    for child in children.into_iter().flatten() {
+        let Ok(cue) = hitboxes.get(*child) else {
+            continue;
+        };
+        let over = match animation.moment {
+            AnimationMoment::Exit => cue.state == animation.state,
+            AnimationMoment::Frame(frame) => frame > cue.last_frame || frame == 0,
+            AnimationMoment::Enter => false,
+        };
+        if over {
+            commands.entity(*child).despawn();
+        }
+    }
+    let Some(clip) = clips.clips.get(&animation.state) else {
+        return;
+    };
+    for (moment, cue) in &clip.cues {
+        match cue {
+            AnimationCue::Sound(name) if *moment == animation.moment => {
+                commands.trigger(PlaySound::new(name.clone()));
+            }
+            AnimationCue::Hitbox {
+                damage,
+                half_size,
+                offset,
+                frames: (first, last),
+            } if animation.moment == AnimationMoment::Frame(*first) => {
+                let team = hurtbox.map_or(Team::Player, |hurtbox| hurtbox.team);
+                commands.entity(animation.entity).with_child((
+                    Name::new("Animation hitbox"),
+                    CueHitbox {
+                        state: animation.state,
+                        last_frame: *last,
+                    },
+                    Hitbox::new(team, *damage, *half_size).with_offset(*offset),
+                    Transform::default(),
+                ));
+            }
+            _ => {}
+        }
+    }
+}
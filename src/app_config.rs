@@ -0,0 +1,43 @@
+//! Settings that differ between builds of the game rather than between players, read from
+//! `app_config.ron` in the working directory. Anything missing falls back to
+//! [`AppConfig::default`].
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+
+pub const CONFIG_PATH: &str = "app_config.ron";
+
+/// `app_config.ron`
+#[derive(Resource, Debug, Clone, Reflect, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct AppConfig {
+    /// window title
+    pub title: String,
+    /// zoom of the game camera, smaller is closer
+    pub projection_scale: f32,
+    /// the physics length unit, roughly the size of a character in pixels
+    pub length_unit: f32,
+    /// LDtk project with the levels, relative to the assets
+    pub ldtk_path: String,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            title: "Projg".to_string(),
+            projection_scale: 0.35,
+            length_unit: 10.0,
+            ldtk_path: "ldtk/mayrio.ldtk".to_string(),
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
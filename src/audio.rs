@@ -1,2 +1,318 @@
+//! Sound effects by name and the music track.
+//!
+//! The [`SoundLibrary`] is read from `assets/audio/sounds.ron`, which maps sound names to files
+//! and names the music. Gameplay triggers [`PlaySound`] with a name instead of loading paths.
+//! A sound can have several files played in turn and a random pitch and volume spread, so
+//! repeated sounds like footsteps don't all come out the same.
+//!
+//! The music ducks under anything with [`DucksMusic`] (dialog boxes) and during a hitstop
+//! freeze, ramping down and back up as set in [`Ducking`].
+//!
+//! In dev builds changed audio files are picked up while playing: music restarts where it was,
+//! sound effects just play the new version the next time.
+
+use crate::screens::Screen;
+use crate::time::StopTimer;
+use bevy::audio::Volume;
 use bevy::prelude::*;
-pub(super) fn plugin(app: &mut App) {}
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+/// `assets/audio/sounds.ron`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct SoundManifest {
+    sounds: HashMap<String, SoundDef>,
+    music: Option<String>,
+}
+
+/// One named sound in the manifest.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct SoundDef {
+    /// played round robin
+    files: Vec<String>,
+    /// each play is this much faster or slower at most, as a fraction
+    pitch: f32,
+    /// each play is this much louder or quieter at most, as a fraction
+    volume: f32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Sound {
+    pub variants: Vec<Handle<AudioSource>>,
+    pub pitch: f32,
+    pub volume: f32,
+    /// variant played next
+    next: usize,
+}
+
+impl Sound {
+    /// The next variant in turn.
+    fn next_variant(&mut self) -> Option<Handle<AudioSource>> {
+        let variant = self.variants.get(self.next)?.clone();
+        self.next = (self.next + 1) % self.variants.len();
+        Some(variant)
+    }
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct SoundLibrary {
+    sounds: HashMap<String, Sound>,
+    music: Option<Handle<AudioSource>>,
+}
+
+impl SoundLibrary {
+    pub fn get(&self, name: &str) -> Option<&Sound> {
+        self.sounds.get(name)
+    }
+}
+
+/// Plays a sound from the [`SoundLibrary`] once.
+#[derive(Event, Clone, Debug)]
+pub struct PlaySound {
+    pub name: String,
+    pub speed: f32,
+    pub bus: SoundBus,
+}
+
+impl PlaySound {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            speed: 1.0,
+            bus: SoundBus::Sfx,
+        }
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = speed;
+        self
+    }
+
+    pub fn on_bus(mut self, bus: SoundBus) -> Self {
+        self.bus = bus;
+        self
+    }
+}
+
+/// Which volume a sound effect plays at.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+pub enum SoundBus {
+    #[default]
+    Sfx,
+    Ui,
+}
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct BusVolumes {
+    pub sfx: f32,
+    pub ui: f32,
+}
+
+impl Default for BusVolumes {
+    fn default() -> Self {
+        Self { sfx: 1.0, ui: 0.7 }
+    }
+}
+
+impl BusVolumes {
+    pub fn get(&self, bus: SoundBus) -> f32 {
+        match bus {
+            SoundBus::Sfx => self.sfx,
+            SoundBus::Ui => self.ui,
+        }
+    }
+}
+
+/// The looping music player.
+#[derive(Component, Debug)]
+pub struct Music;
+
+/// Ducks the music while it exists.
+#[derive(Component, Debug, Default)]
+pub struct DucksMusic;
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Ducking {
+    /// music volume when nothing ducks it
+    pub music_volume: f32,
+    /// fraction of the volume taken away at full duck
+    pub amount: f32,
+    /// seconds to duck fully
+    pub attack: f32,
+    /// seconds to come back up
+    pub release: f32,
+    /// 0 not ducked, 1 fully ducked
+    level: f32,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            amount: 0.6,
+            attack: 0.08,
+            release: 0.4,
+            level: 0.0,
+        }
+    }
+}
+
+const MANIFEST_PATH: &str = "assets/audio/sounds.ron";
+
+fn read_manifest(path: &str) -> Result<SoundManifest, Box<dyn Error>> {
+    let string = fs::read_to_string(path)?;
+    Ok(ron::from_str(&string)?)
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<Ducking>()
+        .register_type::<BusVolumes>()
+        .init_resource::<SoundLibrary>()
+        .init_resource::<Ducking>()
+        .init_resource::<BusVolumes>()
+        .add_systems(Startup, load_library)
+        .add_systems(Update, duck_music)
+        .add_systems(OnEnter(Screen::Game), start_music)
+        .add_observer(play_sound);
+    #[cfg(feature = "dev")]
+    app.add_systems(Update, reload_audio);
+}
+
+fn load_library(mut library: ResMut<SoundLibrary>, asset_server: Res<AssetServer>) {
+    let manifest = read_manifest(MANIFEST_PATH).unwrap_or_else(|e| {
+        warn!("could not read {MANIFEST_PATH}: {e}");
+        SoundManifest::default()
+    });
+    library.sounds = manifest
+        .sounds
+        .into_iter()
+        .map(|(name, def)| {
+            let sound = Sound {
+                variants: def.files.into_iter().map(|path| asset_server.load(path)).collect(),
+                pitch: def.pitch.clamp(0.0, 0.9),
+                volume: def.volume.clamp(0.0, 1.0),
+                next: 0,
+            };
+            (name, sound)
+        })
+        .collect();
+    library.music = manifest.music.map(|path| asset_server.load(path));
+}
+
+fn start_music(
+    mut commands: Commands,
+    library: Res<SoundLibrary>,
+    music: Query<(), With<Music>>,
+) {
+    let Some(track) = library.music.clone() else {
+        return;
+    };
+    if music.is_empty() {
+        commands.spawn((
+            Name::new("Music"),
+            Music,
+            AudioPlayer::new(track),
+            PlaybackSettings::LOOP,
+        ));
+    }
+}
+
+/// A random factor within `spread` of 1.
+fn vary(spread: f32) -> f32 {
+    1.0 + rand::random_range(-spread..=spread)
+}
+
+fn play_sound(
+    play: On<PlaySound>,
+    mut commands: Commands,
+    mut library: ResMut<SoundLibrary>,
+    buses: Res<BusVolumes>,
+) {
+    let Some(sound) = library.sounds.get_mut(&play.name) else {
+        warn!("no sound named {}", play.name);
+        return;
+    };
+    let Some(variant) = sound.next_variant() else {
+        warn!("sound {} has no files", play.name);
+        return;
+    };
+    commands.spawn((
+        AudioPlayer::new(variant),
+        PlaybackSettings::DESPAWN
+            .with_speed(play.speed * vary(sound.pitch))
+            .with_volume(Volume::Linear(buses.get(play.bus) * vary(sound.volume))),
+    ));
+}
+
+fn duck_music(
+    mut ducking: ResMut<Ducking>,
+    duckers: Query<(), With<DucksMusic>>,
+    hitstop: Res<StopTimer>,
+    mut music: Query<&mut AudioSink, With<Music>>,
+    //virtual time stands still during a hitstop
+    time: Res<Time<Real>>,
+) {
+    let ducked = !duckers.is_empty() || hitstop.is_active();
+    let (target, ramp) = if ducked {
+        (1.0, ducking.attack)
+    } else {
+        (0.0, ducking.release)
+    };
+    let step = if ramp > 0.0 {
+        time.delta_secs() / ramp
+    } else {
+        1.0
+    };
+    let level = ducking.level + (target - ducking.level).clamp(-step, step);
+    if level != ducking.level {
+        ducking.level = level;
+    }
+    let volume = ducking.music_volume * (1.0 - ducking.amount * ducking.level);
+    for mut sink in music.iter_mut() {
+        sink.set_volume(Volume::Linear(volume));
+    }
+}
+
+/// A playing sink keeps decoding the old file, so changed music has to be restarted.
+#[cfg(feature = "dev")]
+fn reload_audio(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<AudioSource>>,
+    library: Res<SoundLibrary>,
+    music: Query<(Entity, &AudioPlayer, Option<&AudioSink>), With<Music>>,
+) {
+    for event in events.read() {
+        let AssetEvent::Modified { id } = event else {
+            continue;
+        };
+        for (entity, player, sink) in music.iter() {
+            if player.0.id() != *id {
+                continue;
+            }
+            let position = sink.map(|sink| sink.position()).unwrap_or_default();
+            info!("music changed, restarting at {:.1}s", position.as_secs_f32());
+            commands.entity(entity).despawn();
+            commands.spawn((
+                Name::new("Music"),
+                Music,
+                AudioPlayer::new(player.0.clone()),
+                PlaybackSettings::LOOP.with_start_position(position),
+            ));
+        }
+        if let Some(name) = library.sounds.iter().find_map(|(name, sound)| {
+            sound
+                .variants
+                .iter()
+                .any(|handle| handle.id() == *id)
+                .then_some(name)
+        }) {
+            info!("sound {name} changed");
+        }
+    }
+}
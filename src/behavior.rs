@@ -0,0 +1,296 @@
+//! Behavior trees for enemy decisions.
+//!
+//! A [`BehaviorTree`] is ticked every fixed tick against the entity's [`Blackboard`], which is
+//! filled from the world right before (where the player is, how hurt the entity is, whether it
+//! walked into a wall) and read right after to move the entity. Trees are built out of the
+//! composites and the common leaves in [`Node`], anything enemy specific goes in a
+//! [`Node::Custom`] leaf instead of a new branch in some enemy's system.
+
+use crate::enemies::Enemy;
+use crate::jump::apply_gravity;
+use crate::mario::{nearest_player, Char};
+use crate::physics::{perform_move_and_slide, ControllerHit, Grounded, KinematicController};
+use crate::player_state::Health;
+use crate::screens::level_ready;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+/// A leaf written in Rust.
+pub type LeafFn = fn(&mut Blackboard) -> Status;
+
+#[derive(Clone, Debug)]
+pub enum Node {
+    /// runs children in order until one doesn't succeed
+    Sequence(Vec<Node>),
+    /// runs children in order until one doesn't fail
+    Selector(Vec<Node>),
+    Invert(Box<Node>),
+    PlayerWithin(f32),
+    /// health is at or below this fraction of the maximum
+    HealthBelow(f32),
+    /// walks back and forth up to `distance` from home, turning at walls
+    Patrol { speed: f32, distance: f32 },
+    Chase { speed: f32 },
+    Flee { speed: f32 },
+    /// hops at the player, then waits `cooldown` seconds before the next one
+    Lunge { speed: f32, hop: f32, cooldown: f32 },
+    Custom(&'static str, LeafFn),
+}
+
+/// What a tree knows about its entity and the world, per entity.
+#[derive(Component, Clone, Debug, Default)]
+pub struct Blackboard {
+    pub position: Vec2,
+    pub home: Option<Vec2>,
+    pub player: Option<Vec2>,
+    /// current over max health, 1 without a [`Health`]
+    pub health: f32,
+    /// -1 left, 1 right
+    pub facing: f32,
+    /// walked into a wall since the last tick
+    pub blocked: bool,
+    /// always false without a ground probing [`ShapeCaster`](avian2d::prelude::ShapeCaster)
+    pub grounded: bool,
+    pub delta: f32,
+    /// what the tree wants, applied to the controller after the tick
+    pub move_x: Option<f32>,
+    pub jump: Option<f32>,
+    /// name of the leaf that ran last, for the inspector and logs
+    pub running: &'static str,
+    /// anything else a custom leaf wants to keep, timers and counters by name
+    pub values: HashMap<&'static str, f32>,
+}
+
+impl Blackboard {
+    fn player_offset(&self) -> Option<Vec2> {
+        self.player.map(|player| player - self.position)
+    }
+
+    /// Counts a named timer down, true once it has run out.
+    pub fn cooldown_over(&mut self, name: &'static str) -> bool {
+        let delta = self.delta;
+        let left = self.values.entry(name).or_insert(0.0);
+        *left = (*left - delta).max(0.0);
+        *left == 0.0
+    }
+}
+
+#[derive(Component, Clone, Debug)]
+#[require(Blackboard)]
+pub struct BehaviorTree {
+    pub root: Node,
+}
+
+impl BehaviorTree {
+    pub fn new(root: Node) -> Self {
+        Self { root }
+    }
+
+    /// Walk one way until a wall, then the other.
+    pub fn walker() -> Self {
+        Self::new(Node::Patrol {
+            speed: 30.0,
+            distance: f32::INFINITY,
+        })
+    }
+
+    /// Patrol until the player gets close, chase, lunge when in reach, run away when hurt.
+    pub fn brawler() -> Self {
+        Self::new(Node::Selector(vec![
+            Node::Sequence(vec![
+                Node::HealthBelow(0.34),
+                Node::PlayerWithin(96.0),
+                Node::Flee { speed: 45.0 },
+            ]),
+            Node::Sequence(vec![
+                Node::PlayerWithin(28.0),
+                Node::Lunge {
+                    speed: 70.0,
+                    hop: 120.0,
+                    cooldown: 1.2,
+                },
+            ]),
+            Node::Sequence(vec![
+                Node::PlayerWithin(120.0),
+                Node::Chase { speed: 40.0 },
+            ]),
+            Node::Patrol {
+                speed: 25.0,
+                distance: 48.0,
+            },
+        ]))
+    }
+}
+
+impl Node {
+    pub fn tick(&self, board: &mut Blackboard) -> Status {
+        match self {
+            Node::Sequence(children) => {
+                for child in children {
+                    match child.tick(board) {
+                        Status::Success => continue,
+                        status => return status,
+                    }
+                }
+                Status::Success
+            }
+            Node::Selector(children) => {
+                for child in children {
+                    match child.tick(board) {
+                        Status::Failure => continue,
+                        status => return status,
+                    }
+                }
+                Status::Failure
+            }
+            Node::Invert(child) => match child.tick(board) {
+                Status::Success => Status::Failure,
+                Status::Failure => Status::Success,
+                Status::Running => Status::Running,
+            },
+            Node::PlayerWithin(distance) => succeed_if(
+                board
+                    .player_offset()
+                    .is_some_and(|offset| offset.length() <= *distance),
+            ),
+            Node::HealthBelow(fraction) => succeed_if(board.health <= *fraction),
+            Node::Patrol { speed, distance } => {
+                board.running = "patrol";
+                let home = *board.home.get_or_insert(board.position);
+                let offset = board.position.x - home.x;
+                let too_far = offset.abs() >= *distance && offset.signum() == board.facing;
+                if board.blocked || too_far {
+                    board.facing = -board.facing;
+                }
+                board.move_x = Some(speed * board.facing);
+                Status::Running
+            }
+            Node::Chase { speed } => {
+                let Some(offset) = board.player_offset() else {
+                    return Status::Failure;
+                };
+                board.running = "chase";
+                board.facing = facing(offset.x, board.facing);
+                board.move_x = Some(speed * board.facing);
+                Status::Running
+            }
+            Node::Flee { speed } => {
+                let Some(offset) = board.player_offset() else {
+                    return Status::Failure;
+                };
+                board.running = "flee";
+                board.facing = -facing(offset.x, -board.facing);
+                board.move_x = Some(speed * board.facing);
+                Status::Running
+            }
+            Node::Lunge {
+                speed,
+                hop,
+                cooldown,
+            } => {
+                let Some(offset) = board.player_offset() else {
+                    return Status::Failure;
+                };
+                board.running = "lunge";
+                //mid hop, let it fly
+                if !board.grounded {
+                    return Status::Running;
+                }
+                board.facing = facing(offset.x, board.facing);
+                if !board.cooldown_over("lunge") {
+                    board.move_x = Some(0.0);
+                    return Status::Running;
+                }
+                board.move_x = Some(speed * board.facing);
+                board.jump = Some(*hop);
+                board.values.insert("lunge", *cooldown);
+                Status::Success
+            }
+            Node::Custom(name, leaf) => {
+                board.running = *name;
+                leaf(board)
+            }
+        }
+    }
+}
+
+fn succeed_if(condition: bool) -> Status {
+    if condition {
+        Status::Success
+    } else {
+        Status::Failure
+    }
+}
+
+/// Which way to face for `x`, keeping the old facing when it's straight up or down.
+fn facing(x: f32, current: f32) -> f32 {
+    if x == 0.0 { current } else { x.signum() }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (sense, tick_trees, act)
+            .chain()
+            .after(apply_gravity)
+            .before(perform_move_and_slide)
+            .run_if(level_ready),
+    )
+    .add_observer(notice_walls);
+}
+
+fn notice_walls(hit: On<ControllerHit>, mut boards: Query<&mut Blackboard>) {
+    let Ok(mut board) = boards.get_mut(hit.entity) else {
+        return;
+    };
+    //only walls facing us, floors don't count
+    if hit.normal.x * board.facing < -0.5 {
+        board.blocked = true;
+    }
+}
+
+fn sense(
+    mut boards: Query<(&mut Blackboard, &Transform, Option<&Health>, Has<Grounded>), Without<Char>>,
+    mario: Query<&Transform, With<Char>>,
+    time: Res<Time>,
+) {
+    for (mut board, transform, health, grounded) in boards.iter_mut() {
+        board.position = transform.translation.xy();
+        board.player = nearest_player(&mario, board.position);
+        board.health = health.map_or(1.0, |health| {
+            health.current as f32 / health.max.max(1) as f32
+        });
+        if board.facing == 0.0 {
+            board.facing = -1.0;
+        }
+        board.grounded = grounded;
+        board.delta = time.delta_secs();
+        board.move_x = None;
+        board.jump = None;
+    }
+}
+
+fn tick_trees(mut trees: Query<(&BehaviorTree, &mut Blackboard)>) {
+    for (tree, mut board) in trees.iter_mut() {
+        tree.root.tick(&mut board);
+        board.blocked = false;
+    }
+}
+
+fn act(mut boards: Query<(&Blackboard, &mut KinematicController), With<Enemy>>) {
+    for (board, mut controller) in boards.iter_mut() {
+        if let Some(move_x) = board.move_x {
+            controller.velocity.x = move_x;
+        }
+        if let Some(jump) = board.jump {
+            controller.velocity.y = jump;
+        }
+    }
+}
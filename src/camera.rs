@@ -1,16 +1,95 @@
+use crate::app_config::AppConfig;
+use crate::mario::Char;
+use crate::screens::Screen;
+use crate::split_screen::PlayerSlot;
+use avian2d::prelude::TransformInterpolation;
 use bevy::prelude::*;
 
 #[derive(Event, Copy, Clone)]
 pub struct CameraReset;
+/// The camera of the player in this [`PlayerSlot`]. The first one is spawned once when the game
+/// starts and the others when their players join, then each is kept for the rest of the session:
+/// a respawned player is handed the camera of its slot again instead of a new one.
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+#[reflect(Component)]
+pub struct PlayerCamera(pub usize);
+
 #[derive(Component, Reflect)]
 #[relationship(relationship_target = FollowTargets)]
 pub struct FollowerOf(pub Entity);
 
 #[derive(Component, Reflect)]
 #[relationship_target(relationship = FollowerOf)]
-#[require(FollowAxes)]
+#[require(FollowAxes, CameraOffsets, FollowCenter)]
 pub struct FollowTargets(Vec<Entity>);
 
+/// Where the camera would be without any offsets or clamping, as of the last [`follow_targets`].
+#[derive(Component, Reflect, Default, Clone, Copy, Debug)]
+pub struct FollowCenter(pub Vec2);
+
+/// Who is moving the camera away from what it follows. Each writes only its own channel.
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OffsetChannel {
+    /// ahead of where the player is going
+    Lookahead,
+    /// the player looking up or down
+    Peek,
+    /// added on top of everything else
+    Shake,
+    /// a cutscene taking the camera somewhere, over everything but shake
+    CutscenePan,
+}
+
+impl OffsetChannel {
+    /// Blended channels, highest priority first. Each gets its weight's share of what the ones
+    /// before it left over, so one at full weight hides everything below it.
+    const BLENDED: [Self; 3] = [Self::CutscenePan, Self::Peek, Self::Lookahead];
+}
+
+#[derive(Reflect, Clone, Copy, Debug, Default)]
+pub struct CameraOffset {
+    pub offset: Vec2,
+    /// how much of the offset is used, from 0 to 1
+    pub weight: f32,
+}
+
+/// Moves the camera away from what it follows, before clamping so it never shows past the level.
+/// The channels are composited in [`follow_targets`].
+#[derive(Component, Reflect, Default, Clone, Debug)]
+pub struct CameraOffsets {
+    channels: [CameraOffset; 4],
+}
+
+impl CameraOffsets {
+    pub fn get(&self, channel: OffsetChannel) -> CameraOffset {
+        self.channels[channel as usize]
+    }
+
+    pub fn set(&mut self, channel: OffsetChannel, offset: Vec2, weight: f32) {
+        self.channels[channel as usize] = CameraOffset {
+            offset,
+            weight: weight.clamp(0.0, 1.0),
+        };
+    }
+
+    pub fn clear(&mut self, channel: OffsetChannel) {
+        self.channels[channel as usize] = CameraOffset::default();
+    }
+
+    /// All channels together, see [`OffsetChannel::BLENDED`].
+    pub fn composite(&self) -> Vec2 {
+        let mut left = 1.0;
+        let mut total = Vec2::ZERO;
+        for channel in OffsetChannel::BLENDED {
+            let CameraOffset { offset, weight } = self.get(channel);
+            total += offset * weight * left;
+            left *= 1.0 - weight;
+        }
+        let shake = self.get(OffsetChannel::Shake);
+        total + shake.offset * shake.weight
+    }
+}
+
 #[derive(Component, Reflect)]
 pub struct FollowAxes(pub u8);
 #[derive(Component, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Debug)]
@@ -63,16 +142,111 @@ impl Default for FollowAxes {
     }
 }
 
-pub(crate) fn plugin(app: &mut App) {
-    app.add_systems(PostUpdate, follow_targets);
+pub fn plugin(app: &mut App) {
+    app.register_type::<CameraOffsets>()
+        .register_type::<PlayerCamera>()
+        .add_systems(OnEnter(Screen::Game), spawn_session_camera)
+        .add_systems(
+            Update,
+            (attach_players, despawn_left_cameras)
+                .chain()
+                .run_if(in_state(Screen::Game)),
+        )
+        .add_systems(PostUpdate, follow_targets);
+}
+
+fn player_camera(slot: usize, config: &AppConfig) -> impl Bundle {
+    (
+        Name::new(format!("Camera {}", slot + 1)),
+        PlayerCamera(slot),
+        Camera2d,
+        Projection::Orthographic(OrthographicProjection {
+            scale: config.projection_scale,
+            scaling_mode: bevy::camera::ScalingMode::FixedVertical {
+                viewport_height: 720.0,
+            },
+            ..OrthographicProjection::default_2d()
+        }),
+        //TODO spawn at char location instead?
+        Transform::from_xyz(1280.0 / 4.0, 238.0, 0.0),
+        //per level camera
+        ClampFlags(0),
+        //TODO this really should use Option<T> for clamping
+        ClampPosition {
+            min: vec2(f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: vec2(10000000.0, 10000000.0),
+        },
+        TransformInterpolation,
+        DespawnOnExit(Screen::Game),
+    )
+}
+
+fn spawn_session_camera(
+    mut commands: Commands,
+    config: Res<AppConfig>,
+    cameras: Query<&PlayerCamera>,
+) {
+    if !cameras.iter().any(|camera| camera.0 == 0) {
+        commands.spawn(player_camera(0, &config));
+        info!("camera spawned");
+    }
+}
+
+/// Points players without a camera at the one of their slot, spawning it for a player that just
+/// joined.
+fn attach_players(
+    mut commands: Commands,
+    config: Res<AppConfig>,
+    players: Query<(Entity, Option<&PlayerSlot>), (With<Char>, Without<FollowerOf>)>,
+    cameras: Query<(Entity, &PlayerCamera)>,
+) {
+    for (player, slot) in players.iter() {
+        let slot = slot.map_or(0, |slot| slot.0);
+        let camera = cameras
+            .iter()
+            .find(|(_, camera)| camera.0 == slot)
+            .map(|(camera, _)| camera)
+            .unwrap_or_else(|| {
+                info!("camera spawned for player {}", slot + 1);
+                commands.spawn(player_camera(slot, &config)).id()
+            });
+        commands
+            .entity(player)
+            .insert((FollowerOf(camera), FollowWeight(1)));
+    }
+}
+
+/// The first camera stays for the session, the others go with the players that joined later.
+fn despawn_left_cameras(
+    mut commands: Commands,
+    cameras: Query<(Entity, &PlayerCamera), Without<FollowTargets>>,
+    players: Query<Option<&PlayerSlot>, With<Char>>,
+) {
+    for (entity, camera) in cameras.iter() {
+        let playing = players
+            .iter()
+            .any(|slot| slot.map_or(0, |slot| slot.0) == camera.0);
+        if camera.0 > 0 && !playing {
+            commands.entity(entity).despawn();
+        }
+    }
 }
 
 pub fn follow_targets(
-    mut cam_query: Query<(Entity, &mut Transform), With<FollowTargets>>,
+    mut cam_query: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut FollowCenter,
+            &CameraOffsets,
+            Option<(&ClampPosition, &ClampFlags)>,
+        ),
+        With<FollowTargets>,
+    >,
     follower_query: Query<&FollowTargets>,
     target_query: Query<(&Transform, &FollowWeight), Without<FollowTargets>>,
 ) {
-    for (e, mut transform) in cam_query.iter_mut() {
+    for (e, mut transform, mut center, offsets, clamp) in cam_query.iter_mut() {
         transform.translation.x = 0.0;
         transform.translation.y = 0.0;
         let ancs = follower_query.iter_descendants(e);
@@ -83,5 +257,13 @@ pub fn follow_targets(
             transform.translation.x += xf.translation.x * ratio;
             transform.translation.y += xf.translation.y * ratio;
         }
+        center.0 = transform.translation.xy();
+        transform.translation += offsets.composite().extend(0.0);
+        let Some((clamp, flags)) = clamp else { continue; };
+        let pos = &mut transform.translation;
+        if flags.has(ClampFlags::MIN_X) { pos.x = pos.x.max(clamp.min.x); }
+        if flags.has(ClampFlags::MAX_X) { pos.x = pos.x.min(clamp.max.x); }
+        if flags.has(ClampFlags::MIN_Y) { pos.y = pos.y.max(clamp.min.y); }
+        if flags.has(ClampFlags::MAX_Y) { pos.y = pos.y.min(clamp.max.y); }
     }
 }
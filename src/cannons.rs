@@ -0,0 +1,262 @@
+//! Launch cannons, geysers and wind tunnels.
+//!
+//! Walking into a `Cannon` entity locks the player inside it until it fires. The launch direction is
+//! either the `angle` field (degrees, 90 for a geyser) or, when `aim` is set, whatever direction the
+//! player holds. After firing the player bounces off walls for a short while instead of sliding.
+//!
+//! A `WindTunnel` entity keeps pushing every controller inside it along its `angle` instead, with
+//! a force of `strength` through [`ExternalKinematicForces`].
+
+use crate::jump::apply_gravity;
+use crate::locomotion::LocomotionDriver;
+use crate::mario::Char;
+use crate::physics::{
+    apply_external_forces, perform_move_and_slide, ControllerHit, ExternalKinematicForces,
+    KinematicController, UpDirection,
+};
+use crate::time::PausableSystems;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+#[derive(Component, Clone, Debug, Default, Reflect)]
+pub struct Cannon {
+    /// fixed launch direction
+    pub direction: Vec2,
+    /// let the player pick the direction while loaded
+    pub aim: bool,
+    pub speed: f32,
+    /// seconds the player stays inside before launching
+    pub delay: f32,
+    pub half_size: Vec2,
+    /// cleared on launch so the cannon doesn't catch the player again before they've left it
+    armed: bool,
+}
+
+impl From<&EntityInstance> for Cannon {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let angle = entity_instance
+            .get_float_field("angle")
+            .copied()
+            .unwrap_or(90.0);
+        Self {
+            direction: Vec2::from_angle(angle.to_radians()),
+            aim: entity_instance
+                .get_bool_field("aim")
+                .copied()
+                .unwrap_or(false),
+            speed: entity_instance
+                .get_float_field("speed")
+                .copied()
+                .unwrap_or(500.0),
+            delay: entity_instance
+                .get_float_field("delay")
+                .copied()
+                .unwrap_or(0.5),
+            half_size: vec2(entity_instance.width as f32, entity_instance.height as f32) / 2.0,
+            armed: true,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct CannonBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    cannon: Cannon,
+}
+
+#[derive(Component, Clone, Debug, Default, Reflect)]
+pub struct WindTunnel {
+    /// acceleration given to controllers inside
+    pub force: Vec2,
+    pub half_size: Vec2,
+}
+
+impl From<&EntityInstance> for WindTunnel {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let angle = entity_instance
+            .get_float_field("angle")
+            .copied()
+            .unwrap_or(90.0);
+        let strength = entity_instance
+            .get_float_field("strength")
+            .copied()
+            .unwrap_or(900.0);
+        Self {
+            force: Vec2::from_angle(angle.to_radians()) * strength,
+            half_size: vec2(entity_instance.width as f32, entity_instance.height as f32) / 2.0,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct WindTunnelBundle {
+    #[from_entity_instance]
+    wind_tunnel: WindTunnel,
+}
+
+/// The player is sitting in a cannon waiting to be fired.
+#[derive(Component, Clone, Debug)]
+pub struct Loaded {
+    pub cannon: Entity,
+    pub direction: Vec2,
+    pub timer: Timer,
+}
+
+/// Just fired from a cannon, walls bounce instead of slide until the timer runs out.
+#[derive(Component, Clone, Debug)]
+pub struct Launched {
+    pub timer: Timer,
+    /// velocity going into the slide, before it was projected onto whatever we hit
+    velocity: Vec2,
+}
+
+/// How long wall bounces last after a launch.
+const BOUNCE_SECONDS: f32 = 0.4;
+/// How far a surface's normal may point up or down and still bounce, floors and ceilings slide.
+const BOUNCE_MAX_UP: f32 = 0.5;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Cannon>()
+        .register_type::<WindTunnel>()
+        .register_ldtk_entity::<CannonBundle>("Cannon")
+        .register_ldtk_entity::<WindTunnelBundle>("WindTunnel")
+        .add_systems(
+            Update,
+            (load_cannons, aim_cannons).chain().in_set(PausableSystems),
+        )
+        .add_systems(
+            FixedUpdate,
+            (hold_in_cannon, fire_cannons, track_launch)
+                .chain()
+                .after(apply_gravity)
+                .before(perform_move_and_slide),
+        )
+        .add_systems(
+            FixedUpdate,
+            blow_wind
+                .after(apply_gravity)
+                .before(apply_external_forces),
+        )
+        .add_observer(bounce_off_walls);
+}
+
+fn load_cannons(
+    mut commands: Commands,
+    players: Query<(Entity, &Transform), (With<Char>, Without<Loaded>, Without<Launched>)>,
+    mut cannons: Query<(Entity, &mut Cannon, &GlobalTransform)>,
+) {
+    for (entity, mut cannon, xf) in cannons.iter_mut() {
+        let inside = players.iter().find(|(_, transform)| {
+            let offset = (transform.translation.xy() - xf.translation().xy()).abs();
+            offset.x <= cannon.half_size.x && offset.y <= cannon.half_size.y
+        });
+        let Some((mario, _)) = inside else {
+            cannon.armed = true;
+            continue;
+        };
+        if !cannon.armed {
+            continue;
+        }
+        cannon.armed = false;
+        commands.entity(mario).insert(Loaded {
+            cannon: entity,
+            direction: cannon.direction,
+            timer: Timer::from_seconds(cannon.delay, TimerMode::Once),
+        });
+    }
+}
+
+fn aim_cannons(mut mario: Query<(&mut Loaded, &LocomotionDriver)>, cannons: Query<&Cannon>) {
+    for (mut loaded, driver) in mario.iter_mut() {
+        let Ok(cannon) = cannons.get(loaded.cannon) else {
+            continue;
+        };
+        if cannon.aim && driver.wish_dir != Vec2::ZERO {
+            loaded.direction = driver.wish_dir.normalize();
+        }
+    }
+}
+
+//runs after gravity so nothing drags the player out of the barrel
+fn hold_in_cannon(
+    mut mario: Query<(&mut Transform, &mut KinematicController, &Loaded)>,
+    cannons: Query<&GlobalTransform, With<Cannon>>,
+) {
+    for (mut transform, mut controller, loaded) in mario.iter_mut() {
+        controller.velocity = Vec2::ZERO;
+        if let Ok(xf) = cannons.get(loaded.cannon) {
+            transform.translation = xf.translation().xy().extend(transform.translation.z);
+        }
+    }
+}
+
+fn fire_cannons(
+    mut commands: Commands,
+    mut mario: Query<(Entity, &mut KinematicController, &mut Loaded)>,
+    cannons: Query<&Cannon>,
+    time: Res<Time>,
+) {
+    for (entity, mut controller, mut loaded) in mario.iter_mut() {
+        let Ok(cannon) = cannons.get(loaded.cannon) else {
+            //cannon went away with its level
+            commands.entity(entity).remove::<Loaded>();
+            continue;
+        };
+        if !loaded.timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        controller.velocity = loaded.direction * cannon.speed;
+        commands.entity(entity).remove::<Loaded>().insert(Launched {
+            timer: Timer::from_seconds(BOUNCE_SECONDS, TimerMode::Once),
+            velocity: controller.velocity,
+        });
+    }
+}
+
+fn track_launch(
+    mut commands: Commands,
+    mut mario: Query<(Entity, &KinematicController, &mut Launched)>,
+    time: Res<Time>,
+) {
+    for (entity, controller, mut launched) in mario.iter_mut() {
+        if launched.timer.tick(time.delta()).is_finished() {
+            commands.entity(entity).remove::<Launched>();
+            continue;
+        }
+        launched.velocity = controller.velocity;
+    }
+}
+
+fn blow_wind(
+    mut controllers: Query<(&Transform, &mut ExternalKinematicForces), Without<Loaded>>,
+    tunnels: Query<(&WindTunnel, &GlobalTransform)>,
+) {
+    for (transform, mut forces) in controllers.iter_mut() {
+        let position = transform.translation.xy();
+        for (tunnel, xf) in tunnels.iter() {
+            let offset = (position - xf.translation().xy()).abs();
+            if offset.x <= tunnel.half_size.x && offset.y <= tunnel.half_size.y {
+                forces.apply_force(tunnel.force);
+            }
+        }
+    }
+}
+
+fn bounce_off_walls(
+    hit: On<ControllerHit>,
+    mut mario: Query<(&mut KinematicController, &mut Launched, Option<&UpDirection>)>,
+) {
+    let Ok((mut controller, mut launched, up)) = mario.get_mut(hit.entity) else {
+        return;
+    };
+    let normal = hit.normal.as_vec2();
+    let up = up.copied().unwrap_or_default();
+    //several contacts with the same surface only bounce once
+    if launched.velocity.dot(normal) >= 0.0 || up.speed(normal).abs() > BOUNCE_MAX_UP {
+        return;
+    }
+    launched.velocity = launched.velocity.reflect(normal);
+    controller.velocity = launched.velocity;
+}
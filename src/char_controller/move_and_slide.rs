@@ -1,704 +0,0 @@
-//! Contains the *move and slide* algorithm and utilities for kinematic character controllers.
-//!
-//! See the documentation of [`MoveAndSlide`] for more information.
-use avian2d::math::Scalar;
-use avian2d::math::{AdjustPrecision as _, AsF32 as _, Vector};
-use avian2d::{collision::collider::contact_query::contact_manifolds, prelude::*};
-use bevy::{ecs::system::SystemParam, math::Dir2, prelude::*};
-use core::time::Duration;
-
-/// A [`SystemParam`] for the *move and slide* algorithm, also known as *collide and slide* or *step slide*.
-///
-/// Move and slide is the core movement and collision algorithm used by most kinematic character controllers.
-/// It attempts to move a shape along a desired velocity vector, while sliding along any colliders it hits on the way.
-///
-/// # Algorithm
-///
-/// At a high level, the algorithm works as follows:
-///
-/// 1. Sweep the shape along the desired velocity vector.
-/// 2. If no collision is detected, move the full distance.
-/// 3. If a collision is detected:
-///    - Move up to the point of collision.
-///    - Project the remaining velocity onto the contact surfaces to obtain a new sliding velocity.
-/// 4. Repeat with the new sliding velocity.
-///
-/// The algorithm also includes depenetration passes before and after movement to ensure the shape is not intersecting any colliders.
-///
-/// # Configuration
-///
-/// [`MoveAndSlideConfig`] allows configuring various aspects of the algorithm.
-/// See its documentation for more information.
-///
-/// Additionally, [`move_and_slide`](MoveAndSlide::move_and_slide) can be given a callback that is called
-/// for each contact surface that is detected during movement. This allows for custom handling of collisions,
-/// such as triggering events, or modifying movement based on specific colliders.
-///
-/// # Resources
-///
-/// Some useful resources for learning more about the move and slide algorithm include:
-///
-/// - [*Collide And Slide - \*Actually Decent\* Character Collision From Scratch*](https://youtu.be/YR6Q7dUz2uk) by [Poke Dev](https://www.youtube.com/@poke_gamedev) (video)
-/// - [`PM_SlideMove`](https://github.com/id-Software/Quake-III-Arena/blob/dbe4ddb10315479fc00086f08e25d968b4b43c49/code/game/bg_slidemove.c#L45) in Quake III Arena (source code)
-///
-/// Note that while the high-level concepts are similar across different implementations, details may vary.
-#[derive(SystemParam)]
-#[doc(alias = "CollideAndSlide")]
-#[doc(alias = "StepSlide")]
-pub struct MoveAndSlide<'w, 's> {
-    /// The [`SpatialQueryPipeline`] used to perform spatial queries.
-    pub query_pipeline: Res<'w, SpatialQueryPipeline>,
-    /// The [`Query`] used to query colliders.
-    pub colliders: Query<
-        'w,
-        's,
-        (
-            &'static Collider,
-            &'static Position,
-            &'static Rotation,
-            Option<&'static CollisionLayers>,
-        ),
-    >,
-    /// A units-per-meter scaling factor that adjusts some thresholds and tolerances
-    /// to the scale of the world for better behavior.
-    pub length_unit: Res<'w, PhysicsLengthUnit>,
-}
-
-impl<'w, 's> MoveAndSlide<'w, 's> {
-    #[must_use]
-    #[doc(alias = "collide_and_slide")]
-    #[doc(alias = "step_slide")]
-    pub fn move_and_slide(
-        &self,
-        shape: &Collider,
-        shape_position: Vector,
-        shape_rotation: Scalar,
-        mut velocity: Vector,
-        delta_time: Duration,
-        config: &MoveAndSlideConfig,
-        filter: &SpatialQueryFilter,
-        mut on_hit: impl FnMut(MoveAndSlideHitData) -> bool,
-    ) -> MoveAndSlideOutput {
-        // High level overview:
-        // 1. Initial Gauss-Seidel depenetration pass
-        // 2. For each iteration, until movement is done or max iterations reached:
-        //    - Sweep the shape along the velocity vector
-        //    - If we hit something, move up to the hit point
-        //    - Collect contact planes
-        //    - Depenetrate based on intersections
-        //    - Project velocity to be parallel to all contact planes
-        let mut position = shape_position;
-        let original_velocity = velocity;
-        let mut time_left = delta_time.as_secs_f32();
-
-        // Initial depenetration pass
-        let mut intersections = Vec::new();
-        self.intersections(
-            shape,
-            position,
-            shape_rotation,
-            config.skin_width,
-            filter,
-            |contact_point, normal| {
-                // TODO: Should we call on_hit here?
-                intersections.push((normal, contact_point.penetration + config.skin_width));
-                true
-            },
-        );
-        let depenetration_offset = self.depenetrate(&config.into(), &intersections);
-        position += depenetration_offset;
-
-        // Main move and slide loop:
-        // 1. Sweep the shape along the velocity vector
-        // 2. If we hit something, move up to the hit point
-        // 3. Collect contact planes
-        // 4. Depenetrate based on intersections
-        // 5. Project velocity to be parallel to all contact planes
-        // 6. Repeat until we run out of iterations or time
-        'outer: for _ in 0..config.move_and_slide_iterations {
-            let sweep = time_left * velocity;
-            let Some((vel_dir, distance)) = Dir2::new_and_length(sweep.f32()).ok() else {
-                // No movement left
-                break;
-            };
-            let distance = distance.adjust_precision();
-            const MIN_DISTANCE: Scalar = 1e-4;
-            if distance < MIN_DISTANCE {
-                break;
-            }
-
-            // Sweep the shape along the velocity vector.
-            let Some(sweep_hit) = self.cast_move(
-                shape,
-                position,
-                shape_rotation,
-                sweep,
-                config.skin_width,
-                filter,
-            ) else {
-                // No collision, move the full distance.
-                position += sweep;
-                break;
-            };
-
-            if sweep_hit.intersects() {
-                // The entity is completely trapped in another solid.
-                velocity = Vector::ZERO;
-                break 'outer;
-            }
-
-            // Move up to the hit point.
-            time_left -= time_left * (sweep_hit.distance / distance);
-            position += vel_dir.adjust_precision() * sweep_hit.distance;
-
-            // Initialize velocity clipping planes with the user-defined planes.
-            // This often includes a ground plane.
-            let mut planes = config.planes.clone();
-
-            // Store penetrating contacts for depenetration.
-            let mut intersections = Vec::new();
-
-            // Collect contact planes.
-            self.intersections(
-                shape,
-                position,
-                shape_rotation,
-                // Use a slightly larger skin width to ensure we catch all contacts for velocity clipping.
-                // Depenetration still uses just the normal skin width.
-                config.skin_width * 2.0,
-                filter,
-                |contact_point, mut normal| {
-                    if planes.len() >= config.max_planes {
-                        return false;
-                    }
-
-                    if !on_hit(MoveAndSlideHitData {
-                        entity: sweep_hit.entity,
-                        point: contact_point.point,
-                        normal: &mut normal,
-                        collision_distance: sweep_hit.collision_distance,
-                        distance: sweep_hit.distance,
-                        position: &mut position,
-                        velocity: &mut velocity,
-                    }) {
-                        return false;
-                    }
-
-                    // Add the contact plane for velocity clipping.
-                    planes.push(normal);
-
-                    // Store penetrating contacts for depenetration.
-                    let total_penetration = contact_point.penetration + config.skin_width;
-                    if total_penetration > 0.0 {
-                        intersections.push((normal, total_penetration));
-                    }
-
-                    true
-                },
-            );
-
-            // Depenetrate based on intersections.
-            let depenetration_offset = self.depenetrate(&config.into(), &intersections);
-            position += depenetration_offset;
-
-            // Project velocity to be parallel to all contact planes.
-            velocity = Self::project_velocity(velocity, &planes);
-
-            // If the original velocity is against the original velocity, stop dead
-            // to avoid tiny occilations in sloping corners.
-            if velocity.dot(original_velocity) <= -DOT_EPSILON {
-                velocity = Vector::ZERO;
-                break 'outer;
-            }
-        }
-
-        MoveAndSlideOutput {
-            position,
-            projected_velocity: velocity,
-        }
-    }
-
-    #[must_use]
-    #[doc(alias = "sweep")]
-    pub fn cast_move(
-        &self,
-        shape: &Collider,
-        shape_position: Vector,
-        shape_rotation: Scalar,
-        movement: Vector,
-        skin_width: Scalar,
-        filter: &SpatialQueryFilter,
-    ) -> Option<MoveHitData> {
-        let (direction, distance) = Dir2::new_and_length(movement.f32()).unwrap_or((Dir2::X, 0.0));
-        let distance = distance.adjust_precision();
-        let shape_hit = self.query_pipeline.cast_shape(
-            shape,
-            shape_position,
-            shape_rotation,
-            direction,
-            &ShapeCastConfig::from_max_distance(distance),
-            filter,
-        )?;
-        let safe_distance = if distance == 0.0 {
-            0.0
-        } else {
-            Self::pull_back(shape_hit, direction, skin_width)
-        };
-        Some(MoveHitData {
-            distance: safe_distance,
-            collision_distance: distance,
-            entity: shape_hit.entity,
-            point1: shape_hit.point1,
-            point2: shape_hit.point2,
-            normal1: shape_hit.normal1,
-            normal2: shape_hit.normal2,
-        })
-    }
-
-    /// Returns a [`ShapeHitData::distance`] that is reduced such that the hit distance is at least `skin_width`.
-    /// The result will never be negative, so if the hit is already closer than `skin_width`, the returned distance will be zero.
-    #[must_use]
-    fn pull_back(hit: ShapeHitData, dir: Dir2, skin_width: Scalar) -> Scalar {
-        let dot = dir.adjust_precision().dot(-hit.normal1).max(DOT_EPSILON);
-        let skin_distance = skin_width / dot;
-        (hit.distance - skin_distance).max(0.0)
-    }
-    #[allow(unused)]
-    pub fn depenetrate_all(
-        &self,
-        shape: &Collider,
-        shape_position: Vector,
-        shape_rotation: Scalar,
-        config: &DepenetrationConfig,
-        filter: &SpatialQueryFilter,
-    ) -> Vector {
-        let mut intersections = Vec::new();
-        self.intersections(
-            shape,
-            shape_position,
-            shape_rotation,
-            config.skin_width,
-            filter,
-            |contact_point, normal| {
-                intersections.push((normal, contact_point.penetration + config.skin_width));
-                true
-            },
-        );
-        self.depenetrate(config, &intersections)
-    }
-
-    /// An [intersection test](spatial_query#intersection-tests) that calls a callback for each [`Collider`] found
-    /// that is closer to the given `shape` with a given position and rotation than `prediction_distance`.
-    ///
-    /// # Arguments
-    ///
-    /// - `shape`: The shape that intersections are tested against represented as a [`Collider`].
-    /// - `shape_position`: The position of the shape.
-    /// - `shape_rotation`: The rotation of the shape.
-    /// - `filter`: A [`SpatialQueryFilter`] that determines which colliders are taken into account in the query.
-    /// - `prediction_distance`: An extra margin applied to the [`Collider`].
-    /// - `callback`: A callback that is called for each intersection found. The callback receives the deepest contact point and the contact normal.
-    ///
-    /// # Example
-    ///
-    /// See [`MoveAndSlide::depenetrate`] for a typical usage scenario.
-    ///
-    /// # Related methods
-    ///
-    /// - [`MoveAndSlide::depenetrate`]
-    /// - [`MoveAndSlide::depenetrate_all`]
-    pub fn intersections(
-        &self,
-        shape: &Collider,
-        shape_position: Vector,
-        shape_rotation: Scalar,
-        prediction_distance: Scalar,
-        filter: &SpatialQueryFilter,
-        mut callback: impl FnMut(&ContactPoint, Dir2) -> bool,
-    ) {
-        let expanded_aabb = shape
-            .aabb(shape_position, shape_rotation)
-            .grow(Vector::splat(prediction_distance));
-        let aabb_intersections = self
-            .query_pipeline
-            .aabb_intersections_with_aabb(expanded_aabb);
-        for intersection_entity in aabb_intersections {
-            let Ok((intersection_collider, intersection_pos, intersection_rot, layers)) =
-                self.colliders.get(intersection_entity)
-            else {
-                continue;
-            };
-            let layers = layers.copied().unwrap_or_default();
-            if !filter.test(intersection_entity, layers) {
-                continue;
-            }
-            let mut manifolds = Vec::new();
-            contact_manifolds(
-                shape,
-                shape_position,
-                shape_rotation,
-                intersection_collider,
-                *intersection_pos,
-                *intersection_rot,
-                prediction_distance,
-                &mut manifolds,
-            );
-            for manifold in manifolds {
-                let Some(deepest) = manifold.find_deepest_contact() else {
-                    continue;
-                };
-
-                let normal = Dir2::new_unchecked(-manifold.normal.f32());
-                callback(deepest, normal);
-            }
-        }
-    }
-
-    /// Manual version of [`MoveAndSlide::depenetrate_all`].
-    ///
-    /// Moves a collider so that it no longer intersects any other collider and keeps a minimum distance of [`DepenetrationConfig::skin_width`] to all.
-    /// The intersections should be provided as a list of contact plane normals and penetration distances, which can be obtained via [`MoveAndSlide::intersections`].
-    ///
-    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit, until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`]
-    /// or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`]. If the max iterations were reached before the error was below the threshold,
-    /// the current best attempt is returned, in which case the collider may still be intersecting with other colliders.
-    ///
-    /// # Arguments
-    ///
-    /// - `config`: A [`DepenetrationConfig`] that determines the behavior of the depenetration. [`DepenetrationConfig::default()`] should be a good start for most cases.
-    ///
-    /// # Returns
-    ///
-    /// A displacement vector that can be added to the `shape_position` to resolve the intersections,
-    /// or the best attempt if the max iterations were reached before a solution was found.
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use bevy::prelude::*;
-    /// fn depenetrate_player_manually(
-    ///     player: Single<(Entity, &Collider, &mut Transform)>,
-    ///     move_and_slide: MoveAndSlide,
-    ///     time: Res<Time>
-    /// ) {
-    ///     let (entity, collider, mut transform) = player.into_inner();
-    ///     let filter = SpatialQueryFilter::from_excluded_entities([entity]);
-    ///     let config = DepenetrationConfig::default();
-    ///
-    ///     let mut intersections = Vec::new();
-    ///     move_and_slide.intersections(
-    ///         collider,
-    #[must_use]
-    pub fn depenetrate(
-        &self,
-        config: &DepenetrationConfig,
-        intersections: &[(Dir2, Scalar)],
-    ) -> Vector {
-        if intersections.is_empty() {
-            return Vector::ZERO;
-        }
-
-        let mut fixup = Vector::ZERO;
-        for _ in 0..config.depenetration_iterations {
-            let mut total_error = 0.0;
-            for (normal, dist) in intersections {
-                if *dist > self.length_unit.0 * config.penetration_rejection_threshold {
-                    continue;
-                }
-                let normal = normal.adjust_precision();
-                let error = (dist - fixup.dot(normal)).max(0.0);
-                total_error += error;
-                fixup += error * normal;
-            }
-            if total_error < self.length_unit.0 * config.max_depenetration_error {
-                break;
-            }
-        }
-        fixup
-    }
-
-    /// Projects input velocity `v` onto the convex cone defined by the provided contact `normals`.
-    /// This ensures that `velocity` does not point into any of the given `planes`, but along them.
-    ///
-    /// Returns the projected velocity. If there are no planes, the velocity is returned unchanged.
-    /// The returned vector will have some numerical errors. For example, if your vertical velocity was 0.0 before calling
-    /// this method on a ground plane intersection, the returned velocity might point very slightly upwards.
-    /// As such, it is recommended to set invariants such as `velocity.y = 0.0;` again after calling this method.
-    ///
-    /// Often used after [`MoveAndSlide::cast_move`] to ensure a character moved that way does not try to continue moving into colliding geometry.
-    /// See that method for example usage.
-    pub fn project_velocity(v: Vector, normals: &[Dir2]) -> Vector {
-        // Case 1: Check if v is inside the cone
-        if normals
-            .iter()
-            .all(|n| v.dot(n.adjust_precision()) >= -DOT_EPSILON)
-        {
-            return v;
-        }
-
-        // Best candidate so far
-        let mut best_projection = Vector::ZERO;
-        let mut best_distance_sq = Scalar::INFINITY;
-
-        // Helper to test halfspace validity
-        let is_valid = |projection: Vector| {
-            normals
-                .iter()
-                .all(|n| projection.dot(n.adjust_precision()) >= -DOT_EPSILON)
-        };
-
-        // Case 2a: Face projections (single-plane active set)
-        for n in normals {
-            let n = n.adjust_precision();
-            let v_dot_n = v.dot(n);
-            if v_dot_n < 0.0 {
-                // Project v onto the plane defined by n:
-                // projection = v - (v·n) n
-                let projection = v - v_dot_n * n;
-
-                // Check if better than previous best and valid
-                let distance_sq = v.distance_squared(projection);
-                if distance_sq < best_distance_sq && is_valid(projection) {
-                    best_distance_sq = distance_sq;
-                    best_projection = projection;
-                }
-            }
-        }
-
-        // Case 3: If no candidate is found, the projection is at the apex (the origin)
-        if best_distance_sq.is_infinite() {
-            Vector::ZERO
-        } else {
-            best_projection
-        }
-    }
-}
-
-/// Needed to not accidentally explode when `n.dot(dir)` happens to be very close to zero.
-const DOT_EPSILON: Scalar = 0.005;
-
-/// Data related to a hit during a [`MoveAndSlide::move_and_slide`].
-#[derive(Debug, PartialEq)]
-pub struct MoveAndSlideHitData<'a> {
-    /// The entity of the collider that was hit by the shape.
-    pub entity: Entity,
-
-    /// The maximum distance that is safe to move in the given direction so that the collider still keeps a distance of `skin_width` to the other colliders.
-    /// Is 0.0 when
-    /// - The collider started off intersecting another collider.
-    /// - The collider is moving toward another collider that is already closer than `skin_width`.
-    ///
-    /// If you want to know the real distance to the next collision, use [`Self::collision_distance`].
-    pub distance: Scalar,
-
-    /// The hit point point on the shape that was hit, expressed in world space.
-    pub point: Vector,
-
-    /// The outward surface normal on the hit shape at `point`, expressed in world space.
-    pub normal: &'a mut Dir2,
-
-    /// The position of the collider at the point of the move and slide iteration.
-    pub position: &'a mut Vector,
-
-    /// The velocity of the collider at the point of the move and slide iteration.
-    pub velocity: &'a mut Vector,
-
-    /// The raw distance to the next collision, not respecting skin width.
-    /// To move the shape, use [`Self::distance`] instead.
-    #[doc(alias = "time_of_impact")]
-    pub collision_distance: Scalar,
-}
-
-impl<'a> MoveAndSlideHitData<'a> {
-    /// Whether the collider started off already intersecting another collider when it was cast.
-    /// Note that this will be `false` if the collider was closer than `skin_width`, but not physically intersecting.
-    pub fn intersects(&self) -> bool {
-        self.collision_distance == 0.0
-    }
-}
-
-/// Data related to a hit during a [`MoveAndSlide::cast_move`].
-#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
-#[reflect(Serialize, Deserialize)]
-#[reflect(Debug, PartialEq)]
-pub struct MoveHitData {
-    /// The entity of the collider that was hit by the shape.
-    pub entity: Entity,
-
-    /// The maximum distance that is safe to move in the given direction so that the collider still keeps a distance of `skin_width` to the other colliders.
-    /// Is 0.0 when
-    /// - The collider started off intersecting another collider.
-    /// - The collider is moving toward another collider that is already closer than `skin_width`.
-    ///
-    /// If you want to know the real distance to the next collision, use [`Self::collision_distance`].
-    #[doc(alias = "time_of_impact")]
-    pub distance: Scalar,
-
-    /// The closest point on the shape that was hit, expressed in world space.
-    ///
-    /// If the shapes are penetrating or the target distance is greater than zero,
-    /// this will be different from `point2`.
-    pub point1: Vector,
-
-    /// The closest point on the shape that was cast, expressed in world space.
-    ///
-    /// If the shapes are penetrating or the target distance is greater than zero,
-    /// this will be different from `point1`.
-    pub point2: Vector,
-
-    /// The outward surface normal on the hit shape at `point1`, expressed in world space.
-    pub normal1: Vector,
-
-    /// The outward surface normal on the cast shape at `point2`, expressed in world space.
-    pub normal2: Vector,
-
-    /// The raw distance to the next collision, not respecting skin width.
-    /// To move the shape, use [`Self::distance`] instead.
-    #[doc(alias = "time_of_impact")]
-    pub collision_distance: Scalar,
-}
-
-impl MoveHitData {
-    /// Whether the collider started off already intersecting another collider when it was cast.
-    /// Note that this will be `false` if the collider was closer than `skin_width`, but not physically intersecting.
-    pub fn intersects(self) -> bool {
-        self.collision_distance == 0.0
-    }
-}
-
-/// Configuration for a [`MoveAndSlide::move_and_slide`].
-#[derive(Clone, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
-#[reflect(Debug, PartialEq, Serialize, Deserialize)]
-pub struct MoveAndSlideConfig {
-    /// How many iterations to use when moving the character. A single iteration consists of
-    /// - Performing depenetration
-    /// - Moving the character as far as possible in the desired velocity
-    /// - Modifying the velocity to slide along any colliding planes
-    pub move_and_slide_iterations: usize,
-
-    /// How many iterations to use when performing depenetration.
-    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
-    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
-    ///
-    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
-    pub depenetration_iterations: usize,
-
-    /// The target error to achieve when performing depenetration.
-    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
-    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
-    ///
-    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
-    pub max_depenetration_error: Scalar,
-
-    /// The maximum penetration depth that is allowed for a contact to be resolved during depenetration.
-    ///
-    /// This is used to reject invalid contacts that have an excessively high penetration depth,
-    /// which can lead to clipping through geometry. This may be removed in the future once the
-    /// collision errors in the underlying collision detection system are fixed.
-    pub penetration_rejection_threshold: Scalar,
-
-    /// A minimal distance to always keep between the collider and any other colliders.
-    /// This is here to ensure that the collider never intersects anything, even when numeric errors accumulate.
-    /// Set this to a very small value.
-    ///
-    /// Increase the value if you notice your character getting stuck in geometry.
-    /// Decrease it when you notice jittering, especially around V-shaped walls.
-    pub skin_width: Scalar,
-
-    /// The initial planes to consider for a move-and-slide operation. This will be expanded during the algorithm with
-    /// the colliding planes, but you can also initialize it with some planes you want to make sure the algorithm will never move against.
-    ///
-    /// A good use-case for this is adding the ground plane when a character controller is standing or walking on the ground.
-    pub planes: Vec<Dir2>,
-
-    /// The maximum number of planes to solve while performing move-and-slide. If the collided planes exceed this number, the move is aborted and the velocity is set to zero.
-    /// Realistically, this will probably never be reached, unless you have very exotic geometry and very high velocity.
-    pub max_planes: usize,
-}
-
-/// Configuration for a [`MoveAndSlide::depenetrate`].
-#[derive(Clone, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
-#[reflect(Debug, PartialEq, Serialize, Deserialize)]
-pub struct DepenetrationConfig {
-    /// How many iterations to use when performing depenetration.
-    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
-    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
-    pub depenetration_iterations: usize,
-
-    /// The target error to achieve when performing depenetration.
-    /// Depenetration is an iterative process that solves penetrations for all planes bit-by-bit,
-    /// until we either reached [`MoveAndSlideConfig::move_and_slide_iterations`] or the accumulated error is less than [`MoveAndSlideConfig::max_depenetration_error`].
-    ///
-    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
-    pub max_depenetration_error: Scalar,
-
-    /// The maximum penetration depth that is allowed for a contact to be resolved during depenetration.
-    ///
-    /// This is used to reject invalid contacts that have an excessively high penetration depth,
-    /// which can lead to clipping through geometry. This may be removed in the future once the
-    /// collision errors in the underlying collision detection system are fixed.
-    ///
-    /// This is implicitly scaled by the [`PhysicsLengthUnit`].
-    pub penetration_rejection_threshold: Scalar,
-
-    /// A minimal distance to always keep between the collider and any other colliders.
-    /// This is here to ensure that the collider never intersects anything, even when numeric errors accumulate.
-    /// Set this to a very small value.
-    ///
-    /// Increase the value if you notice your character getting stuck in geometry.
-    /// Decrease it when you notice jittering, especially around V-shaped walls.
-    pub skin_width: Scalar,
-}
-
-impl Default for DepenetrationConfig {
-    fn default() -> Self {
-        Self {
-            depenetration_iterations: 16,
-            max_depenetration_error: 0.0001,
-            penetration_rejection_threshold: 0.5,
-            skin_width: 0.002,
-        }
-    }
-}
-
-impl From<&MoveAndSlideConfig> for DepenetrationConfig {
-    fn from(config: &MoveAndSlideConfig) -> Self {
-        Self {
-            depenetration_iterations: config.depenetration_iterations,
-            max_depenetration_error: config.max_depenetration_error,
-            penetration_rejection_threshold: config.penetration_rejection_threshold,
-            skin_width: config.skin_width,
-        }
-    }
-}
-
-/// Output from a [`MoveAndSlide::move_and_slide`].
-#[derive(Clone, Copy, Debug, PartialEq, Reflect, serde::Deserialize, serde::Serialize)]
-#[reflect(Debug, PartialEq, Serialize, Deserialize)]
-pub struct MoveAndSlideOutput {
-    /// The final position of the character after move and slide. Set your [`Transform::translation`] to this value.
-    pub position: Vector,
-
-    /// The final velocity of the character after move and slide. This corresponds to the actual velocity, not the wished velocity.
-    /// For example, if the character is trying to move to the right and there's a ramp on its path, this vector will point up the ramp.
-    /// It is useful to store this value and apply your wish movement vectors, friction, gravity, etc. on it before handing it to [`MoveAndSlide::move_and_slide`] as the input `velocity`.
-    /// You can also ignore this value if you don't wish to preserve momentum along colliding planes.
-    ///
-    /// Do *not* set [`LinearVelocity`] to this value, as that would apply the movement twice and cause intersections. Instead, set [`Transform::translation`] to [`MoveAndSlideOutput::position`].
-    pub projected_velocity: Vector,
-}
-
-impl Default for MoveAndSlideConfig {
-    fn default() -> Self {
-        let default_depen_cfg = DepenetrationConfig::default();
-        Self {
-            move_and_slide_iterations: 4,
-            depenetration_iterations: default_depen_cfg.depenetration_iterations,
-            max_depenetration_error: default_depen_cfg.max_depenetration_error,
-            penetration_rejection_threshold: default_depen_cfg.penetration_rejection_threshold,
-            skin_width: default_depen_cfg.skin_width * 5.0,
-            planes: Vec::new(),
-            max_planes: 20,
-        }
-    }
-}
@@ -0,0 +1,163 @@
+//! Hitboxes and hurtboxes, the shared base for stomps, projectiles, boss attacks and hazards.
+//!
+//! Both are overlap-only boxes around the entity's transform and never push anything around.
+//! Every fixed tick each active [`Hitbox`] is checked against the [`Hurtbox`]es of other teams, and
+//! each new overlap triggers a [`HitLanded`]. What a hit actually does is up to its observers.
+
+use crate::physics::perform_move_and_slide;
+use bevy::prelude::*;
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+pub enum Team {
+    #[default]
+    Player,
+    Enemy,
+    /// spikes, lava and the like, hurt every team
+    Hazard,
+}
+
+impl Team {
+    pub fn hurts(self, other: Team) -> bool {
+        self == Team::Hazard || self != other
+    }
+}
+
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Hitbox {
+    pub team: Team,
+    pub damage: u32,
+    pub offset: Vec2,
+    pub half_size: Vec2,
+    /// fixed ticks (counted from when the hitbox was added) during which it can hit, always active if `None`
+    pub window: Option<Range<u32>>,
+    age: u32,
+    /// targets overlapped last tick, they only get hit again after leaving
+    #[reflect(ignore)]
+    hit: Vec<Entity>,
+}
+
+impl Hitbox {
+    pub fn new(team: Team, damage: u32, half_size: Vec2) -> Self {
+        Self {
+            team,
+            damage,
+            half_size,
+            ..default()
+        }
+    }
+
+    pub fn with_offset(mut self, offset: Vec2) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_window(mut self, window: Range<u32>) -> Self {
+        self.window = Some(window);
+        self
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.window
+            .as_ref()
+            .is_none_or(|window| window.contains(&self.age))
+    }
+
+    /// Fixed ticks since the hitbox was added or rearmed, only counted when it has a window.
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+
+    /// Restarts the active window, for attacks that repeat.
+    pub fn rearm(&mut self) {
+        self.age = 0;
+    }
+}
+
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Hurtbox {
+    pub team: Team,
+    pub offset: Vec2,
+    pub half_size: Vec2,
+}
+
+impl Hurtbox {
+    pub fn new(team: Team, half_size: Vec2) -> Self {
+        Self {
+            team,
+            half_size,
+            ..default()
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct HitLanded {
+    pub attacker: Entity,
+    pub target: Entity,
+    pub damage: u32,
+    /// from the hitbox center to the hurtbox center
+    pub direction: Vec2,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Hitbox>()
+        .register_type::<Hurtbox>()
+        .add_systems(
+            FixedUpdate,
+            (resolve_hits, age_hitboxes)
+                .chain()
+                .after(perform_move_and_slide),
+        );
+}
+
+fn overlaps(a: Vec2, a_half: Vec2, b: Vec2, b_half: Vec2) -> bool {
+    let offset = (a - b).abs();
+    offset.x <= a_half.x + b_half.x && offset.y <= a_half.y + b_half.y
+}
+
+fn resolve_hits(
+    mut commands: Commands,
+    mut hitboxes: Query<(Entity, &mut Hitbox, &GlobalTransform)>,
+    hurtboxes: Query<(Entity, &Hurtbox, &GlobalTransform)>,
+) {
+    for (attacker, mut hitbox, hit_xf) in hitboxes.iter_mut() {
+        if !hitbox.is_active() {
+            hitbox.hit.clear();
+            continue;
+        }
+        let hit_center = hit_xf.translation().xy() + hitbox.offset;
+        let mut overlapping = Vec::new();
+        for (target, hurtbox, hurt_xf) in hurtboxes.iter() {
+            if target == attacker || !hitbox.team.hurts(hurtbox.team) {
+                continue;
+            }
+            let hurt_center = hurt_xf.translation().xy() + hurtbox.offset;
+            if !overlaps(hit_center, hitbox.half_size, hurt_center, hurtbox.half_size) {
+                continue;
+            }
+            overlapping.push(target);
+            if hitbox.hit.contains(&target) {
+                continue;
+            }
+            commands.trigger(HitLanded {
+                attacker,
+                target,
+                damage: hitbox.damage,
+                direction: (hurt_center - hit_center).normalize_or_zero(),
+            });
+        }
+        hitbox.hit = overlapping;
+    }
+}
+
+fn age_hitboxes(mut hitboxes: Query<&mut Hitbox>) {
+    for mut hitbox in hitboxes.iter_mut() {
+        //don't bother counting for hitboxes that are always on
+        if hitbox.window.is_some() {
+            hitbox.age = hitbox.age.saturating_add(1);
+        }
+    }
+}
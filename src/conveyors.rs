@@ -0,0 +1,52 @@
+//! Conveyor belts and treadmills placed in LDtk.
+//!
+//! A `Conveyor` entity is a solid block the size of the entity that carries whatever stands on it
+//! along with its [`SurfaceVelocity`], set by its `speed` field in pixels per second, negative to
+//! the left. An optional `vertical` bool runs it up instead, for treadmills on walls.
+
+use crate::physics::SurfaceVelocity;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+const DEFAULT_SPEED: f32 = 60.0;
+
+#[derive(Bundle, Default)]
+pub struct ConveyorBody {
+    collider: Collider,
+    rb: RigidBody,
+    surface: SurfaceVelocity,
+}
+
+impl From<&EntityInstance> for ConveyorBody {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let speed = entity_instance
+            .get_float_field("speed")
+            .copied()
+            .unwrap_or(DEFAULT_SPEED);
+        let vertical = entity_instance
+            .get_bool_field("vertical")
+            .copied()
+            .unwrap_or(false);
+        Self {
+            collider: Collider::rectangle(
+                entity_instance.width as f32,
+                entity_instance.height as f32,
+            ),
+            rb: RigidBody::Static,
+            surface: SurfaceVelocity(if vertical { vec2(0.0, speed) } else { vec2(speed, 0.0) }),
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct ConveyorBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    body: ConveyorBody,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<ConveyorBundle>("Conveyor");
+}
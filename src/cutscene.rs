@@ -0,0 +1,261 @@
+//! Camera paths for cutscenes and level intros.
+//!
+//! A path is a list of [`CameraKeyframe`]s the camera travels through, zooming as it goes, before
+//! blending back to following the player. It moves the camera through
+//! [`OffsetChannel::CutscenePan`], so clamping and shake still apply on top, and the player stands
+//! still until it's over.
+//!
+//! A level gets an intro from `CameraKeyframe` entities placed in LDtk, ordered by their `order`
+//! field and with optional `zoom`, `travel` and `hold` fields, or otherwise from its entry in
+//! `assets/cutscenes.ron`. Anything else can play a path with [`PlayCameraPath`].
+
+use crate::app_config::AppConfig;
+use crate::camera::{follow_targets, CameraOffsets, FollowCenter, FollowTargets, OffsetChannel};
+use crate::levels::{Campaign, LevelProgress};
+use crate::locomotion::LocomotionDriver;
+use crate::mario::Char;
+use crate::physics::check_grounded;
+use crate::screens::LevelReadiness;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+const CUTSCENES_PATH: &str = "assets/cutscenes.ron";
+
+#[derive(Debug, Clone, Copy, Deserialize, Reflect)]
+#[serde(default)]
+pub struct CameraKeyframe {
+    pub position: Vec2,
+    /// relative to the usual zoom, smaller is closer
+    pub zoom: f32,
+    /// seconds to get here from the keyframe before, or from wherever the camera was
+    pub travel: f32,
+    /// seconds to stay here before moving on
+    pub hold: f32,
+}
+
+impl Default for CameraKeyframe {
+    fn default() -> Self {
+        Self {
+            position: Vec2::ZERO,
+            zoom: 1.0,
+            travel: 1.0,
+            hold: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Reflect)]
+#[serde(default)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+    /// seconds to blend back to following the player after the last keyframe
+    pub blend_out: f32,
+}
+
+impl CameraPath {
+    /// Seconds until the last keyframe is done, not counting the blend out.
+    pub fn duration(&self) -> f32 {
+        self.keyframes.iter().map(|key| key.travel + key.hold).sum()
+    }
+
+    /// Position and zoom `time` seconds in, for a camera that started at `start`.
+    pub fn sample(&self, start: (Vec2, f32), mut time: f32) -> (Vec2, f32) {
+        let mut from = start;
+        for key in &self.keyframes {
+            let to = (key.position, key.zoom);
+            if time < key.travel {
+                let t = time / key.travel;
+                let t = t * t * (3.0 - 2.0 * t);
+                return (from.0.lerp(to.0, t), from.1.lerp(to.1, t));
+            }
+            time -= key.travel;
+            if time < key.hold {
+                return to;
+            }
+            time -= key.hold;
+            from = to;
+        }
+        from
+    }
+}
+
+/// `assets/cutscenes.ron`, level intros by level identifier.
+#[derive(Resource, Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct Cutscenes(pub HashMap<String, CameraPath>);
+
+impl Cutscenes {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
+
+/// Plays a path on every following camera, replacing whatever path it was playing.
+#[derive(Event, Clone, Debug)]
+pub struct PlayCameraPath(pub CameraPath);
+
+/// The camera is on a path right now.
+#[derive(Component, Clone, Debug)]
+pub struct PlayingCameraPath {
+    path: CameraPath,
+    elapsed: f32,
+    /// where the camera was when the path started
+    start: Option<(Vec2, f32)>,
+}
+
+/// A keyframe of the intro of the level it's in, placed in LDtk.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+pub struct IntroKeyframe {
+    pub order: i32,
+    pub keyframe: CameraKeyframe,
+}
+
+impl From<&EntityInstance> for IntroKeyframe {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let default = CameraKeyframe::default();
+        let float = |name: &str, default: f32| {
+            entity_instance
+                .get_float_field(name)
+                .copied()
+                .unwrap_or(default)
+        };
+        Self {
+            order: entity_instance.get_int_field("order").copied().unwrap_or(0),
+            keyframe: CameraKeyframe {
+                zoom: float("zoom", default.zoom),
+                travel: float("travel", default.travel),
+                hold: float("hold", default.hold),
+                ..default
+            },
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct IntroKeyframeBundle {
+    #[from_entity_instance]
+    keyframe: IntroKeyframe,
+}
+
+/// Run condition for while a camera is on a path.
+pub fn camera_path_playing(paths: Query<(), With<PlayingCameraPath>>) -> bool {
+    !paths.is_empty()
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let cutscenes = Cutscenes::read(CUTSCENES_PATH).unwrap_or_else(|e| {
+        info!("no level intros from {CUTSCENES_PATH}: {e}");
+        Cutscenes::default()
+    });
+    app.register_type::<IntroKeyframe>()
+        .register_ldtk_entity::<IntroKeyframeBundle>("CameraKeyframe")
+        .insert_resource(cutscenes)
+        .add_systems(Update, start_intro)
+        .add_systems(PostUpdate, play_camera_paths.before(follow_targets))
+        .add_systems(
+            FixedUpdate,
+            hold_player
+                .before(check_grounded)
+                .run_if(camera_path_playing),
+        )
+        .add_observer(play_camera_path);
+}
+
+fn play_camera_path(
+    play: On<PlayCameraPath>,
+    mut commands: Commands,
+    cameras: Query<Entity, With<FollowTargets>>,
+) {
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(PlayingCameraPath {
+            path: play.0.clone(),
+            elapsed: 0.0,
+            start: None,
+        });
+    }
+}
+
+/// Plays the intro of a level once it's ready, only the first time it's entered in a row so
+/// respawning doesn't show it again.
+fn start_intro(
+    mut commands: Commands,
+    readiness: Res<LevelReadiness>,
+    campaign: Res<Campaign>,
+    progress: Res<LevelProgress>,
+    cutscenes: Res<Cutscenes>,
+    keyframes: Query<(&IntroKeyframe, &GlobalTransform)>,
+    mut was_ready: Local<bool>,
+    mut last_intro: Local<Option<usize>>,
+) {
+    let ready = readiness.is_ready();
+    let became_ready = ready && !*was_ready;
+    *was_ready = ready;
+    if !became_ready || *last_intro == Some(progress.current) {
+        return;
+    }
+    *last_intro = Some(progress.current);
+    let mut placed = keyframes.iter().collect::<Vec<_>>();
+    placed.sort_by_key(|(key, _)| key.order);
+    let path = if placed.is_empty() {
+        let Some(level) = campaign.level(progress.current) else {
+            return;
+        };
+        let Some(path) = cutscenes.0.get(level.identifier) else {
+            return;
+        };
+        path.clone()
+    } else {
+        CameraPath {
+            keyframes: placed
+                .into_iter()
+                .map(|(key, transform)| CameraKeyframe {
+                    position: transform.translation().xy(),
+                    ..key.keyframe
+                })
+                .collect(),
+            blend_out: 1.0,
+        }
+    };
+    commands.trigger(PlayCameraPath(path));
+}
+
+fn play_camera_paths(
+    mut commands: Commands,
+    time: Res<Time>,
+    config: Res<AppConfig>,
+    mut cameras: Query<(
+        Entity,
+        &mut PlayingCameraPath,
+        &FollowCenter,
+        &mut CameraOffsets,
+        &mut Projection,
+    )>,
+) {
+    for (entity, mut playing, center, mut offsets, mut projection) in cameras.iter_mut() {
+        let start = *playing.start.get_or_insert((center.0, 1.0));
+        playing.elapsed += time.delta_secs();
+        let (position, zoom) = playing.path.sample(start, playing.elapsed);
+        //after the last keyframe the player gets the camera back bit by bit
+        let past_end = playing.elapsed - playing.path.duration();
+        let weight = 1.0 - (past_end / playing.path.blend_out.max(f32::EPSILON)).clamp(0.0, 1.0);
+        offsets.set(OffsetChannel::CutscenePan, position - center.0, weight);
+        if let Projection::Orthographic(orthographic) = &mut *projection {
+            orthographic.scale = config.projection_scale * 1.0_f32.lerp(zoom, weight);
+        }
+        if weight == 0.0 {
+            offsets.clear(OffsetChannel::CutscenePan);
+            commands.entity(entity).remove::<PlayingCameraPath>();
+        }
+    }
+}
+
+fn hold_player(mut drivers: Query<&mut LocomotionDriver, With<Char>>) {
+    for mut driver in drivers.iter_mut() {
+        *driver = LocomotionDriver::default();
+    }
+}
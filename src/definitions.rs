@@ -0,0 +1,491 @@
+//! Entity definitions read from the RON files in `assets/entities/<identifier>/`, or from a mod
+//! that has its own copy.
+//!
+//! A definition can start from another entity's file of the same name with
+//! `extends: "base_walker"`. Its own fields replace the base's, and fields that are plain
+//! `(field: value)` structs on both sides, named or not, are merged field by field the same way.
+//! Tuples and `Some` are merged element by element, and the `#![enable(..)]` extensions of both
+//! files are kept. Anything else, like lists or a different enum variant, is replaced whole.
+
+use crate::mods::mod_override;
+use crate::ui::Toast;
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::fs::read_to_string;
+use std::marker::PhantomData;
+use std::path::Path;
+
+/// Where part of an entity was read from, and why it couldn't be if loading failed.
+///
+/// `T` is the bundle that was loaded, so one entity can carry a definition per file.
+#[derive(Component, Debug)]
+pub struct Definition<T> {
+    pub path: String,
+    pub error: Option<String>,
+    //for generics
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Definition<T> {
+    pub fn new(path: String, error: Option<String>) -> Self {
+        Self {
+            path,
+            error,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Definition<T> {
+    fn default() -> Self {
+        Self::new(String::new(), None)
+    }
+}
+
+impl<T> Clone for Definition<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.path.clone(), self.error.clone())
+    }
+}
+
+/// Definition files that carry a `version` field.
+///
+/// New fields should be `#[serde(default)]` so files written before them still parse,
+/// anything that can't be expressed with defaults belongs in [`Migrate::migrate`].
+pub trait Migrate {
+    /// The version files are written with today.
+    const VERSION: u32;
+
+    /// Brings a definition read from a file of an older `version` up to date.
+    fn migrate(&mut self, version: u32) {}
+}
+
+/// Just the version of a definition file, files from before versioning are version 0.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct VersionHeader {
+    version: u32,
+    /// identifier of the entity this one starts from
+    extends: Option<String>,
+}
+
+/// How many bases deep a definition may go, so a loop of them errors instead of hanging.
+const MAX_EXTENDS_DEPTH: usize = 8;
+
+pub fn definition_path(identifier: &str, file: &str) -> String {
+    let path = format!("entities/{}/{file}", identifier.to_lowercase());
+    match mod_override(&path) {
+        Some(modded) => modded.to_string_lossy().into_owned(),
+        None => format!("assets/{path}"),
+    }
+}
+
+/// Reads and parses a definition file.
+/// The error names the file and, through ron, the position, field and expected type.
+pub fn read_definition<T: DeserializeOwned + Migrate>(path: &str) -> Result<T, String> {
+    let str = read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let str = resolve_extends(path, str, 0)?;
+    let VersionHeader { version, .. } =
+        ron::de::from_str(&str).map_err(|e| format!("{path}:{e}"))?;
+    if version > T::VERSION {
+        warn!(
+            "{path} is version {version} but only {} is known, loading it anyway",
+            T::VERSION
+        );
+    }
+    let mut value: T = ron::de::from_str(&str).map_err(|e| format!("{path}:{e}"))?;
+    if version < T::VERSION {
+        info!("migrating {path} from version {version} to {}", T::VERSION);
+        value.migrate(version);
+    }
+    Ok(value)
+}
+
+/// Merges the bases of a definition into it, see the module docs.
+fn resolve_extends(path: &str, str: String, depth: usize) -> Result<String, String> {
+    let VersionHeader { extends, .. } =
+        ron::de::from_str(&str).map_err(|e| format!("{path}:{e}"))?;
+    let Some(base) = extends else {
+        return Ok(str);
+    };
+    if depth >= MAX_EXTENDS_DEPTH {
+        return Err(format!("{path}: extends goes more than {MAX_EXTENDS_DEPTH} deep"));
+    }
+    let file = Path::new(path)
+        .file_name()
+        .map(|file| file.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let base_path = definition_path(&base, &file);
+    let base_str = read_to_string(&base_path).map_err(|e| format!("{base_path}: {e}"))?;
+    let base_str = resolve_extends(&base_path, base_str, depth + 1)?;
+    let (base_str, str) = (strip_comments(&base_str), strip_comments(&str));
+    let (base_header, base_value) = merge_ron::split_header(&base_str);
+    let (header, value) = merge_ron::split_header(&str);
+    let merged = merge_ron::merge(base_value, value)
+        .map(|merged| merge_ron::without_field(&merged, "extends"))
+        .ok_or_else(|| format!("{path}: it and {base_path} need to be (field: value) structs"))?;
+    Ok(format!("{base_header}\n{header}\n{merged}"))
+}
+
+/// Loads `file` for the entity `identifier`, falling back to the default value on failure.
+/// The returned [`Definition`] records the failure so the entity can be rejected once spawned.
+pub fn load_definition<T: DeserializeOwned + Default + Migrate, B>(
+    identifier: &str,
+    file: &str,
+) -> (T, Definition<B>) {
+    let path = definition_path(identifier, file);
+    info!("Looking at path: {path}");
+    match read_definition(&path) {
+        Ok(value) => (value, Definition::new(path, None)),
+        Err(e) => {
+            warn!("could not load entity definition {e}");
+            (T::default(), Definition::new(path, Some(e)))
+        }
+    }
+}
+
+/// Reports entities that were built from a broken definition.
+/// In dev builds they are despawned instead of running around with default stats.
+pub fn reject_invalid_definition<T: Send + Sync + 'static>(
+    add: On<Add, Definition<T>>,
+    query: Query<&Definition<T>>,
+    mut commands: Commands,
+) {
+    let Ok(Definition {
+        error: Some(error), ..
+    }) = query.get(add.entity)
+    else {
+        return;
+    };
+    commands.trigger(Toast(error.clone()));
+    #[cfg(feature = "dev")]
+    commands.entity(add.entity).try_despawn();
+}
+
+/// Drops `//` and `/* */` comments outside of strings and chars, so they don't stick to the
+/// field after.
+fn strip_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        if let Some(closing) = quote {
+            out.push(c);
+            match c {
+                '\\' => out.extend(chars.next()),
+                _ if c == closing => quote = None,
+                _ => {}
+            }
+            continue;
+        }
+        match (c, chars.peek()) {
+            ('/', Some('/')) => while chars.next_if(|&c| c != '\n').is_some() {},
+            ('/', Some('*')) => {
+                chars.next();
+                let mut last = ' ';
+                for c in chars.by_ref() {
+                    if last == '*' && c == '/' {
+                        break;
+                    }
+                    last = c;
+                }
+                out.push(' ');
+            }
+            _ => {
+                if c == '"' || c == '\'' {
+                    quote = Some(c);
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+/// Field by field merging of RON structs, working on the text so enums survive untouched.
+mod merge_ron {
+    /// Characters of `text` outside of strings and chars, with the bracket depth after each.
+    fn outside_strings(text: &str) -> impl Iterator<Item = (usize, char, i32)> + '_ {
+        let (mut depth, mut quote, mut escaped) = (0, None, false);
+        text.char_indices().filter_map(move |(i, c)| {
+            if let Some(closing) = quote {
+                match c {
+                    _ if escaped => escaped = false,
+                    '\\' => escaped = true,
+                    _ if c == closing => quote = None,
+                    _ => {}
+                }
+                return None;
+            }
+            match c {
+                '"' | '\'' => {
+                    quote = Some(c);
+                    return None;
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' => depth -= 1,
+                _ => {}
+            }
+            Some((i, c, depth))
+        })
+    }
+
+    /// Byte offsets of `separator` in `text` that are outside of brackets and strings.
+    fn top_level(text: &str, separator: char) -> Vec<usize> {
+        outside_strings(text)
+            .filter(|&(_, c, depth)| c == separator && depth == 0)
+            .map(|(i, ..)| i)
+            .collect()
+    }
+
+    /// Byte offset of the bracket closing the one `text` starts with.
+    fn closing(text: &str) -> Option<usize> {
+        if !text.starts_with(['(', '[', '{']) {
+            return None;
+        }
+        outside_strings(text)
+            .find(|&(_, c, depth)| matches!(c, ')' | ']' | '}') && depth == 0)
+            .map(|(i, ..)| i)
+    }
+
+    /// The `#![enable(..)]` attributes at the start of `text`, and the value after them.
+    pub fn split_header(text: &str) -> (&str, &str) {
+        let mut end = 0;
+        loop {
+            let rest = &text[end..];
+            let trimmed = rest.trim_start();
+            let Some(close) = trimmed.strip_prefix("#!").and_then(closing) else {
+                break;
+            };
+            end += rest.len() - trimmed.len() + "#!".len() + close + 1;
+        }
+        (&text[..end], &text[end..])
+    }
+
+    /// A `Name(..)` or `(..)` value split at its top level commas. Struct fields have their
+    /// name, tuple elements don't.
+    struct Group<'a> {
+        name: &'a str,
+        items: Vec<(Option<&'a str>, &'a str)>,
+    }
+
+    impl<'a> Group<'a> {
+        fn parse(text: &'a str) -> Option<Self> {
+            let text = text.trim();
+            let open = text.find('(')?;
+            let name = text[..open].trim();
+            if !is_identifier(name) && !name.is_empty() {
+                return None;
+            }
+            //the bracket that opens has to be the one that closes, not `(a) + (b)`
+            if closing(&text[open..])? != text.len() - open - 1 {
+                return None;
+            }
+            let inner = &text[open + 1..text.len() - 1];
+            let mut items = Vec::new();
+            let mut start = 0;
+            for end in top_level(inner, ',').into_iter().chain([inner.len()]) {
+                let piece = inner[start..end].trim();
+                start = end + 1;
+                if piece.is_empty() {
+                    continue;
+                }
+                let item = match top_level(piece, ':').first() {
+                    Some(&colon) => {
+                        let field = piece[..colon].trim();
+                        if !is_identifier(field) {
+                            return None;
+                        }
+                        (Some(field), piece[colon + 1..].trim())
+                    }
+                    None => (None, piece),
+                };
+                items.push(item);
+            }
+            let fields = items.iter().filter(|(field, _)| field.is_some()).count();
+            if fields != 0 && fields != items.len() {
+                return None;
+            }
+            Some(Self { name, items })
+        }
+
+        fn is_struct(&self) -> bool {
+            self.items.iter().all(|(field, _)| field.is_some())
+        }
+
+        fn is_tuple(&self) -> bool {
+            self.items.iter().all(|(field, _)| field.is_none())
+        }
+    }
+
+    fn is_identifier(text: &str) -> bool {
+        !text.is_empty() && text.chars().all(|c| c.is_alphanumeric() || c == '_')
+    }
+
+    fn join(name: &str, items: &[(Option<&str>, String)]) -> String {
+        let items = items
+            .iter()
+            .map(|(field, value)| match field {
+                Some(field) => format!("{field}: {value}"),
+                None => value.clone(),
+            })
+            .collect::<Vec<_>>();
+        format!("{name}({})", items.join(", "))
+    }
+
+    fn merge_value(base: &str, over: &str) -> String {
+        merge(base, over).unwrap_or_else(|| over.to_string())
+    }
+
+    /// The fields of `over` on top of those of `base`, or its elements for tuples and `Some`.
+    /// `None` unless both are the same kind of group with the same name, if they have one.
+    pub fn merge(base: &str, over: &str) -> Option<String> {
+        let (base, over) = (Group::parse(base)?, Group::parse(over)?);
+        if !base.name.is_empty() && !over.name.is_empty() && base.name != over.name {
+            return None;
+        }
+        let name = if over.name.is_empty() { base.name } else { over.name };
+        let mut merged = base
+            .items
+            .iter()
+            .map(|&(field, value)| (field, value.to_string()))
+            .collect::<Vec<_>>();
+        if base.is_struct() && over.is_struct() {
+            for (field, value) in over.items {
+                match merged.iter_mut().find(|(base_field, _)| *base_field == field) {
+                    Some((_, base_value)) => *base_value = merge_value(base_value, value),
+                    None => merged.push((field, value.to_string())),
+                }
+            }
+        } else if base.is_tuple() && over.is_tuple() {
+            for (i, (_, value)) in over.items.into_iter().enumerate() {
+                match merged.get_mut(i) {
+                    Some((_, base_value)) => *base_value = merge_value(base_value, value),
+                    None => merged.push((None, value.to_string())),
+                }
+            }
+        } else {
+            return None;
+        }
+        Some(join(name, &merged))
+    }
+
+    /// `text` without the field `name`, as is if it isn't a struct.
+    pub fn without_field(text: &str, name: &str) -> String {
+        let Some(group) = Group::parse(text).filter(Group::is_struct) else {
+            return text.to_string();
+        };
+        let kept = group
+            .items
+            .into_iter()
+            .filter(|(field, _)| *field != Some(name))
+            .map(|(field, value)| (field, value.to_string()))
+            .collect::<Vec<_>>();
+        join(group.name, &kept)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::merge_ron::{merge, split_header, without_field};
+    use super::strip_comments;
+
+    fn merged(base: &str, over: &str) -> String {
+        merge(&strip_comments(base), &strip_comments(over)).unwrap()
+    }
+
+    #[test]
+    fn fields_replace_and_add() {
+        assert_eq!(merged("(a: 1, b: 2)", "(b: 3, c: 4)"), "(a: 1, b: 3, c: 4)");
+    }
+
+    #[test]
+    fn nested_structs_merge() {
+        assert_eq!(
+            merged("(jump: (height: 1, time: 2))", "(jump: (time: 3))"),
+            "(jump: (height: 1, time: 3))"
+        );
+        assert_eq!(
+            merged("(jump: Jump(height: 1, time: 2))", "(jump: Jump(time: 3))"),
+            "(jump: Jump(height: 1, time: 3))"
+        );
+    }
+
+    #[test]
+    fn nested_some_merges() {
+        assert_eq!(
+            merged("(climb: Some((speed: 1, side_speed: 2)))", "(climb: Some((speed: 5)))"),
+            "(climb: Some((speed: 5, side_speed: 2)))"
+        );
+        assert_eq!(merged("(climb: Some((speed: 1)))", "(climb: None)"), "(climb: None)");
+    }
+
+    #[test]
+    fn tuples_merge_by_position() {
+        assert_eq!(
+            merged("(offset: ((x: 1, y: 2), 3))", "(offset: ((y: 4), 5))"),
+            "(offset: ((x: 1, y: 4), 5))"
+        );
+    }
+
+    #[test]
+    fn other_values_replace_whole() {
+        assert_eq!(merged("(path: [1, 2, 3])", "(path: [4])"), "(path: [4])");
+        assert_eq!(
+            merged("(ai: Patrol(speed: 1))", "(ai: Chase(range: 2))"),
+            "(ai: Chase(range: 2))"
+        );
+        assert_eq!(merged("(ai: Patrol(speed: 1))", "(ai: Idle)"), "(ai: Idle)");
+    }
+
+    #[test]
+    fn comments_are_dropped() {
+        assert_eq!(
+            merged("(a: 1, // first\n b: 2)", "(/* why */ b: 3)"),
+            "(a: 1, b: 3)"
+        );
+    }
+
+    #[test]
+    fn brackets_and_colons_in_strings() {
+        assert_eq!(
+            merged(r#"(name: "a: (b", tag: 1)"#, r#"(tag: "]), // not a comment")"#),
+            r#"(name: "a: (b", tag: "]), // not a comment")"#
+        );
+        assert_eq!(
+            merged(r#"(name: "say \"(\"", tag: 1)"#, "(tag: 2)"),
+            r#"(name: "say \"(\"", tag: 2)"#
+        );
+    }
+
+    #[test]
+    fn brackets_in_chars() {
+        assert_eq!(
+            merged("(open: '(', close: ')', slash: '/')", "(close: ']')"),
+            "(open: '(', close: ']', slash: '/')"
+        );
+        assert_eq!(merged(r"(quote: '\'', a: 1)", "(a: 2)"), r"(quote: '\'', a: 2)");
+    }
+
+    #[test]
+    fn extension_header_is_split_off() {
+        let text = "#![enable(implicit_some)]\n#![enable(unwrap_newtypes)]\n(a: 1)";
+        let (header, value) = split_header(text);
+        assert_eq!(header, "#![enable(implicit_some)]\n#![enable(unwrap_newtypes)]");
+        assert_eq!(value.trim(), "(a: 1)");
+        assert_eq!(split_header("(a: 1)"), ("", "(a: 1)"));
+    }
+
+    #[test]
+    fn without_field_keeps_the_name() {
+        assert_eq!(without_field("Stats(extends: \"a\", b: 1)", "extends"), "Stats(b: 1)");
+        assert_eq!(without_field("[1, 2]", "extends"), "[1, 2]");
+    }
+
+    #[test]
+    fn non_groups_dont_merge() {
+        assert_eq!(merge("[1]", "(a: 1)"), None);
+        assert_eq!(merge("(a: 1)", "(1, 2)"), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! Every [`HitLanded`] with the fixed tick it happened on, to debug i-frames and hitbox timing.
+//!
+//! The "Combat log" window lists the latest hits and can be filtered down to one entity by index
+//! or name. Hits can also float their damage over the target as a popup.
+
+use super::DevSettings;
+use crate::combat::HitLanded;
+use crate::popups::Popup;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts, EguiPrimaryContextPass};
+use std::collections::VecDeque;
+
+#[derive(Clone, Debug)]
+pub struct CombatLogEntry {
+    /// fixed tick of the hit, i-frames and hitboxes run on those rather than render frames
+    pub tick: u32,
+    pub attacker: Entity,
+    pub attacker_name: String,
+    pub target: Entity,
+    pub target_name: String,
+    pub damage: u32,
+}
+
+#[derive(Resource, Debug)]
+pub struct CombatLog {
+    pub open: bool,
+    pub capacity: usize,
+    pub entries: VecDeque<CombatLogEntry>,
+    /// float the damage of each hit over its target
+    pub damage_numbers: bool,
+    /// entity index or part of a name, empty shows everything
+    filter: String,
+}
+
+impl Default for CombatLog {
+    fn default() -> Self {
+        Self {
+            open: false,
+            capacity: 200,
+            entries: VecDeque::new(),
+            damage_numbers: false,
+            filter: String::new(),
+        }
+    }
+}
+
+/// Fixed updates run since startup.
+#[derive(Resource, Default, Debug)]
+pub struct FixedTick(pub u32);
+
+impl CombatLogEntry {
+    fn matches(&self, filter: &str) -> bool {
+        if filter.is_empty() {
+            return true;
+        }
+        let filter = filter.to_lowercase();
+        [
+            (self.attacker, &self.attacker_name),
+            (self.target, &self.target_name),
+        ]
+        .iter()
+        .any(|(entity, name)| {
+            entity.index().to_string() == filter || name.to_lowercase().contains(&filter)
+        })
+    }
+}
+
+pub(super) fn plugin(app: &mut App) {
+    let open = app.world().resource::<DevSettings>().open.combat_log;
+    app.insert_resource(CombatLog { open, ..default() })
+        .init_resource::<FixedTick>()
+        .add_systems(FixedFirst, count_ticks)
+        .add_systems(Update, toggle_panel)
+        .add_systems(EguiPrimaryContextPass, combat_log_panel)
+        .add_observer(log_hit);
+}
+
+fn toggle_panel(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut log: ResMut<CombatLog>,
+) {
+    if input.just_pressed(settings.keys.combat_log) {
+        log.open = !log.open;
+    }
+}
+
+fn count_ticks(mut tick: ResMut<FixedTick>) {
+    tick.0 = tick.0.wrapping_add(1);
+}
+
+fn name_of(entity: Entity, names: &Query<&Name>) -> String {
+    names
+        .get(entity)
+        .map_or_else(|_| entity.to_string(), |name| name.to_string())
+}
+
+fn log_hit(
+    hit: On<HitLanded>,
+    mut log: ResMut<CombatLog>,
+    mut commands: Commands,
+    names: Query<&Name>,
+    transforms: Query<&GlobalTransform>,
+    tick: Res<FixedTick>,
+) {
+    if log.entries.len() >= log.capacity {
+        log.entries.pop_front();
+    }
+    log.entries.push_back(CombatLogEntry {
+        tick: tick.0,
+        attacker: hit.attacker,
+        attacker_name: name_of(hit.attacker, &names),
+        target: hit.target,
+        target_name: name_of(hit.target, &names),
+        damage: hit.damage,
+    });
+    if log.damage_numbers
+        && let Ok(xf) = transforms.get(hit.target)
+    {
+        commands.trigger(Popup {
+            text: hit.damage.to_string(),
+            position: xf.translation().xy() + Vec2::Y * 8.0,
+        });
+    }
+}
+
+fn combat_log_panel(mut contexts: EguiContexts, mut log: ResMut<CombatLog>) -> Result {
+    if !log.open {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    let mut open = log.open;
+    egui::Window::new("Combat log")
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("filter");
+                ui.text_edit_singleline(&mut log.filter);
+                ui.checkbox(&mut log.damage_numbers, "damage numbers");
+                if ui.button("clear").clicked() {
+                    log.entries.clear();
+                }
+            });
+            ui.separator();
+            let filter = log.filter.trim().to_string();
+            egui::ScrollArea::vertical()
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    let mut last_tick = None;
+                    for entry in log.entries.iter().filter(|entry| entry.matches(&filter)) {
+                        //ticks since the previous hit shown, that's what i-frames are about
+                        let gap = last_tick
+                            .map(|last| format!(" (+{})", entry.tick.wrapping_sub(last)))
+                            .unwrap_or_default();
+                        last_tick = Some(entry.tick);
+                        ui.monospace(format!(
+                            "{:>7}{gap} {} -> {} {}",
+                            entry.tick, entry.attacker_name, entry.target_name, entry.damage
+                        ));
+                    }
+                });
+        });
+    log.open = open;
+    Ok(())
+}
@@ -0,0 +1,186 @@
+//! Shows where players died or fell in the current level, from the playtest records in
+//! [`PlaytestSettings::path`].
+//!
+//! Single deaths and falls are marked with gizmos, toggled with [`DevSettings::gizmos`]. The
+//! heatmap spreads all of them over a grid of cells colored by how many landed nearby, toggled
+//! with its own key, so the spots that take the most attempts stand out.
+
+use super::DevSettings;
+use crate::layers::{RenderLayer, RenderLayerOrder};
+use crate::mario::PlayerDied;
+use crate::playtest::{PlaytestEvent, PlaytestRecord, PlaytestSettings};
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::collections::HashMap;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct DeathGizmos;
+
+/// Recorded deaths and falls in the current level.
+#[derive(Resource, Debug, Default)]
+pub struct RecordedDeaths {
+    pub deaths: Vec<Vec2>,
+    /// where falls left the ground
+    pub falls: Vec<Vec2>,
+}
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Heatmap {
+    pub shown: bool,
+    /// side of a cell in pixels
+    pub cell_size: f32,
+    /// how many cells around a death still count it, falling off with distance
+    pub radius: i32,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self {
+            shown: false,
+            cell_size: 16.0,
+            radius: 2,
+        }
+    }
+}
+
+#[derive(Component)]
+struct HeatmapCell;
+
+const DEATH: Srgba = tailwind::ROSE_500;
+const FALL: Srgba = tailwind::SKY_400;
+const MARKER_SIZE: f32 = 6.0;
+const COLD: Srgba = tailwind::BLUE_500;
+const HOT: Srgba = tailwind::RED_500;
+/// alpha of the hottest cell
+const MAX_ALPHA: f32 = 0.6;
+
+pub(super) fn plugin(app: &mut App) {
+    let shown = app.world().resource::<DevSettings>().open.heatmap;
+    app.init_gizmo_group::<DeathGizmos>()
+        .register_type::<Heatmap>()
+        .init_resource::<RecordedDeaths>()
+        .insert_resource(Heatmap {
+            shown,
+            ..default()
+        })
+        .add_systems(
+            Update,
+            (load_deaths, toggle_heatmap, build_heatmap, draw_deaths).chain(),
+        )
+        .add_observer(add_death);
+}
+
+fn load_deaths(
+    mut level_events: MessageReader<LevelEvent>,
+    settings: Res<PlaytestSettings>,
+    mut recorded: ResMut<RecordedDeaths>,
+) {
+    for event in level_events.read() {
+        let LevelEvent::Spawned(iid) = event else {
+            continue;
+        };
+        let level = iid.to_string();
+        let records = PlaytestRecord::read_all(&settings.path).unwrap_or_default();
+        *recorded = RecordedDeaths::default();
+        for record in records.into_iter().filter(|record| record.level == level) {
+            match record.event {
+                PlaytestEvent::Death { position: [x, y] } => {
+                    recorded.deaths.push(vec2(x as f32, y as f32));
+                }
+                PlaytestEvent::Fall {
+                    position: [x, y], ..
+                } => {
+                    recorded.falls.push(vec2(x as f32, y as f32));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Deaths of this session show up right away, same as they are recorded.
+fn add_death(
+    death: On<PlayerDied>,
+    settings: Res<PlaytestSettings>,
+    mut recorded: ResMut<RecordedDeaths>,
+) {
+    if settings.enabled {
+        recorded.deaths.push(death.position.round());
+    }
+}
+
+fn toggle_heatmap(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut heatmap: ResMut<Heatmap>,
+) {
+    if input.just_pressed(settings.keys.heatmap) {
+        heatmap.shown = !heatmap.shown;
+    }
+}
+
+/// Respawns the cells whenever the records or the heatmap settings change.
+fn build_heatmap(
+    mut commands: Commands,
+    heatmap: Res<Heatmap>,
+    recorded: Res<RecordedDeaths>,
+    cells: Query<Entity, With<HeatmapCell>>,
+) {
+    if !heatmap.is_changed() && !recorded.is_changed() {
+        return;
+    }
+    for entity in cells.iter() {
+        commands.entity(entity).despawn();
+    }
+    if !heatmap.shown || heatmap.cell_size <= 0.0 {
+        return;
+    }
+    let mut density = HashMap::<IVec2, f32>::new();
+    let radius = heatmap.radius.max(0);
+    for position in recorded.deaths.iter().chain(&recorded.falls) {
+        let center = (*position / heatmap.cell_size).floor().as_ivec2();
+        for x in -radius..=radius {
+            for y in -radius..=radius {
+                let offset = ivec2(x, y);
+                let falloff = 1.0 - offset.as_vec2().length() / (radius as f32 + 1.0);
+                if falloff > 0.0 {
+                    *density.entry(center + offset).or_default() += falloff;
+                }
+            }
+        }
+    }
+    let max = density.values().copied().fold(0.0, f32::max);
+    if max <= 0.0 {
+        return;
+    }
+    for (cell, heat) in density {
+        let t = heat / max;
+        let color = COLD.mix(&HOT, t).with_alpha(MAX_ALPHA * t);
+        let center = (cell.as_vec2() + 0.5) * heatmap.cell_size;
+        commands.spawn((
+            Name::new("Heatmap cell"),
+            HeatmapCell,
+            Sprite::from_color(color, Vec2::splat(heatmap.cell_size)),
+            Transform::from_translation(center.extend(0.0)),
+            RenderLayer(RenderLayerOrder::Overlay),
+        ));
+    }
+}
+
+fn draw_deaths(
+    mut gizmos: Gizmos<DeathGizmos>,
+    settings: Res<DevSettings>,
+    recorded: Res<RecordedDeaths>,
+) {
+    if !settings.gizmos.deaths {
+        return;
+    }
+    for &position in &recorded.deaths {
+        gizmos.cross_2d(position, MARKER_SIZE, DEATH);
+    }
+    for &position in &recorded.falls {
+        gizmos.circle_2d(position, MARKER_SIZE / 2.0, FALL);
+    }
+}
@@ -0,0 +1,74 @@
+//! Rereads `input.ron` and the entity definitions at a keypress, for tuning on disk without a
+//! restart.
+//!
+//! Live players are rebound and every entity loaded from a [`Definition`] gets the stats of its
+//! file again. Anything else on the entity, like where it is or how fast it's going, is left
+//! alone. A file that no longer parses keeps the old values and shows why in a toast.
+
+use super::DevSettings;
+use crate::definitions::{read_definition, Definition};
+use crate::input::InputSettings;
+use crate::mario::{CharBundle, ColliderBuilder, ColliderBundle};
+use crate::ui::Toast;
+use bevy::prelude::*;
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(Update, reload_on_key);
+}
+
+fn reload_on_key(
+    mut commands: Commands,
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut input_settings: ResMut<InputSettings>,
+    chars: Query<(Entity, &Definition<CharBundle>)>,
+    colliders: Query<(Entity, &Definition<ColliderBundle>)>,
+) {
+    if !input.just_pressed(settings.keys.reload) {
+        return;
+    }
+    let mut errors = Vec::new();
+    //changing the resource rebinds every player
+    match InputSettings::read_saved() {
+        Ok(saved) => *input_settings = saved,
+        Err(e) => errors.push(format!("input: {e}")),
+    }
+    for (entity, definition) in chars.iter() {
+        match read_definition::<CharBundle>(&definition.path) {
+            Ok(bundle) => {
+                commands.entity(entity).insert((
+                    bundle.move_stats,
+                    bundle.jump_stats,
+                    bundle.stomp_stats,
+                    bundle.ghost_config,
+                ));
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    for (entity, definition) in colliders.iter() {
+        match read_definition::<ColliderBuilder>(&definition.path) {
+            Ok(builder) => {
+                let bundle = ColliderBundle::from(builder);
+                commands.entity(entity).insert((
+                    bundle.collider,
+                    bundle.shape_caster,
+                    bundle.rotation_constraints,
+                    bundle.gravity_scale,
+                    bundle.friction,
+                    bundle.move_and_slide,
+                ));
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let reloaded = chars.iter().count() + colliders.iter().count();
+    info!("reloaded input and {reloaded} definitions, {} failed", errors.len());
+    if errors.is_empty() {
+        commands.trigger(Toast(format!("Reloaded input and {reloaded} definitions")));
+    }
+    for error in errors {
+        warn!("reload failed: {error}");
+        commands.trigger(Toast(error));
+    }
+}
@@ -0,0 +1,138 @@
+//! Draws hitboxes, hurtboxes, sensors and interaction ranges, each category in its own color and
+//! toggled from [`DevSettings::gizmos`].
+//!
+//! Hitboxes with an active window are labeled with their age and the tick their window ends on.
+
+use super::DevSettings;
+use crate::combat::{Hitbox, Hurtbox};
+use crate::enemies::Spawner;
+use crate::layers::RenderLayerOrder;
+use crate::separation::SoftSeparation;
+use avian2d::prelude::*;
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct HitboxGizmos;
+
+/// Frame count label of a windowed hitbox, a child of it.
+#[derive(Component, Debug)]
+struct HitboxLabel;
+
+const ACTIVE_HITBOX: Srgba = tailwind::RED_500;
+const INACTIVE_HITBOX: Srgba = tailwind::RED_900;
+const HURTBOX: Srgba = tailwind::GREEN_400;
+const SENSOR: Srgba = tailwind::CYAN_400;
+const RANGE: Srgba = tailwind::YELLOW_300;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_gizmo_group::<HitboxGizmos>().add_systems(
+        Update,
+        (draw_combat_boxes, draw_sensors, draw_ranges, label_hitboxes),
+    );
+}
+
+fn draw_combat_boxes(
+    mut gizmos: Gizmos<HitboxGizmos>,
+    settings: Res<DevSettings>,
+    hitboxes: Query<(&Hitbox, &GlobalTransform)>,
+    hurtboxes: Query<(&Hurtbox, &GlobalTransform)>,
+) {
+    if settings.gizmos.hitboxes {
+        for (hitbox, xf) in hitboxes.iter() {
+            let color = if hitbox.is_active() {
+                ACTIVE_HITBOX
+            } else {
+                INACTIVE_HITBOX
+            };
+            let center = xf.translation().xy() + hitbox.offset;
+            gizmos.rect_2d(center, hitbox.half_size * 2.0, color);
+        }
+    }
+    if settings.gizmos.hurtboxes {
+        for (hurtbox, xf) in hurtboxes.iter() {
+            let center = xf.translation().xy() + hurtbox.offset;
+            gizmos.rect_2d(center, hurtbox.half_size * 2.0, HURTBOX);
+        }
+    }
+}
+
+fn draw_sensors(
+    mut gizmos: Gizmos<HitboxGizmos>,
+    settings: Res<DevSettings>,
+    sensors: Query<&ColliderAabb, With<Sensor>>,
+) {
+    if !settings.gizmos.sensors {
+        return;
+    }
+    for aabb in sensors.iter() {
+        gizmos.rect_2d(aabb.center(), aabb.size(), SENSOR);
+    }
+}
+
+fn draw_ranges(
+    mut gizmos: Gizmos<HitboxGizmos>,
+    settings: Res<DevSettings>,
+    spawners: Query<(&Spawner, &GlobalTransform)>,
+    separations: Query<(&SoftSeparation, &GlobalTransform)>,
+) {
+    if !settings.gizmos.ranges {
+        return;
+    }
+    for (spawner, xf) in spawners.iter() {
+        gizmos.circle_2d(xf.translation().xy(), spawner.radius, RANGE);
+    }
+    for (separation, xf) in separations.iter() {
+        gizmos.rect_2d(
+            xf.translation().xy(),
+            separation.half_size * 2.0,
+            RANGE.with_alpha(0.4),
+        );
+    }
+}
+
+fn label_hitboxes(
+    mut commands: Commands,
+    settings: Res<DevSettings>,
+    hitboxes: Query<(Entity, &Hitbox, Option<&Children>)>,
+    mut labels: Query<(&mut Text2d, &mut Visibility), With<HitboxLabel>>,
+) {
+    for (entity, hitbox, children) in hitboxes.iter() {
+        let Some(window) = &hitbox.window else {
+            continue;
+        };
+        let text = format!("{}/{}", hitbox.age(), window.end);
+        let label = children
+            .into_iter()
+            .flatten()
+            .find(|child| labels.contains(**child));
+        let visibility = if settings.gizmos.hitboxes {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        match label.and_then(|label| labels.get_mut(*label).ok()) {
+            Some((mut label, mut label_visibility)) => {
+                if label.0 != text {
+                    label.0 = text;
+                }
+                label_visibility.set_if_neq(visibility);
+            }
+            None => {
+                commands.spawn((
+                    Name::new("Hitbox label"),
+                    HitboxLabel,
+                    Text2d::new(text),
+                    TextFont::from_font_size(16.0),
+                    TextColor(ACTIVE_HITBOX.into()),
+                    Transform::from_translation(
+                        (hitbox.offset + Vec2::Y * 12.0).extend(RenderLayerOrder::Popups.z()),
+                    )
+                    .with_scale(Vec3::splat(0.5)),
+                    visibility,
+                    ChildOf(entity),
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Sanity checks for the character controller, run every fixed tick.
+//!
+//! A violation is logged with a dump of the offending controller and pauses the game,
+//! so regressions in the controller show up right where they happen.
+
+use char_controller::prelude::*;
+use crate::physics::{
+    check_grounded, perform_move_and_slide, KinematicController, LastMoveOutput,
+    MoveAndSlideSettings, SlideController,
+};
+use crate::time::PauseEvent;
+use avian2d::math::{AdjustPrecision, AsF32};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+
+#[derive(Resource, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct PhysicsChecks {
+    pub enabled: bool,
+    pub pause_on_violation: bool,
+}
+
+impl Default for PhysicsChecks {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            pause_on_violation: true,
+        }
+    }
+}
+
+/// How far past its skin width a controller may sink into geometry after moving.
+const OVERLAP_TOLERANCE: f32 = 0.01;
+/// How far the transform may drift from the last move and slide output between ticks.
+/// Anything teleporting a controller should clear its [`LastMoveOutput`].
+const DRIFT_TOLERANCE: f32 = 0.001;
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<PhysicsChecks>().add_systems(
+        FixedUpdate,
+        (
+            check_before_move.before(check_grounded),
+            check_after_move.after(perform_move_and_slide),
+        )
+            .run_if(|checks: Res<PhysicsChecks>| checks.enabled),
+    );
+}
+
+fn report(
+    commands: &mut Commands,
+    checks: &PhysicsChecks,
+    entity: Entity,
+    problem: &str,
+    dump: impl std::fmt::Debug,
+) {
+    error!("physics invariant violated on {entity}: {problem}\n{dump:#?}");
+    if checks.pause_on_violation {
+        commands.trigger(PauseEvent::Enable);
+    }
+}
+
+fn check_before_move(
+    query: Query<(Entity, &KinematicController, &Transform, &LastMoveOutput), With<SlideController>>,
+    checks: Res<PhysicsChecks>,
+    mut commands: Commands,
+) {
+    for (entity, controller, transform, last) in query.iter() {
+        let dump = (controller, transform, last);
+        if !controller.velocity.is_finite() {
+            report(&mut commands, &checks, entity, "velocity is not finite", dump);
+            continue;
+        }
+        let Some(output) = last.0 else {
+            continue;
+        };
+        let drift = transform.translation.xy().distance(output.position.f32());
+        if drift > DRIFT_TOLERANCE {
+            report(
+                &mut commands,
+                &checks,
+                entity,
+                &format!("transform drifted {drift} from the last move and slide output"),
+                dump,
+            );
+        }
+    }
+}
+
+fn check_after_move(
+    query: Query<
+        (
+            Entity,
+            &Collider,
+            &Transform,
+            &KinematicController,
+            Option<&MoveAndSlideSettings>,
+        ),
+        With<SlideController>,
+    >,
+    move_and_slide: MoveAndSlide,
+    checks: Res<PhysicsChecks>,
+    mut commands: Commands,
+) {
+    let default_config = MoveAndSlideConfig::default();
+    for (entity, collider, transform, controller, settings) in query.iter() {
+        let config = settings.map_or(&default_config, |settings| &settings.0);
+        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+        let mut deepest = None;
+        move_and_slide.intersections(
+            collider,
+            transform.translation.xy().adjust_precision(),
+            transform
+                .rotation
+                .to_euler(EulerRot::XYZ)
+                .2
+                .adjust_precision(),
+            0.0,
+            &filter,
+            |contact, normal| {
+                if deepest.is_none_or(|(depth, _)| contact.penetration > depth) {
+                    deepest = Some((contact.penetration, normal));
+                }
+                true
+            },
+        );
+        if let Some((depth, normal)) = deepest
+            && depth > config.skin_width + OVERLAP_TOLERANCE
+        {
+            report(
+                &mut commands,
+                &checks,
+                entity,
+                &format!("overlapping geometry by {depth} along {normal:?} after moving"),
+                (controller, transform, config),
+            );
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Draws the predicted jump arcs of the player from its current [`JumpStats`].
+
+use super::DevSettings;
+use crate::jump::JumpStats;
+use crate::locomotion::{LocomotionDriver, MoveStats};
+use crate::mario::Char;
+use crate::physics::KinematicController;
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+
+#[derive(Resource, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct JumpArcPreview {
+    pub enabled: bool,
+}
+
+/// Simulation step of the prediction, matches the default fixed timestep.
+const STEP: f32 = 1.0 / 64.0;
+/// Give up predicting after this many seconds.
+const MAX_TIME: f32 = 3.0;
+
+pub(super) fn plugin(app: &mut App) {
+    let enabled = app.world().resource::<DevSettings>().open.jump_arc;
+    app.insert_resource(JumpArcPreview { enabled }).add_systems(
+        Update,
+        (
+            toggle_preview,
+            draw_jump_arcs.run_if(|preview: Res<JumpArcPreview>| preview.enabled),
+        )
+            .chain(),
+    );
+}
+
+fn toggle_preview(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut preview: ResMut<JumpArcPreview>,
+) {
+    if input.just_pressed(settings.keys.jump_arc) {
+        preview.enabled = !preview.enabled;
+    }
+}
+
+/// Simulates a jump from `start` the same way `apply_gravity` does, holding jump for `hold_time` seconds.
+fn predict_arc(stats: &JumpStats, start: Vec2, speed_x: f32, hold_time: f32) -> Vec<Vec2> {
+    let mut points = vec![start];
+    let mut position = start;
+    let mut velocity = vec2(speed_x, stats.jump_velocity());
+    let mut time = 0.0;
+    //stop once we've fallen well below the takeoff height
+    while time < MAX_TIME && position.y > start.y - stats.max_height * 2.0 {
+        let gravity = match velocity.y {
+            y if y > 0.0 && time < hold_time => stats.rise_gravity(),
+            y if y > 0.0 => stats.release_gravity(),
+            _ => stats.fall_gravity(),
+        };
+        velocity.y = (velocity.y - gravity * STEP).max(-stats.max_fall_speed);
+        position += velocity * STEP;
+        time += STEP;
+        points.push(position);
+    }
+    points
+}
+
+fn draw_jump_arcs(
+    query: Query<
+        (
+            &Transform,
+            &JumpStats,
+            &MoveStats,
+            &KinematicController,
+            Option<&LocomotionDriver>,
+        ),
+        With<Char>,
+    >,
+    mut gizmos: Gizmos,
+) {
+    for (transform, stats, move_stats, controller, driver) in query.iter() {
+        let start = transform.translation.xy();
+        //preview in whichever direction we're moving or about to move
+        let facing = match (controller.velocity.x, driver.map_or(0.0, |d| d.wish_dir.x)) {
+            (_, wish) if wish != 0.0 => wish.signum(),
+            (vel, _) if vel != 0.0 => vel.signum(),
+            _ => 1.0,
+        };
+        let speed_x = controller.velocity.x.abs().max(move_stats.move_speed) * facing;
+
+        let full = predict_arc(stats, start, speed_x, f32::INFINITY);
+        let tap = predict_arc(stats, start, speed_x, 0.0);
+        gizmos.linestrip_2d(full, tailwind::SKY_400);
+        gizmos.linestrip_2d(tap, tailwind::AMBER_400);
+
+        //heights the arcs should peak at
+        let width = stats.max_height;
+        for (height, color) in [
+            (stats.max_height, tailwind::SKY_700),
+            (stats.min_height, tailwind::AMBER_700),
+        ] {
+            let y = start.y + height;
+            gizmos.line_2d(vec2(start.x, y), vec2(start.x + width * facing, y), color);
+        }
+    }
+}
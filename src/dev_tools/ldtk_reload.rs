@@ -0,0 +1,57 @@
+//! Keeps the player where they were when the `.ldtk` file is edited.
+//!
+//! `bevy_ecs_ldtk` already respawns the world when the project asset changes on disk (with the
+//! `file_watcher` feature), but that also respawns the player at the level's start.
+
+use crate::mario::Char;
+use crate::physics::KinematicController;
+use crate::split_screen::PlayerSlot;
+use crate::ui::Toast;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::collections::HashMap;
+
+/// Where each player was right before the reload, by [`PlayerSlot`].
+#[derive(Resource, Default, Debug)]
+struct ReloadCheckpoint(HashMap<usize, Vec2>);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<ReloadCheckpoint>()
+        .add_systems(PreUpdate, remember_player)
+        .add_observer(restore_player);
+}
+
+fn remember_player(
+    mut commands: Commands,
+    mut asset_events: MessageReader<AssetEvent<LdtkProject>>,
+    mario: Query<(&Transform, Option<&PlayerSlot>), With<Char>>,
+    mut checkpoint: ResMut<ReloadCheckpoint>,
+) {
+    if !asset_events
+        .read()
+        .any(|event| matches!(event, AssetEvent::Modified { .. }))
+    {
+        return;
+    }
+    checkpoint.0 = mario
+        .iter()
+        .map(|(xf, slot)| (slot.map_or(0, |slot| slot.0), xf.translation.xy()))
+        .collect();
+    commands.trigger(Toast("Level file changed, reloading".into()));
+}
+
+fn restore_player(
+    add: On<Add, Char>,
+    mut mario: Query<(&mut Transform, &mut KinematicController, Option<&PlayerSlot>)>,
+    mut checkpoint: ResMut<ReloadCheckpoint>,
+) {
+    let Ok((mut transform, mut controller, slot)) = mario.get_mut(add.entity) else {
+        return;
+    };
+    let Some(position) = checkpoint.0.remove(&slot.map_or(0, |slot| slot.0)) else {
+        return;
+    };
+    info!("level reloaded, putting the player back at {position}");
+    transform.translation = position.extend(transform.translation.z);
+    controller.velocity = Vec2::ZERO;
+}
@@ -0,0 +1,88 @@
+//! Development tools for the game. This plugin is only enabled in dev builds.
+
+mod combat_log;
+mod deaths;
+mod definition_reload;
+mod hitbox_gizmos;
+mod invariants;
+mod jump_arc;
+mod ldtk_reload;
+mod picking;
+mod settings;
+mod slide_editor;
+mod telemetry;
+mod test_levels;
+
+use crate::physics::ControllerHit;
+use crate::screens::Screen;
+use avian2d::prelude::*;
+use bevy::input::common_conditions::input_toggle_active;
+use bevy::{dev_tools::states::log_transitions, prelude::*};
+use bevy_ecs_tilemap::prelude::*;
+use bevy_inspector_egui::bevy_egui::{EguiContext, EguiPlugin, PrimaryEguiContext};
+pub use settings::DevSettings;
+
+pub(super) fn plugin(app: &mut App) {
+    // Log `Screen` state transitions.
+    app.add_systems(Update, log_transitions::<Screen>);
+
+    settings::plugin(app);
+    let settings = app.world().resource::<DevSettings>().clone();
+
+    // Toggle the debug overlay for UI.
+    //inspect stuff and things
+    app.add_plugins((
+        EguiPlugin::default(),
+        bevy_inspector_egui::quick::WorldInspectorPlugin::new()
+            .run_if(input_toggle_active(settings.open.inspector, settings.keys.inspector)),
+        PhysicsDebugPlugin::default(),
+        combat_log::plugin,
+        deaths::plugin,
+        definition_reload::plugin,
+        hitbox_gizmos::plugin,
+        invariants::plugin,
+        jump_arc::plugin,
+        ldtk_reload::plugin,
+        picking::plugin,
+        slide_editor::plugin,
+        telemetry::plugin,
+        test_levels::plugin,
+    ));
+
+    // Blacken every tile a controller touches.
+    app.add_observer(color_touched_tiles);
+}
+
+fn toggle_debug_ui(
+    mut options: ResMut<UiDebugOptions>,
+    egui: Single<&mut EguiContext, With<PrimaryEguiContext>>,
+) {
+    options.toggle();
+}
+
+fn color_touched_tiles(
+    hit: On<ControllerHit>,
+    tilemap_q: Query<(
+        &TilemapSize,
+        &TilemapGridSize,
+        &TilemapTileSize,
+        &TilemapType,
+        &TileStorage,
+        &TilemapAnchor,
+        &GlobalTransform,
+    )>,
+    mut tile_q: Query<&mut TileColor>,
+) {
+    //nudge the contact point into the touched tile
+    let point = hit.point - hit.normal * 0.5;
+    for (size, grid_size, tile_size, map_type, storage, anchor, xf) in tilemap_q.iter() {
+        let local = point - xf.translation().xy();
+        if let Some(pos) =
+            TilePos::from_world_pos(&local, size, grid_size, tile_size, map_type, anchor)
+            && let Some(tile) = storage.get(&pos)
+            && let Ok(mut color) = tile_q.get_mut(tile)
+        {
+            color.0 = Color::BLACK;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+//! Click a collider to inspect its entity, shift-click to teleport the player to the cursor.
+
+use crate::camera::PlayerCamera;
+use crate::mario::Char;
+use crate::physics::{KinematicController, LastMoveOutput};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContext, EguiContexts, EguiPrimaryContextPass, PrimaryEguiContext};
+
+/// The entity shown in the "Selected entity" window.
+#[derive(Resource, Debug, Default)]
+pub struct InspectorSelection(pub Option<Entity>);
+
+pub(super) fn plugin(app: &mut App) {
+    app.init_resource::<InspectorSelection>()
+        .add_systems(Update, pick_or_teleport)
+        .add_systems(EguiPrimaryContextPass, selection_window);
+}
+
+/// Where the cursor points in the world, through whichever player camera's viewport it's over.
+fn cursor_world_position(
+    window: &Window,
+    cameras: &Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+) -> Option<Vec2> {
+    let cursor = window.cursor_position()?;
+    let (camera, camera_transform) = cameras.iter().find(|(camera, _)| {
+        camera
+            .logical_viewport_rect()
+            .is_some_and(|viewport| viewport.contains(cursor))
+    })?;
+    camera.viewport_to_world_2d(camera_transform, cursor).ok()
+}
+
+fn pick_or_teleport(
+    mouse: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    window: Single<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    spatial_query: SpatialQuery,
+    mut contexts: EguiContexts,
+    mut selection: ResMut<InspectorSelection>,
+    mut mario: Query<(&mut Transform, &mut KinematicController, &mut LastMoveOutput), With<Char>>,
+) -> Result {
+    if !mouse.just_pressed(MouseButton::Left) || contexts.ctx_mut()?.wants_pointer_input() {
+        return Ok(());
+    }
+    let Some(point) = cursor_world_position(&window, &cameras) else {
+        return Ok(());
+    };
+
+    if keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        for (mut transform, mut controller, mut last_output) in mario.iter_mut() {
+            info!("teleporting to {point}");
+            transform.translation = point.extend(transform.translation.z);
+            controller.velocity = Vec2::ZERO;
+            //not a move and slide result, don't let the invariant checks complain
+            last_output.0 = None;
+        }
+        return Ok(());
+    }
+
+    let picked = spatial_query
+        .point_intersections(point, &SpatialQueryFilter::default())
+        .into_iter()
+        .next();
+    info!("picked {picked:?} at {point}");
+    selection.0 = picked;
+    Ok(())
+}
+
+fn selection_window(world: &mut World) {
+    let Some(entity) = world.resource::<InspectorSelection>().0 else {
+        return;
+    };
+    let Ok(egui_context) = world
+        .query_filtered::<&mut EguiContext, With<PrimaryEguiContext>>()
+        .single(world)
+    else {
+        return;
+    };
+    let mut egui_context = egui_context.clone();
+    let mut open = true;
+    egui::Window::new("Selected entity")
+        .open(&mut open)
+        .show(egui_context.get_mut(), |ui| {
+            if world.get_entity(entity).is_err() {
+                ui.label(format!("{entity} was despawned"));
+                return;
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                bevy_inspector_egui::bevy_inspector::ui_for_entity(world, entity, ui);
+            });
+        });
+    if !open {
+        world.resource_mut::<InspectorSelection>().0 = None;
+    }
+}
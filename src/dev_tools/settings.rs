@@ -0,0 +1,140 @@
+//! Keys and defaults for the dev tools, read from `assets/dev_settings.ron` so everyone can pick
+//! their own without touching the code. A missing file is written out with the defaults.
+
+use crate::physics::ContactGizmos;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs;
+
+#[derive(Resource, Debug, Clone, Default, Reflect, Deserialize, Serialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct DevSettings {
+    pub keys: DevKeys,
+    /// what is open when the game starts
+    pub open: DevPanels,
+    pub gizmos: GizmoCategories,
+}
+
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DevKeys {
+    pub inspector: KeyCode,
+    pub telemetry: KeyCode,
+    pub jump_arc: KeyCode,
+    pub slide_editor: KeyCode,
+    /// swaps between the A and B move and slide presets
+    pub slide_a_b: KeyCode,
+    pub test_levels: KeyCode,
+    pub combat_log: KeyCode,
+    pub heatmap: KeyCode,
+    /// rereads the input settings and the entity definitions
+    pub reload: KeyCode,
+}
+
+#[derive(Debug, Clone, Default, Reflect, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DevPanels {
+    pub inspector: bool,
+    pub telemetry: bool,
+    pub jump_arc: bool,
+    pub slide_editor: bool,
+    pub test_levels: bool,
+    pub combat_log: bool,
+    pub heatmap: bool,
+}
+
+#[derive(Debug, Clone, Reflect, Deserialize, Serialize)]
+#[serde(default)]
+pub struct GizmoCategories {
+    /// avian's collider outlines
+    pub physics: bool,
+    /// contacts made by move and slide
+    pub contacts: bool,
+    pub hitboxes: bool,
+    pub hurtboxes: bool,
+    /// avian sensor colliders
+    pub sensors: bool,
+    /// spawner radii and soft separation boxes
+    pub ranges: bool,
+    /// recorded playtest deaths
+    pub deaths: bool,
+}
+
+impl Default for DevKeys {
+    fn default() -> Self {
+        Self {
+            inspector: KeyCode::Backquote,
+            telemetry: KeyCode::F1,
+            jump_arc: KeyCode::F2,
+            slide_editor: KeyCode::F3,
+            slide_a_b: KeyCode::F4,
+            test_levels: KeyCode::F5,
+            combat_log: KeyCode::F8,
+            heatmap: KeyCode::F9,
+            reload: KeyCode::F6,
+        }
+    }
+}
+
+impl Default for GizmoCategories {
+    fn default() -> Self {
+        Self {
+            physics: true,
+            contacts: true,
+            hitboxes: true,
+            hurtboxes: true,
+            sensors: true,
+            ranges: false,
+            deaths: false,
+        }
+    }
+}
+
+impl DevSettings {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+
+    fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        fs::write(path, string)?;
+        Ok(())
+    }
+
+    /// Reads the settings file, writing the defaults if there is none yet.
+    pub fn load() -> Self {
+        match Self::read(SETTINGS_PATH) {
+            Ok(settings) => settings,
+            Err(e) => {
+                match e.downcast_ref::<std::io::Error>() {
+                    Some(io) if io.kind() == std::io::ErrorKind::NotFound => {
+                        if let Err(e) = Self::default().write(SETTINGS_PATH) {
+                            warn!("write error {e}");
+                        }
+                    }
+                    _ => warn!("couldn't read {SETTINGS_PATH}: {e}"),
+                }
+                Self::default()
+            }
+        }
+    }
+}
+
+const SETTINGS_PATH: &str = "assets/dev_settings.ron";
+
+/// Inserts [`DevSettings`], has to run before the other dev plugins read it.
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<DevSettings>()
+        .insert_resource(DevSettings::load())
+        .add_systems(Startup, apply_gizmo_categories);
+}
+
+fn apply_gizmo_categories(settings: Res<DevSettings>, mut store: ResMut<GizmoConfigStore>) {
+    store.config_mut::<PhysicsGizmos>().0.enabled = settings.gizmos.physics;
+    store.config_mut::<ContactGizmos>().0.enabled = settings.gizmos.contacts;
+}
@@ -0,0 +1,456 @@
+//! Live editor for the player's [`MoveAndSlideSettings`], along with the gravity, acceleration
+//! and jump stats that make up most of how it feels.
+//!
+//! Named presets are kept in `assets/slide_presets.ron`. Two of them can be picked as A and B and
+//! swapped with a single key while moving, to compare how they feel back to back.
+
+use super::DevSettings;
+use char_controller::prelude::*;
+use crate::jump::JumpStats;
+use crate::locomotion::MoveStats;
+use crate::mario::Char;
+use crate::physics::MoveAndSlideSettings;
+use crate::split_screen::{PlayerSlot, MAX_PLAYERS};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts, EguiPrimaryContextPass};
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+/// Solver settings plus the stats that change how movement feels, saved and swapped together.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SlidePreset {
+    pub slide: MoveAndSlideConfig,
+    pub gravity_scale: f32,
+    pub ground_acceleration: f32,
+    pub ground_deceleration: f32,
+    pub air_acceleration: f32,
+    /// see [`JumpStats::max_height`]
+    pub jump_height: f32,
+    pub jump_time: f32,
+    pub fall_time: f32,
+}
+
+impl Default for SlidePreset {
+    fn default() -> Self {
+        let (move_stats, jump_stats) = (MoveStats::default(), JumpStats::default());
+        Self {
+            slide: MoveAndSlideConfig::default(),
+            gravity_scale: 1.0,
+            ground_acceleration: move_stats.ground_acceleration,
+            ground_deceleration: move_stats.ground_deceleration,
+            air_acceleration: move_stats.air_acceleration,
+            jump_height: jump_stats.max_height,
+            jump_time: jump_stats.jump_time,
+            fall_time: jump_stats.fall_time,
+        }
+    }
+}
+
+impl SlidePreset {
+    fn capture(
+        settings: &MoveAndSlideSettings,
+        move_stats: &MoveStats,
+        jump_stats: &JumpStats,
+        gravity_scale: Option<&GravityScale>,
+    ) -> Self {
+        Self {
+            slide: settings.0.clone(),
+            gravity_scale: gravity_scale.map_or(1.0, |scale| scale.0),
+            ground_acceleration: move_stats.ground_acceleration,
+            ground_deceleration: move_stats.ground_deceleration,
+            air_acceleration: move_stats.air_acceleration,
+            jump_height: jump_stats.max_height,
+            jump_time: jump_stats.jump_time,
+            fall_time: jump_stats.fall_time,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SlidePresets {
+    pub presets: BTreeMap<String, SlidePreset>,
+    pub a: String,
+    pub b: String,
+    /// B is applied, not A
+    #[serde(skip)]
+    pub showing_b: bool,
+    #[serde(skip)]
+    pub open: bool,
+    /// [`PlayerSlot`] the panel edits, the A/B key swaps everyone
+    #[serde(skip)]
+    pub player: usize,
+    /// name typed into the save field
+    #[serde(skip)]
+    new_name: String,
+}
+
+impl Default for SlidePresets {
+    fn default() -> Self {
+        let default = MoveAndSlideConfig::default();
+        Self {
+            presets: BTreeMap::from([
+                //snappy turns and a short, quick jump
+                (
+                    "tight".to_string(),
+                    SlidePreset {
+                        slide: MoveAndSlideConfig {
+                            move_and_slide_iterations: 8,
+                            skin_width: default.skin_width * 0.5,
+                            ..default.clone()
+                        },
+                        ground_acceleration: 700.0,
+                        ground_deceleration: 1000.0,
+                        air_acceleration: 550.0,
+                        jump_height: 56.0,
+                        jump_time: 0.32,
+                        fall_time: 0.24,
+                        ..SlidePreset::default()
+                    },
+                ),
+                //slow to get going, hangs in the air
+                (
+                    "floaty".to_string(),
+                    SlidePreset {
+                        slide: MoveAndSlideConfig {
+                            move_and_slide_iterations: 2,
+                            skin_width: default.skin_width * 4.0,
+                            ..default.clone()
+                        },
+                        gravity_scale: 0.7,
+                        ground_acceleration: 200.0,
+                        ground_deceleration: 250.0,
+                        air_acceleration: 250.0,
+                        jump_height: 72.0,
+                        jump_time: 0.55,
+                        fall_time: 0.5,
+                        ..SlidePreset::default()
+                    },
+                ),
+                //stops dead, barely steers in the air and drops like a stone
+                (
+                    "heavy".to_string(),
+                    SlidePreset {
+                        slide: MoveAndSlideConfig {
+                            depenetration_iterations: 32,
+                            penetration_rejection_threshold: default
+                                .penetration_rejection_threshold
+                                * 2.0,
+                            ..default.clone()
+                        },
+                        gravity_scale: 1.4,
+                        ground_acceleration: 250.0,
+                        ground_deceleration: 900.0,
+                        air_acceleration: 150.0,
+                        jump_height: 48.0,
+                        jump_time: 0.36,
+                        fall_time: 0.22,
+                        ..SlidePreset::default()
+                    },
+                ),
+            ]),
+            a: "tight".to_string(),
+            b: "floaty".to_string(),
+            showing_b: false,
+            open: false,
+            player: 0,
+            new_name: String::new(),
+        }
+    }
+}
+
+impl SlidePresets {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+
+    fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        fs::write(path, string)?;
+        Ok(())
+    }
+
+    fn current(&self) -> &str {
+        if self.showing_b { &self.b } else { &self.a }
+    }
+}
+
+const PRESETS_PATH: &str = "assets/slide_presets.ron";
+
+pub(super) fn plugin(app: &mut App) {
+    let open = app.world().resource::<DevSettings>().open.slide_editor;
+    let presets = SlidePresets::read(PRESETS_PATH).unwrap_or_default();
+    app.insert_resource(SlidePresets { open, ..presets })
+        .add_systems(Update, (toggle_editor, swap_a_b))
+        .add_systems(EguiPrimaryContextPass, editor_panel);
+}
+
+fn toggle_editor(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut presets: ResMut<SlidePresets>,
+) {
+    if input.just_pressed(settings.keys.slide_editor) {
+        presets.open = !presets.open;
+    }
+}
+
+type Players<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut MoveAndSlideSettings,
+        &'static mut MoveStats,
+        &'static mut JumpStats,
+        Option<&'static mut GravityScale>,
+        Option<&'static PlayerSlot>,
+    ),
+    With<Char>,
+>;
+
+fn slot_of(slot: Option<&PlayerSlot>) -> usize {
+    slot.map_or(0, |slot| slot.0)
+}
+
+/// Applies to the player in slot `player`, or to everyone without one.
+fn apply(preset: &SlidePreset, mario: &mut Players, player: Option<usize>) {
+    for (mut settings, mut move_stats, mut jump_stats, gravity_scale, slot) in mario.iter_mut() {
+        if player.is_some_and(|player| player != slot_of(slot)) {
+            continue;
+        }
+        settings.0 = preset.slide.clone();
+        move_stats.ground_acceleration = preset.ground_acceleration;
+        move_stats.ground_deceleration = preset.ground_deceleration;
+        move_stats.air_acceleration = preset.air_acceleration;
+        jump_stats.max_height = preset.jump_height;
+        jump_stats.jump_time = preset.jump_time;
+        jump_stats.fall_time = preset.fall_time;
+        if let Some(mut gravity_scale) = gravity_scale {
+            gravity_scale.0 = preset.gravity_scale;
+        }
+    }
+}
+
+fn swap_a_b(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut presets: ResMut<SlidePresets>,
+    mut mario: Players,
+) {
+    if !input.just_pressed(settings.keys.slide_a_b) {
+        return;
+    }
+    presets.showing_b = !presets.showing_b;
+    let name = presets.current().to_string();
+    match presets.presets.get(&name) {
+        Some(preset) => {
+            info!("move and slide preset {name}");
+            apply(preset, &mut mario, None);
+        }
+        None => warn!("no move and slide preset named {name}"),
+    }
+}
+
+fn editor_panel(
+    mut contexts: EguiContexts,
+    mut presets: ResMut<SlidePresets>,
+    mut mario: Players,
+) -> Result {
+    if !presets.open {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    let mut open = presets.open;
+    egui::Window::new("Move and slide")
+        .open(&mut open)
+        .show(ctx, |ui| {
+            let player = presets.player;
+            ui.horizontal(|ui| {
+                ui.label("player");
+                ui.add(egui::DragValue::new(&mut presets.player).range(0..=MAX_PLAYERS - 1));
+            });
+            let selected = mario.iter().find(|(.., slot)| slot_of(*slot) == player);
+            let Some((settings, move_stats, jump_stats, gravity_scale, _)) = selected else {
+                ui.label("no player");
+                return;
+            };
+            //only write through when something actually changed, keeps change detection quiet
+            let current = SlidePreset::capture(settings, move_stats, jump_stats, gravity_scale);
+            let mut preset = current.clone();
+            let config = &mut preset.slide;
+            ui.horizontal(|ui| {
+                ui.label("iterations");
+                ui.add(egui::DragValue::new(&mut config.move_and_slide_iterations).range(1..=32));
+            });
+            ui.horizontal(|ui| {
+                ui.label("depenetration iterations");
+                ui.add(egui::DragValue::new(&mut config.depenetration_iterations).range(1..=64));
+            });
+            ui.horizontal(|ui| {
+                ui.label("max depenetration error");
+                ui.add(egui::DragValue::new(&mut config.max_depenetration_error).speed(0.00001));
+            });
+            ui.horizontal(|ui| {
+                ui.label("penetration rejection");
+                ui.add(egui::DragValue::new(&mut config.penetration_rejection_threshold).speed(0.01));
+            });
+            ui.horizontal(|ui| {
+                ui.label("skin width");
+                ui.add(egui::DragValue::new(&mut config.skin_width).speed(0.0005).range(0.0..=1.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("max planes");
+                ui.add(egui::DragValue::new(&mut config.max_planes).range(1..=64));
+            });
+            ui.horizontal(|ui| {
+                ui.label("contact cache tolerance");
+                ui.add(egui::DragValue::new(&mut config.contact_cache_tolerance).speed(0.001).range(0.0..=1.0));
+            });
+            let mut stick = config.ground_stick.is_some();
+            if ui.checkbox(&mut stick, "ground stick").changed() {
+                config.ground_stick = stick.then(GroundStick::default);
+            }
+            let mut snap = config.ground_snap.is_some();
+            if ui.checkbox(&mut snap, "ground snap").changed() {
+                config.ground_snap = snap.then(GroundSnap::default);
+            }
+            if let Some(snap) = &mut config.ground_snap {
+                ui.horizontal(|ui| {
+                    ui.label("snap distance");
+                    ui.add(egui::DragValue::new(&mut snap.max_distance).speed(0.01).range(0.0..=1.6));
+                });
+            }
+            let mut step = config.step_down.is_some();
+            if ui.checkbox(&mut step, "step down").changed() {
+                config.step_down = step.then(StepDown::default);
+            }
+            if let Some(step) = &mut config.step_down {
+                ui.horizontal(|ui| {
+                    ui.label("max step height");
+                    ui.add(egui::DragValue::new(&mut step.max_step_height).speed(0.01).range(0.0..=3.2));
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.label("max slope");
+                let mut degrees = config.max_slope_angle.to_degrees();
+                if ui.add(egui::DragValue::new(&mut degrees).speed(0.5).range(0.0..=90.0).suffix("°")).changed() {
+                    config.max_slope_angle = degrees.to_radians();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("steep slide speed");
+                ui.add(egui::DragValue::new(&mut config.steep_slide_speed).speed(0.1).range(0.0..=100.0));
+            });
+            let mut substep = config.max_substep_distance.is_some();
+            if ui.checkbox(&mut substep, "substeps").changed() {
+                config.max_substep_distance = substep.then_some(0.5);
+            }
+            if let Some(distance) = &mut config.max_substep_distance {
+                ui.horizontal(|ui| {
+                    ui.label("max substep distance");
+                    ui.add(egui::DragValue::new(distance).speed(0.05).range(0.05..=4.0));
+                });
+                ui.horizontal(|ui| {
+                    ui.label("max substeps");
+                    ui.add(egui::DragValue::new(&mut config.max_substeps).range(1..=32));
+                });
+            }
+            let mut bonk = matches!(config.ceiling, CeilingMode::Bonk(_));
+            if ui.checkbox(&mut bonk, "ceiling bonk").changed() {
+                config.ceiling = if bonk {
+                    CeilingMode::Bonk(CeilingBonk::default())
+                } else {
+                    CeilingMode::Slide
+                };
+            }
+            if let CeilingMode::Bonk(bonk) = &mut config.ceiling {
+                ui.horizontal(|ui| {
+                    ui.label("corner nudge");
+                    ui.add(egui::DragValue::new(&mut bonk.corner_nudge).speed(0.01).range(0.0..=1.6));
+                });
+            }
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("gravity scale");
+                ui.add(egui::DragValue::new(&mut preset.gravity_scale).speed(0.01).range(0.0..=4.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("ground acceleration");
+                ui.add(egui::DragValue::new(&mut preset.ground_acceleration).speed(5.0).range(0.0..=4000.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("ground deceleration");
+                ui.add(egui::DragValue::new(&mut preset.ground_deceleration).speed(5.0).range(0.0..=4000.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("air acceleration");
+                ui.add(egui::DragValue::new(&mut preset.air_acceleration).speed(5.0).range(0.0..=4000.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("jump height");
+                ui.add(egui::DragValue::new(&mut preset.jump_height).speed(0.5).range(1.0..=256.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("jump time");
+                ui.add(egui::DragValue::new(&mut preset.jump_time).speed(0.005).range(0.05..=2.0));
+            });
+            ui.horizontal(|ui| {
+                ui.label("fall time");
+                ui.add(egui::DragValue::new(&mut preset.fall_time).speed(0.005).range(0.05..=2.0));
+            });
+            if preset != current {
+                apply(&preset, &mut mario, Some(player));
+            }
+
+            ui.separator();
+            let names = presets.presets.keys().cloned().collect::<Vec<_>>();
+            ui.horizontal_wrapped(|ui| {
+                for name in &names {
+                    if ui.button(name).clicked() {
+                        apply(&presets.presets[name], &mut mario, Some(player));
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut presets.new_name);
+                if ui.button("save").clicked() && !presets.new_name.is_empty() {
+                    let name = presets.new_name.clone();
+                    presets.presets.insert(name, preset.clone());
+                    if let Err(e) = presets.write(PRESETS_PATH) {
+                        warn!("write error {e}");
+                    }
+                }
+            });
+
+            ui.separator();
+            let (mut a, mut b) = (presets.a.clone(), presets.b.clone());
+            preset_combo(ui, "A", &mut a, &names);
+            preset_combo(ui, "B", &mut b, &names);
+            if a != presets.a {
+                presets.a = a;
+            }
+            if b != presets.b {
+                presets.b = b;
+            }
+            ui.label(format!("applied: {}", presets.current()));
+        });
+    presets.open = open;
+    Ok(())
+}
+
+fn preset_combo(ui: &mut egui::Ui, label: &str, selected: &mut String, names: &[String]) {
+    egui::ComboBox::from_label(label)
+        .selected_text(selected.as_str())
+        .show_ui(ui, |ui| {
+            for name in names {
+                ui.selectable_value(selected, name.clone(), name);
+            }
+        });
+}
@@ -0,0 +1,284 @@
+//! Per-tick controller telemetry for tuning jump arcs and acceleration curves.
+//!
+//! Entities with [`RecordTelemetry`] are sampled every fixed tick into a ring buffer,
+//! which is plotted in an egui panel and can be exported as CSV.
+
+use super::DevSettings;
+use crate::jump::JumpStats;
+use crate::locomotion::MoveStats;
+use crate::mario::Char;
+use crate::physics::{perform_move_and_slide, Grounded, KinematicController, LastMoveOutput};
+use avian2d::math::AsF32;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts, EguiPrimaryContextPass};
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+/// Marks an entity whose controller gets sampled by the telemetry recorder.
+#[derive(Component, Default, Clone, Copy, Reflect)]
+pub struct RecordTelemetry;
+
+#[derive(Clone, Copy, Debug)]
+pub struct TelemetrySample {
+    pub tick: u64,
+    pub entity: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub grounded: bool,
+    pub iterations: usize,
+    pub planes_hit: usize,
+}
+
+#[derive(Resource, Debug)]
+pub struct Telemetry {
+    pub recording: bool,
+    pub open: bool,
+    pub capacity: usize,
+    pub samples: VecDeque<TelemetrySample>,
+    tick: u64,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self {
+            recording: true,
+            open: false,
+            //ten seconds at the default fixed timestep
+            capacity: 640,
+            samples: VecDeque::new(),
+            tick: 0,
+        }
+    }
+}
+
+impl Telemetry {
+    fn push(&mut self, sample: TelemetrySample) {
+        if self.samples.len() >= self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv =
+            String::from("tick,entity,x,y,velocity_x,velocity_y,grounded,iterations,planes_hit\n");
+        for sample in &self.samples {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{},{}",
+                sample.tick,
+                sample.entity,
+                sample.position.x,
+                sample.position.y,
+                sample.velocity.x,
+                sample.velocity.y,
+                sample.grounded,
+                sample.iterations,
+                sample.planes_hit
+            );
+        }
+        csv
+    }
+}
+
+const CSV_PATH: &str = "telemetry.csv";
+
+pub(super) fn plugin(app: &mut App) {
+    let open = app.world().resource::<DevSettings>().open.telemetry;
+    app.insert_resource(Telemetry {
+        open,
+        ..default()
+    })
+        .add_systems(
+            FixedUpdate,
+            record_telemetry.after(perform_move_and_slide),
+        )
+        .add_systems(Update, toggle_panel)
+        .add_systems(EguiPrimaryContextPass, telemetry_panel)
+        .add_observer(record_char);
+}
+
+fn record_char(add: On<Add, Char>, mut commands: Commands) {
+    commands.entity(add.entity).insert(RecordTelemetry);
+}
+
+fn toggle_panel(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut telemetry: ResMut<Telemetry>,
+) {
+    if input.just_pressed(settings.keys.telemetry) {
+        telemetry.open = !telemetry.open;
+    }
+}
+
+fn record_telemetry(
+    query: Query<
+        (
+            Entity,
+            &Transform,
+            &KinematicController,
+            &LastMoveOutput,
+            Has<Grounded>,
+        ),
+        With<RecordTelemetry>,
+    >,
+    mut telemetry: ResMut<Telemetry>,
+) {
+    telemetry.tick += 1;
+    if !telemetry.recording {
+        return;
+    }
+    let tick = telemetry.tick;
+    for (entity, transform, controller, last_output, grounded) in query.iter() {
+        let (iterations, planes_hit) = last_output
+            .0
+            .map_or((0, 0), |out| (out.iterations, out.planes_hit));
+        telemetry.push(TelemetrySample {
+            tick,
+            entity,
+            position: transform.translation.xy(),
+            velocity: controller.velocity.f32(),
+            grounded,
+            iterations,
+            planes_hit,
+        });
+    }
+}
+
+fn telemetry_panel(
+    mut contexts: EguiContexts,
+    mut telemetry: ResMut<Telemetry>,
+    stats: Query<(&MoveStats, Option<&JumpStats>), With<RecordTelemetry>>,
+) -> Result {
+    if !telemetry.open {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    let mut open = telemetry.open;
+    egui::Window::new("Controller telemetry")
+        .open(&mut open)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut telemetry.recording, "recording");
+                if ui.button("clear").clicked() {
+                    telemetry.samples.clear();
+                }
+                if ui.button("export csv").clicked() {
+                    match std::fs::write(CSV_PATH, telemetry.to_csv()) {
+                        Ok(()) => info!("wrote telemetry to {CSV_PATH}"),
+                        Err(e) => warn!("could not write telemetry: {e}"),
+                    }
+                }
+            });
+
+            let samples = &telemetry.samples;
+            let Some(last) = samples.back() else {
+                ui.label("no samples yet");
+                return;
+            };
+            ui.label(format!(
+                "pos {:.1} vel {:.1} grounded {} iterations {} planes {}",
+                last.position, last.velocity, last.grounded, last.iterations, last.planes_hit
+            ));
+
+            //only plot the most recently recorded entity
+            let series = samples
+                .iter()
+                .filter(|sample| sample.entity == last.entity)
+                .collect::<Vec<_>>();
+            let (move_stats, jump_stats) = stats.get(last.entity).ok().unzip();
+            let jump_stats = jump_stats.flatten();
+
+            ui.label("horizontal velocity");
+            let mut targets = Vec::new();
+            if let Some(stats) = move_stats {
+                targets.extend([stats.move_speed, -stats.move_speed, stats.run_speed, -stats.run_speed]);
+            }
+            plot(
+                ui,
+                &series.iter().map(|s| s.velocity.x).collect::<Vec<_>>(),
+                &targets,
+            );
+
+            ui.label("vertical velocity");
+            plot(
+                ui,
+                &series.iter().map(|s| s.velocity.y).collect::<Vec<_>>(),
+                &jump_stats
+                    .map(|stats| vec![stats.jump_velocity(), -stats.max_fall_speed])
+                    .unwrap_or_default(),
+            );
+
+            ui.label("height above lowest sample");
+            let heights = series.iter().map(|s| s.position.y).collect::<Vec<_>>();
+            let floor = heights.iter().copied().fold(f32::INFINITY, f32::min);
+            plot(
+                ui,
+                &heights.iter().map(|y| y - floor).collect::<Vec<_>>(),
+                &jump_stats
+                    .map(|stats| vec![stats.min_height, stats.max_height])
+                    .unwrap_or_default(),
+            );
+        });
+    telemetry.open = open;
+    Ok(())
+}
+
+/// Draws `values` as a line with horizontal reference lines at `targets`.
+fn plot(ui: &mut egui::Ui, values: &[f32], targets: &[f32]) {
+    let (response, painter) = ui.allocate_painter(
+        egui::vec2(ui.available_width().max(300.0), 100.0),
+        egui::Sense::hover(),
+    );
+    let rect = response.rect;
+    painter.rect_stroke(
+        rect,
+        0.0,
+        egui::Stroke::new(1.0, egui::Color32::DARK_GRAY),
+        egui::StrokeKind::Inside,
+    );
+    let (min, max) = values
+        .iter()
+        .chain(targets)
+        .fold((0.0f32, 0.0f32), |(min, max), &v| (min.min(v), max.max(v)));
+    let range = (max - min).max(1.0);
+    let to_y = |v: f32| rect.bottom() - (v - min) / range * rect.height();
+
+    for &target in targets {
+        painter.hline(
+            rect.x_range(),
+            to_y(target),
+            egui::Stroke::new(1.0, egui::Color32::from_rgb(200, 160, 40)),
+        );
+    }
+    painter.hline(
+        rect.x_range(),
+        to_y(0.0),
+        egui::Stroke::new(1.0, egui::Color32::GRAY),
+    );
+    let step = rect.width() / values.len().max(2) as f32;
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| egui::pos2(rect.left() + i as f32 * step, to_y(v)))
+        .collect::<Vec<_>>();
+    painter.add(egui::Shape::line(
+        points,
+        egui::Stroke::new(1.5, egui::Color32::LIGHT_GREEN),
+    ));
+    painter.text(
+        rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("{max:.1}"),
+        egui::FontId::monospace(10.0),
+        egui::Color32::GRAY,
+    );
+    painter.text(
+        rect.left_bottom(),
+        egui::Align2::LEFT_BOTTOM,
+        format!("{min:.1}"),
+        egui::FontId::monospace(10.0),
+        egui::Color32::GRAY,
+    );
+}
@@ -0,0 +1,296 @@
+//! Hand built worst cases for the character controller, to smoke test controller changes against.
+//!
+//! Each [`TestLevel`] is built out of plain static colliders far away from the LDtk world, loading
+//! one with [`LoadTestLevel`] (or from the "Test levels" window) replaces the last and teleports the
+//! player to its start. Every level has floor around the start, the interesting part is off to
+//! the sides.
+
+use super::DevSettings;
+use crate::combat::{Hitbox, Team};
+use crate::layers::{RenderLayer, RenderLayerOrder};
+use crate::mario::Char;
+use crate::physics::{CharacterSystems, KinematicController, LastMoveOutput, PlatformVelocity};
+use avian2d::prelude::*;
+use bevy::color::palettes::tailwind;
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts, EguiPrimaryContextPass};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum TestLevel {
+    /// walls meeting at sharp angles, in a pit and in the ceiling
+    AcuteCorners,
+    /// one pixel holes in the floor and a ceiling one pixel too low to walk under
+    PixelGaps,
+    /// shallow and steep slopes long enough to reach full speed on
+    LongSlopes,
+    /// a platform going back and forth over a floor of spikes
+    PlatformOverSpikes,
+    /// platforms on a turning wheel, to ride around on
+    FerrisWheel,
+}
+
+impl TestLevel {
+    pub const ALL: [TestLevel; 5] = [
+        TestLevel::AcuteCorners,
+        TestLevel::PixelGaps,
+        TestLevel::LongSlopes,
+        TestLevel::PlatformOverSpikes,
+        TestLevel::FerrisWheel,
+    ];
+}
+
+/// Replaces the loaded test level with the given one and puts the player at its start.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct LoadTestLevel(pub TestLevel);
+
+/// Root of the loaded test level.
+#[derive(Component, Debug)]
+struct TestLevelRoot;
+
+/// Goes back and forth between two points in its parent's space.
+#[derive(Component, Clone, Copy, Debug)]
+#[require(PlatformVelocity)]
+struct PingPong {
+    from: Vec2,
+    to: Vec2,
+    /// pixels per second
+    speed: f32,
+    forward: bool,
+}
+
+/// Turns in place, taking its children around with it.
+#[derive(Component, Clone, Copy, Debug)]
+struct Spin {
+    /// radians per second
+    speed: f32,
+}
+
+#[derive(Resource, Debug, Default)]
+struct TestLevelWindow {
+    open: bool,
+}
+
+/// Far enough from the LDtk world that nothing there gets in the way.
+const ORIGIN: Vec2 = vec2(0.0, -20_000.0);
+/// Where the player starts, relative to [`ORIGIN`].
+const START: Vec2 = vec2(0.0, 64.0);
+const WALL_COLOR: Srgba = tailwind::SLATE_500;
+const THICKNESS: f32 = 16.0;
+/// Matches the collider in `entities/char/collider.ron`.
+const PLAYER_HEIGHT: f32 = 15.0;
+
+pub(super) fn plugin(app: &mut App) {
+    let open = app.world().resource::<DevSettings>().open.test_levels;
+    app.insert_resource(TestLevelWindow { open })
+        .add_systems(Update, toggle_window)
+        .add_systems(
+            FixedUpdate,
+            (move_platforms, spin).before(CharacterSystems::Sense),
+        )
+        .add_systems(EguiPrimaryContextPass, test_level_window)
+        .add_observer(load_test_level);
+}
+
+fn toggle_window(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<DevSettings>,
+    mut window: ResMut<TestLevelWindow>,
+) {
+    if input.just_pressed(settings.keys.test_levels) {
+        window.open = !window.open;
+    }
+}
+
+fn test_level_window(
+    mut contexts: EguiContexts,
+    mut commands: Commands,
+    mut window: ResMut<TestLevelWindow>,
+) -> Result {
+    if !window.open {
+        return Ok(());
+    }
+    let ctx = contexts.ctx_mut()?;
+    egui::Window::new("Test levels")
+        .open(&mut window.open)
+        .show(ctx, |ui| {
+            for level in TestLevel::ALL {
+                if ui.button(format!("{level:?}")).clicked() {
+                    commands.trigger(LoadTestLevel(level));
+                }
+            }
+        });
+    Ok(())
+}
+
+fn wall(center: Vec2, size: Vec2, angle: f32) -> impl Bundle {
+    (
+        Name::new("Test wall"),
+        RigidBody::Static,
+        Collider::rectangle(size.x, size.y),
+        Friction::new(1.0),
+        Sprite::from_color(WALL_COLOR, size),
+        Transform::from_translation(center.extend(RenderLayerOrder::Tiles.z()))
+            .with_rotation(Quat::from_rotation_z(angle)),
+    )
+}
+
+/// A wall whose middle line runs from `from` to `to`.
+fn segment(from: Vec2, to: Vec2) -> impl Bundle {
+    let delta = to - from;
+    wall(
+        (from + to) / 2.0,
+        vec2(delta.length(), THICKNESS),
+        delta.to_angle(),
+    )
+}
+
+fn build(level: TestLevel, parent: &mut ChildSpawnerCommands) {
+    match level {
+        TestLevel::AcuteCorners => {
+            parent.spawn(segment(vec2(-512.0, 0.0), vec2(512.0, 0.0)));
+            //narrow pit to land in
+            parent.spawn(segment(vec2(-320.0, 8.0), vec2(-360.0, 160.0)));
+            parent.spawn(segment(vec2(-320.0, 8.0), vec2(-280.0, 160.0)));
+            //wedge in the ceiling to jump into
+            parent.spawn(segment(vec2(320.0, 160.0), vec2(280.0, 40.0)));
+            parent.spawn(segment(vec2(320.0, 160.0), vec2(360.0, 40.0)));
+            //steep wall straight off the floor
+            parent.spawn(segment(vec2(512.0, 0.0), vec2(600.0, 160.0)));
+        }
+        TestLevel::PixelGaps => {
+            //floor with a single pixel hole in the middle of the start
+            parent.spawn(wall(vec2(-128.0, 0.0), vec2(255.0, THICKNESS), 0.0));
+            parent.spawn(wall(vec2(128.0, 0.0), vec2(255.0, THICKNESS), 0.0));
+            //a run of them
+            for i in 0..8 {
+                parent.spawn(wall(
+                    vec2(264.5 + i as f32 * 17.0, 0.0),
+                    vec2(16.0, THICKNESS),
+                    0.0,
+                ));
+            }
+            //ceiling a pixel lower than the player is tall
+            let gap = PLAYER_HEIGHT - 1.0;
+            parent.spawn(wall(
+                vec2(-160.0, THICKNESS + gap),
+                vec2(128.0, THICKNESS),
+                0.0,
+            ));
+        }
+        TestLevel::LongSlopes => {
+            let length = 1024.0;
+            parent.spawn(segment(vec2(-128.0, 0.0), vec2(128.0, 0.0)));
+            for (angle, side) in [(0.35_f32, 1.0), (FRAC_PI_4, -1.0)] {
+                let from = vec2(128.0 * side, 0.0);
+                let direction = vec2(angle.cos() * side, angle.sin());
+                parent.spawn(segment(from, from + direction * length));
+            }
+        }
+        TestLevel::PlatformOverSpikes => {
+            let width = 768.0;
+            parent.spawn(segment(vec2(-128.0, 0.0), vec2(128.0, 0.0)));
+            parent.spawn(segment(vec2(128.0 + width, 0.0), vec2(256.0 + width, 0.0)));
+            parent.spawn((
+                Name::new("Test spikes"),
+                Hitbox::new(Team::Hazard, 1, vec2(width / 2.0, 8.0)),
+                Sprite::from_color(tailwind::RED_600, vec2(width, 16.0)),
+                RenderLayer(RenderLayerOrder::Hazards),
+                Transform::from_xyz(128.0 + width / 2.0, -16.0, 0.0),
+            ));
+            let size = vec2(64.0, 8.0);
+            let from = vec2(160.0, 32.0);
+            parent.spawn((
+                Name::new("Test platform"),
+                RigidBody::Kinematic,
+                Collider::rectangle(size.x, size.y),
+                Sprite::from_color(WALL_COLOR, size),
+                Transform::from_translation(from.extend(RenderLayerOrder::Tiles.z())),
+                PingPong {
+                    from,
+                    to: vec2(96.0 + width, 32.0),
+                    speed: 80.0,
+                    forward: true,
+                },
+            ));
+        }
+        TestLevel::FerrisWheel => {
+            let radius = 96.0;
+            let size = vec2(48.0, 8.0);
+            parent.spawn(segment(vec2(-128.0, 0.0), vec2(128.0, 0.0)));
+            parent
+                .spawn((
+                    Name::new("Test wheel"),
+                    Spin { speed: 0.5 },
+                    Transform::from_xyz(256.0, radius + 32.0, 0.0),
+                    Visibility::default(),
+                ))
+                .with_children(|wheel| {
+                    for i in 0..4 {
+                        let offset = Vec2::from_angle(i as f32 * FRAC_PI_2) * radius;
+                        wheel.spawn((
+                            Name::new("Test wheel platform"),
+                            RigidBody::Kinematic,
+                            Collider::rectangle(size.x, size.y),
+                            Sprite::from_color(WALL_COLOR, size),
+                            Transform::from_translation(
+                                offset.extend(RenderLayerOrder::Tiles.z()),
+                            ),
+                            PlatformVelocity::default(),
+                        ));
+                    }
+                });
+        }
+    }
+}
+
+fn load_test_level(
+    load: On<LoadTestLevel>,
+    mut commands: Commands,
+    roots: Query<Entity, With<TestLevelRoot>>,
+    mut mario: Query<(&mut Transform, &mut KinematicController, &mut LastMoveOutput), With<Char>>,
+) {
+    for root in roots.iter() {
+        commands.entity(root).despawn();
+    }
+    let level = load.0;
+    info!("loading test level {level:?}");
+    commands
+        .spawn((
+            Name::new(format!("Test level {level:?}")),
+            TestLevelRoot,
+            Transform::from_translation(ORIGIN.extend(0.0)),
+            Visibility::default(),
+        ))
+        .with_children(|parent| build(level, parent));
+
+    for (mut transform, mut controller, mut last_output) in mario.iter_mut() {
+        transform.translation = (ORIGIN + START).extend(transform.translation.z);
+        controller.velocity = Vec2::ZERO;
+        //not a move and slide result, don't let the invariant checks complain
+        last_output.0 = None;
+    }
+}
+
+fn spin(mut spinning: Query<(&Spin, &mut Transform)>, time: Res<Time>) {
+    for (spin, mut transform) in spinning.iter_mut() {
+        transform.rotate_z(spin.speed * time.delta_secs());
+    }
+}
+
+fn move_platforms(mut platforms: Query<(&mut PingPong, &mut Transform)>, time: Res<Time>) {
+    for (mut platform, mut transform) in platforms.iter_mut() {
+        let target = if platform.forward {
+            platform.to
+        } else {
+            platform.from
+        };
+        let position = transform.translation.xy();
+        let step = platform.speed * time.delta_secs();
+        let next = position + (target - position).clamp_length_max(step);
+        if next == target {
+            platform.forward = !platform.forward;
+        }
+        transform.translation = next.extend(transform.translation.z);
+    }
+}
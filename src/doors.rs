@@ -0,0 +1,215 @@
+//! Speed doors: doors that only stay open for a few seconds after being triggered.
+//!
+//! `SpeedDoor` entities have an `open_seconds` float and a `trigger` string, either `switch` (any
+//! switch hit opens them) or `start` (they open as soon as the level spawns). While a door is open
+//! the HUD counts down the time left and a tick plays every second.
+
+use crate::audio::PlaySound;
+use crate::physics::CharacterSystems;
+use crate::screens::Screen;
+use crate::snapshot::Snapshotted;
+use crate::switch_blocks::SwitchHit;
+use crate::time::PausableSystems;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_cobweb_ui::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+pub enum DoorTrigger {
+    #[default]
+    Switch,
+    LevelStart,
+}
+
+#[derive(Component, Clone, Debug, Default, Reflect)]
+#[reflect(Component)]
+#[require(Snapshotted)]
+pub struct SpeedDoor {
+    pub open_seconds: f32,
+    pub trigger: DoorTrigger,
+    /// running while the door is open
+    pub timer: Option<Timer>,
+}
+
+impl From<&EntityInstance> for SpeedDoor {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            open_seconds: entity_instance
+                .get_float_field("open_seconds")
+                .copied()
+                .unwrap_or(5.0),
+            trigger: match entity_instance.get_string_field("trigger") {
+                Ok(Some(trigger)) if trigger == "start" => DoorTrigger::LevelStart,
+                _ => DoorTrigger::Switch,
+            },
+            timer: None,
+        }
+    }
+}
+
+/// Slides a kinematic body between its spawn position and an open offset.
+#[derive(Component, Clone, Debug, Default)]
+#[require(PlatformVelocity)]
+pub struct DoorMover {
+    pub offset: Vec2,
+    /// pixels per second
+    pub speed: f32,
+    pub open: bool,
+    /// where the door was spawned, filled in on the first frame
+    home: Option<Vec2>,
+}
+
+impl From<&EntityInstance> for DoorMover {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            //doors slide up out of the way by default
+            offset: vec2(0.0, entity_instance.height as f32),
+            speed: entity_instance
+                .get_float_field("speed")
+                .copied()
+                .unwrap_or(120.0),
+            open: false,
+            home: None,
+        }
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct DoorBody {
+    collider: Collider,
+    rb: RigidBody,
+}
+
+impl From<&EntityInstance> for DoorBody {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            collider: Collider::rectangle(
+                entity_instance.width as f32,
+                entity_instance.height as f32,
+            ),
+            rb: RigidBody::Kinematic,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct SpeedDoorBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    door: SpeedDoor,
+    #[from_entity_instance]
+    mover: DoorMover,
+    #[from_entity_instance]
+    body: DoorBody,
+}
+
+#[derive(Component)]
+struct DoorCountdown;
+
+const TICK_SOUND: &str = "door_tick";
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<SpeedDoorBundle>("SpeedDoor")
+        .add_systems(OnEnter(Screen::Game), spawn_countdown)
+        .add_systems(
+            Update,
+            (
+                open_on_level_start,
+                tick_doors.in_set(PausableSystems),
+                show_countdown,
+            )
+                .chain(),
+        )
+        //moved in step with physics so riders get a steady platform velocity
+        .add_systems(FixedUpdate, move_doors.before(CharacterSystems::Sense))
+        .add_observer(open_on_switch);
+}
+
+fn open(door: &mut SpeedDoor, mover: &mut DoorMover) {
+    door.timer = Some(Timer::from_seconds(door.open_seconds, TimerMode::Once));
+    mover.open = true;
+}
+
+fn open_on_switch(_hit: On<SwitchHit>, mut doors: Query<(&mut SpeedDoor, &mut DoorMover)>) {
+    for (mut door, mut mover) in doors.iter_mut() {
+        if door.trigger == DoorTrigger::Switch {
+            open(&mut door, &mut mover);
+        }
+    }
+}
+
+fn open_on_level_start(mut doors: Query<(&mut SpeedDoor, &mut DoorMover), Added<SpeedDoor>>) {
+    for (mut door, mut mover) in doors.iter_mut() {
+        if door.trigger == DoorTrigger::LevelStart {
+            open(&mut door, &mut mover);
+        }
+    }
+}
+
+fn tick_doors(
+    mut commands: Commands,
+    mut doors: Query<(&mut SpeedDoor, &mut DoorMover)>,
+    time: Res<Time>,
+) {
+    let mut tick = false;
+    for (mut door, mut mover) in doors.iter_mut() {
+        let Some(timer) = door.timer.as_mut() else {
+            continue;
+        };
+        let before = timer.remaining_secs().ceil();
+        timer.tick(time.delta());
+        //one tick per second no matter how many doors are open
+        tick |= timer.remaining_secs().ceil() < before;
+        if timer.is_finished() {
+            door.timer = None;
+            mover.open = false;
+        }
+    }
+    if tick {
+        commands.trigger(PlaySound::new(TICK_SOUND));
+    }
+}
+
+fn move_doors(mut doors: Query<(&mut DoorMover, &mut Transform)>, time: Res<Time>) {
+    for (mut mover, mut transform) in doors.iter_mut() {
+        let home = *mover.home.get_or_insert(transform.translation.xy());
+        let target = if mover.open { home + mover.offset } else { home };
+        let position = transform.translation.xy();
+        let step = mover.speed * time.delta_secs();
+        let next = position + (target - position).clamp_length_max(step);
+        transform.translation = next.extend(transform.translation.z);
+    }
+}
+
+fn spawn_countdown(mut c: Commands, mut s: SceneBuilder) {
+    c.ui_root()
+        .spawn_scene(("ui/main.cob", "door_countdown"), &mut s, |sc| {
+            sc.insert(DespawnOnExit(Screen::Game));
+            sc.get("text").insert(DoorCountdown);
+        });
+}
+
+fn show_countdown(
+    mut c: Commands,
+    doors: Query<&SpeedDoor>,
+    countdown: Query<Entity, With<DoorCountdown>>,
+    mut shown: Local<Option<u32>>,
+) {
+    let Ok(countdown) = countdown.single() else {
+        return;
+    };
+    //the door closing soonest is the one that matters
+    let remaining = doors
+        .iter()
+        .filter_map(|door| door.timer.as_ref())
+        .map(|timer| timer.remaining_secs().ceil() as u32)
+        .min();
+    if remaining == *shown {
+        return;
+    }
+    *shown = remaining;
+    let text = remaining.map_or(String::new(), |seconds| seconds.to_string());
+    c.ui_builder(countdown).update_text(text);
+}
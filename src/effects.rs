@@ -0,0 +1,142 @@
+//! Short visual feedback on any sprite, so things that light up or bounce when touched don't each
+//! write [`Sprite::color`] themselves.
+//!
+//! Triggering an [`EffectRequest`] on an entity plays its [`Effect`] on top of whatever the sprite
+//! looks like. Effects stack, and the sprite goes back to how it was once the last one is over.
+//! Colors written by anything else in the meantime become the new base.
+
+use crate::time::Pause;
+use bevy::prelude::*;
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, Reflect)]
+pub enum Effect {
+    /// turns `color` at once, then fades back over `duration` seconds
+    Flash { color: Color, duration: f32 },
+    /// grows to `scale` and back over `duration` seconds
+    Pulse { scale: f32, duration: f32 },
+    /// stays `color` for `duration` seconds
+    Tint { color: Color, duration: f32 },
+}
+
+impl Effect {
+    /// A quick bright flash, for pickups and checkpoints.
+    pub fn flash() -> Self {
+        Effect::Flash {
+            color: Color::srgb(1.0, 0.95, 0.5),
+            duration: 0.2,
+        }
+    }
+
+    /// A quick bump in size, for blocks and switches.
+    pub fn pulse() -> Self {
+        Effect::Pulse {
+            scale: 1.25,
+            duration: 0.15,
+        }
+    }
+
+    fn duration(&self) -> f32 {
+        match *self {
+            Effect::Flash { duration, .. }
+            | Effect::Pulse { duration, .. }
+            | Effect::Tint { duration, .. } => duration,
+        }
+    }
+}
+
+/// Plays an [`Effect`] on a sprite entity.
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct EffectRequest {
+    pub entity: Entity,
+    pub effect: Effect,
+}
+
+impl EffectRequest {
+    pub fn new(entity: Entity, effect: Effect) -> Self {
+        Self { entity, effect }
+    }
+}
+
+/// The effects playing on an entity and how it looked without them.
+#[derive(Component, Clone, Debug)]
+struct ActiveEffects {
+    /// every effect with the seconds it has been playing
+    effects: Vec<(Effect, f32)>,
+    base_color: Color,
+    base_scale: Vec3,
+    /// what the effects last wrote, to notice anything else writing over it
+    written: (Color, Vec3),
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    //after everything else is done writing the sprites for this frame
+    app.add_systems(
+        PostUpdate,
+        play_effects
+            .before(TransformSystems::Propagate)
+            .run_if(in_state(Pause(false))),
+    )
+    .add_observer(start_effect);
+}
+
+fn start_effect(
+    request: On<EffectRequest>,
+    mut commands: Commands,
+    mut sprites: Query<(&Sprite, &Transform, Option<&mut ActiveEffects>)>,
+) {
+    let Ok((sprite, transform, active)) = sprites.get_mut(request.entity) else {
+        return;
+    };
+    match active {
+        Some(mut active) => active.effects.push((request.effect, 0.0)),
+        None => {
+            commands.entity(request.entity).insert(ActiveEffects {
+                effects: vec![(request.effect, 0.0)],
+                base_color: sprite.color,
+                base_scale: transform.scale,
+                written: (sprite.color, transform.scale),
+            });
+        }
+    }
+}
+
+fn play_effects(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Sprite, &mut Transform, &mut ActiveEffects)>,
+    time: Res<Time>,
+) {
+    for (entity, mut sprite, mut transform, mut active) in query.iter_mut() {
+        if sprite.color != active.written.0 {
+            active.base_color = sprite.color;
+        }
+        if transform.scale != active.written.1 {
+            active.base_scale = transform.scale;
+        }
+        let mut color = active.base_color;
+        let mut scale = active.base_scale;
+        for (effect, elapsed) in &active.effects {
+            let t = (elapsed / effect.duration().max(f32::EPSILON)).min(1.0);
+            match *effect {
+                Effect::Flash { color: flash, .. } => color = color.mix(&flash, 1.0 - t),
+                Effect::Pulse { scale: pulse, .. } => {
+                    scale *= 1.0 + (pulse - 1.0) * (t * PI).sin();
+                }
+                Effect::Tint { color: tint, .. } => color = tint,
+            }
+        }
+        let delta = time.delta_secs();
+        active.effects.retain_mut(|(effect, elapsed)| {
+            *elapsed += delta;
+            *elapsed < effect.duration()
+        });
+        if active.effects.is_empty() {
+            color = active.base_color;
+            scale = active.base_scale;
+            commands.entity(entity).remove::<ActiveEffects>();
+        }
+        sprite.color = color;
+        transform.scale = scale;
+        active.written = (color, scale);
+    }
+}
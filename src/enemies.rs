@@ -0,0 +1,236 @@
+//! Enemies and the spawners (pipes) that emit them.
+//!
+//! `Spawner` entities read an `enemy` string field naming an [`EntityRegistry`] entry, plus
+//! `interval` (seconds), `max_alive`, `radius` (only spawns while the player is this close) and
+//! `despawn_on_leave` (clears its enemies once the player walks out of range).
+//!
+//! Anything hitting an enemy goes through [`DamageEnemy`], which takes it off the enemy's
+//! [`Health`] and despawns the enemy once that runs out. Enemies without one die to any hit.
+
+use crate::behavior::BehaviorTree;
+use crate::combat::{HitLanded, Hitbox, Hurtbox, Team};
+use crate::effects::{Effect, EffectRequest};
+use crate::jump::JumpStats;
+use crate::layers::{RenderLayer, RenderLayerOrder};
+use crate::mario::Char;
+use crate::physics::CharacterControllerBundle;
+use crate::player_state::Health;
+use crate::registry::EntityRegistry;
+use crate::screens::level_ready;
+use crate::separation::SoftSeparation;
+use crate::snapshot::Snapshotted;
+use crate::time::PausableSystems;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[require(Snapshotted)]
+pub struct Enemy;
+
+/// Takes `damage` off an enemy's [`Health`].
+#[derive(EntityEvent, Clone, Copy, Debug)]
+pub struct DamageEnemy {
+    pub entity: Entity,
+    pub damage: u32,
+}
+
+const HURT_EFFECT: Effect = Effect::Flash {
+    color: Color::WHITE,
+    duration: 0.15,
+};
+
+#[derive(Component, Clone, Debug, Reflect)]
+#[relationship(relationship_target = SpawnedEnemies)]
+pub struct SpawnedBy(pub Entity);
+
+//enemies go away with their spawner
+#[derive(Component, Clone, Debug, Reflect)]
+#[relationship_target(relationship = SpawnedBy, linked_spawn)]
+pub struct SpawnedEnemies(Vec<Entity>);
+
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+#[require(Snapshotted)]
+pub struct Spawner {
+    pub enemy: String,
+    pub timer: Timer,
+    pub max_alive: usize,
+    pub radius: f32,
+    pub despawn_on_leave: bool,
+}
+
+impl Default for Spawner {
+    fn default() -> Self {
+        Self {
+            enemy: "Walker".into(),
+            timer: Timer::from_seconds(3.0, TimerMode::Repeating),
+            max_alive: 3,
+            radius: 200.0,
+            despawn_on_leave: false,
+        }
+    }
+}
+
+impl From<&EntityInstance> for Spawner {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let default = Spawner::default();
+        Self {
+            enemy: match entity_instance.get_string_field("enemy") {
+                Ok(Some(enemy)) => enemy.clone(),
+                _ => default.enemy,
+            },
+            timer: entity_instance
+                .get_float_field("interval")
+                .ok()
+                .map_or(default.timer, |&interval| {
+                    Timer::from_seconds(interval, TimerMode::Repeating)
+                }),
+            max_alive: entity_instance
+                .get_int_field("max_alive")
+                .ok()
+                .map_or(default.max_alive, |&max| max.max(0) as usize),
+            radius: entity_instance
+                .get_float_field("radius")
+                .copied()
+                .unwrap_or(default.radius),
+            despawn_on_leave: entity_instance
+                .get_bool_field("despawn_on_leave")
+                .copied()
+                .unwrap_or(default.despawn_on_leave),
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct SpawnerBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    spawner: Spawner,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.world_mut()
+        .get_resource_or_init::<EntityRegistry>()
+        .register("Walker", spawn_walker);
+    app.world_mut()
+        .get_resource_or_init::<EntityRegistry>()
+        .register("Brawler", spawn_brawler);
+    app.register_ldtk_entity::<SpawnerBundle>("Spawner")
+        .add_systems(
+            Update,
+            run_spawners.in_set(PausableSystems).run_if(level_ready),
+        )
+        .add_observer(hurt_enemies)
+        .add_observer(damage_enemy);
+}
+
+/// Walks in a straight line and turns around at walls, see [`BehaviorTree::walker`].
+pub fn spawn_walker(commands: &mut Commands, position: Vec3) -> Entity {
+    commands
+        .spawn((
+            Name::new("Walker"),
+            Enemy,
+            BehaviorTree::walker(),
+            Sprite::from_color(Color::srgb(0.6, 0.3, 0.1), Vec2::splat(14.0)),
+            Transform::from_translation(position),
+            RenderLayer(RenderLayerOrder::Entities),
+            CharacterControllerBundle::new(Collider::circle(7.0)),
+            JumpStats::default(),
+            Hurtbox::new(Team::Enemy, Vec2::splat(7.0)),
+            Hitbox::new(Team::Enemy, 1, Vec2::splat(6.0)),
+            SoftSeparation::new(Team::Enemy, Vec2::splat(9.0)),
+        ))
+        .id()
+}
+
+/// Patrols, chases, lunges and runs away, see [`BehaviorTree::brawler`].
+pub fn spawn_brawler(commands: &mut Commands, position: Vec3) -> Entity {
+    commands
+        .spawn((
+            Name::new("Brawler"),
+            Enemy,
+            BehaviorTree::brawler(),
+            Health { current: 3, max: 3 },
+            Sprite::from_color(Color::srgb(0.5, 0.1, 0.2), Vec2::splat(14.0)),
+            Transform::from_translation(position),
+            RenderLayer(RenderLayerOrder::Entities),
+            CharacterControllerBundle::new(Collider::circle(7.0)),
+            JumpStats::default(),
+            Hurtbox::new(Team::Enemy, Vec2::splat(7.0)),
+            Hitbox::new(Team::Enemy, 1, Vec2::splat(6.0)),
+            SoftSeparation::new(Team::Enemy, Vec2::splat(9.0)),
+        ))
+        .id()
+}
+
+fn run_spawners(
+    mut commands: Commands,
+    players: Query<&Transform, With<Char>>,
+    mut spawners: Query<(
+        Entity,
+        &mut Spawner,
+        &GlobalTransform,
+        Option<&SpawnedEnemies>,
+    )>,
+    registry: Res<EntityRegistry>,
+    time: Res<Time>,
+) {
+    for (entity, mut spawner, xf, spawned) in spawners.iter_mut() {
+        let alive = spawned.map_or(0, |spawned| spawned.len());
+        //the nearest player keeps a spawner going
+        let distance = players
+            .iter()
+            .map(|transform| transform.translation.xy().distance(xf.translation().xy()))
+            .reduce(f32::min)
+            .unwrap_or(f32::INFINITY);
+        if distance > spawner.radius {
+            if spawner.despawn_on_leave && alive > 0 {
+                commands.entity(entity).despawn_related::<SpawnedEnemies>();
+            }
+            spawner.timer.reset();
+            continue;
+        }
+        if !spawner.timer.tick(time.delta()).just_finished() || alive >= spawner.max_alive {
+            continue;
+        }
+        if let Some(enemy) = registry.spawn(&spawner.enemy, &mut commands, xf.translation()) {
+            commands.entity(enemy).insert(SpawnedBy(entity));
+        }
+    }
+}
+
+fn hurt_enemies(
+    hit: On<HitLanded>,
+    mut commands: Commands,
+    enemies: Query<(), With<Enemy>>,
+    players: Query<(), With<Char>>,
+) {
+    //the player's feet only hurt by stomping, see stomp.rs
+    if !enemies.contains(hit.target) || players.contains(hit.attacker) {
+        return;
+    }
+    commands.trigger(DamageEnemy {
+        entity: hit.target,
+        damage: hit.damage,
+    });
+}
+
+fn damage_enemy(
+    damage: On<DamageEnemy>,
+    mut commands: Commands,
+    mut enemies: Query<Option<&mut Health>, With<Enemy>>,
+) {
+    let Ok(health) = enemies.get_mut(damage.entity) else {
+        return;
+    };
+    if let Some(mut health) = health {
+        health.current = health.current.saturating_sub(damage.damage);
+        if health.current > 0 {
+            commands.trigger(EffectRequest::new(damage.entity, HURT_EFFECT));
+            return;
+        }
+    }
+    commands.entity(damage.entity).despawn();
+}
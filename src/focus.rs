@@ -0,0 +1,144 @@
+//! Pauses the game while its window is in the background.
+//!
+//! Browsers throttle or stop hidden tabs, so the game would otherwise jump ahead by however long
+//! the tab was away once it comes back. Natively nothing stops, the player just isn't looking.
+//! On wasm the game carries on once the tab is back; natively it waits for a key or button press,
+//! so clicking back into the window doesn't drop the player straight into a jump.
+//!
+//! All of it can be turned off in `assets/focus.ron`, for streamers who keep the game in the
+//! background on purpose.
+
+use crate::locomotion::LocomotionDriver;
+use crate::storage;
+use crate::time::{Pause, PauseEvent};
+use crate::ui::Toast;
+use bevy::prelude::*;
+use bevy::window::WindowFocused;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+const SETTINGS_PATH: &str = "assets/focus.ron";
+
+/// `assets/focus.ron`
+#[derive(Resource, Debug, Clone, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct FocusSettings {
+    /// pause when the window loses focus
+    pub pause: bool,
+    /// also mute all audio while paused that way
+    pub mute: bool,
+    /// stay paused after focus comes back until something is pressed
+    pub wait_for_input: bool,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            pause: true,
+            mute: false,
+            wait_for_input: !cfg!(target_arch = "wasm32"),
+        }
+    }
+}
+
+impl FocusSettings {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(ron::from_str(&storage::read(path)?)?)
+    }
+
+    fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        storage::write(path, &string)
+    }
+}
+
+#[derive(Default, PartialEq, Eq, Debug)]
+enum Blur {
+    #[default]
+    Focused,
+    /// paused by us and still in the background
+    Away,
+    /// back in focus, waiting for a press to resume
+    Waiting,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let settings = FocusSettings::read(SETTINGS_PATH).unwrap_or_else(|e| {
+        info!("no focus settings ({e}), writing defaults");
+        let settings = FocusSettings::default();
+        if let Err(e) = settings.write(SETTINGS_PATH) {
+            warn!("write error {e}");
+        }
+        settings
+    });
+    app.register_type::<FocusSettings>()
+        .insert_resource(settings)
+        .add_systems(Update, pause_on_blur);
+}
+
+/// Only resumes what it paused itself, a pause from anywhere else stays. Likewise only the sinks
+/// it muted get unmuted again.
+fn pause_on_blur(
+    mut commands: Commands,
+    mut focus_events: MessageReader<WindowFocused>,
+    settings: Res<FocusSettings>,
+    pause: Res<State<Pause>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut gamepads: Query<&mut Gamepad>,
+    mut sinks: Query<(Entity, &mut AudioSink)>,
+    mut drivers: Query<&mut LocomotionDriver>,
+    mut blur: Local<Blur>,
+    mut muted: Local<Vec<Entity>>,
+) {
+    for event in focus_events.read() {
+        if !event.focused && *blur == Blur::Focused && settings.pause && !pause.get().0 {
+            info!("window lost focus, pausing");
+            next_pause.set(Pause(true));
+            commands.trigger(PauseEvent::Enable);
+            if settings.mute {
+                for (entity, mut sink) in sinks.iter_mut().filter(|(_, sink)| !sink.is_muted()) {
+                    sink.mute();
+                    muted.push(entity);
+                }
+            }
+            *blur = Blur::Away;
+        } else if !event.focused && *blur == Blur::Waiting {
+            *blur = Blur::Away;
+        } else if event.focused && *blur == Blur::Away {
+            *blur = Blur::Waiting;
+            if settings.wait_for_input {
+                commands.trigger(Toast("paused, press any key".to_string()));
+            }
+        }
+    }
+    if *blur != Blur::Waiting {
+        return;
+    }
+    let pressed = keys.get_just_pressed().next().is_some()
+        || gamepads
+            .iter()
+            .any(|pad| pad.get_just_pressed().next().is_some());
+    if settings.wait_for_input && !pressed {
+        return;
+    }
+    info!("resuming");
+    next_pause.set(Pause(false));
+    commands.trigger(PauseEvent::Disable);
+    for entity in muted.drain(..) {
+        if let Ok((_, mut sink)) = sinks.get_mut(entity) {
+            sink.unmute();
+        }
+    }
+    //the press that resumed is spent, it shouldn't also jump or walk
+    keys.reset_all();
+    for mut gamepad in gamepads.iter_mut() {
+        gamepad.digital_mut().reset_all();
+    }
+    for mut driver in drivers.iter_mut() {
+        driver.jump_requested = false;
+    }
+    *blur = Blur::Focused;
+}
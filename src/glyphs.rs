@@ -0,0 +1,306 @@
+//! Maps actions to the glyph of their binding on whichever device the player used last.
+//!
+//! HUD prompts use [`InputPrompt`] for text like "Press {jump} to jump",
+//! and [`InputGlyphIcon`] for an image of a single action's button.
+//!
+//! Which icons exist is read once from a manifest embedded in the binary, see [`GlyphIcons`].
+
+use crate::input::{InputProfile, InputSettings};
+use bevy::platform::collections::HashSet;
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum GamepadFamily {
+    Xbox,
+    PlayStation,
+    Nintendo,
+    Generic,
+}
+
+impl GamepadFamily {
+    pub fn from_vendor(vendor_id: Option<u16>) -> Self {
+        match vendor_id {
+            Some(0x045E) => GamepadFamily::Xbox,
+            Some(0x054C) => GamepadFamily::PlayStation,
+            Some(0x057E) => GamepadFamily::Nintendo,
+            _ => GamepadFamily::Generic,
+        }
+    }
+
+    fn dir(self) -> &'static str {
+        match self {
+            GamepadFamily::Xbox => "xbox",
+            GamepadFamily::PlayStation => "playstation",
+            GamepadFamily::Nintendo => "nintendo",
+            GamepadFamily::Generic => "gamepad",
+        }
+    }
+}
+
+/// The device the player touched last, glyphs follow it.
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+#[reflect(Resource)]
+pub enum ActiveDevice {
+    #[default]
+    Keyboard,
+    Gamepad(GamepadFamily),
+}
+
+/// Actions that can be shown in prompts, `{jump}` etc. in prompt text.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+pub enum PromptAction {
+    Jump,
+    Run,
+    Respawn,
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl PromptAction {
+    const ALL: [PromptAction; 7] = [
+        PromptAction::Jump,
+        PromptAction::Run,
+        PromptAction::Respawn,
+        PromptAction::Up,
+        PromptAction::Down,
+        PromptAction::Left,
+        PromptAction::Right,
+    ];
+
+    fn placeholder(self) -> &'static str {
+        match self {
+            PromptAction::Jump => "{jump}",
+            PromptAction::Run => "{run}",
+            PromptAction::Respawn => "{respawn}",
+            PromptAction::Up => "{up}",
+            PromptAction::Down => "{down}",
+            PromptAction::Left => "{left}",
+            PromptAction::Right => "{right}",
+        }
+    }
+
+    fn bindings(self, profile: &InputProfile) -> Vec<Binding> {
+        let [north, east, south, west] = profile.movement;
+        match self {
+            PromptAction::Jump => profile.jump.to_vec(),
+            PromptAction::Run => profile.run.to_vec(),
+            PromptAction::Respawn => profile.respawn.to_vec(),
+            PromptAction::Up => vec![north],
+            PromptAction::Down => vec![south],
+            PromptAction::Left => vec![west],
+            PromptAction::Right => vec![east],
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glyph {
+    pub label: String,
+    /// asset path of an icon for the glyph, if one exists
+    pub icon: Option<String>,
+}
+
+/// Asset paths of the glyph icons that exist, from `assets/images/glyphs/manifest.ron`.
+#[derive(Resource, Default, Debug)]
+pub struct GlyphIcons(HashSet<String>);
+
+impl GlyphIcons {
+    fn from_manifest(manifest: &str) -> Self {
+        match ron::from_str::<Vec<String>>(manifest) {
+            Ok(icons) => Self(icons.into_iter().collect()),
+            Err(e) => {
+                warn!("could not read the glyph manifest {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
+/// Looks up the glyph of `action` in `profile` for `device`.
+pub fn glyph(
+    action: PromptAction,
+    profile: &InputProfile,
+    device: ActiveDevice,
+    icons: &GlyphIcons,
+) -> Option<Glyph> {
+    action
+        .bindings(profile)
+        .into_iter()
+        .find_map(|binding| binding_glyph(binding, device, icons))
+}
+
+fn binding_glyph(binding: Binding, device: ActiveDevice, icons: &GlyphIcons) -> Option<Glyph> {
+    let (label, icon) = match (binding, device) {
+        (Binding::Keyboard { key, .. }, ActiveDevice::Keyboard) => {
+            let name = format!("{key:?}");
+            let label = name
+                .trim_start_matches("Key")
+                .trim_start_matches("Digit")
+                .trim_start_matches("Arrow")
+                .to_string();
+            (label, format!("images/glyphs/keyboard/{}.png", name.to_lowercase()))
+        }
+        (Binding::GamepadButton(button), ActiveDevice::Gamepad(family)) => (
+            button_label(button, family),
+            format!("images/glyphs/{}/{button:?}.png", family.dir()).to_lowercase(),
+        ),
+        _ => return None,
+    };
+    let icon = icons.0.contains(&icon).then_some(icon);
+    Some(Glyph { label, icon })
+}
+
+fn button_label(button: GamepadButton, family: GamepadFamily) -> String {
+    use GamepadButton::*;
+    let label = match (button, family) {
+        (South, GamepadFamily::Xbox) | (East, GamepadFamily::Nintendo) => "A",
+        (East, GamepadFamily::Xbox) | (South, GamepadFamily::Nintendo) => "B",
+        (West, GamepadFamily::Xbox) | (North, GamepadFamily::Nintendo) => "X",
+        (North, GamepadFamily::Xbox) | (West, GamepadFamily::Nintendo) => "Y",
+        (South, GamepadFamily::PlayStation) => "Cross",
+        (East, GamepadFamily::PlayStation) => "Circle",
+        (West, GamepadFamily::PlayStation) => "Square",
+        (North, GamepadFamily::PlayStation) => "Triangle",
+        (LeftTrigger, GamepadFamily::PlayStation) => "L1",
+        (RightTrigger, GamepadFamily::PlayStation) => "R1",
+        (LeftTrigger2, GamepadFamily::PlayStation) => "L2",
+        (RightTrigger2, GamepadFamily::PlayStation) => "R2",
+        (LeftTrigger, _) => "LB",
+        (RightTrigger, _) => "RB",
+        (LeftTrigger2, _) => "LT",
+        (RightTrigger2, _) => "RT",
+        _ => return format!("{button:?}"),
+    };
+    label.to_string()
+}
+
+/// Text with action placeholders such as `{jump}` replaced by `[glyph]`.
+#[derive(Component, Clone, Debug)]
+pub struct InputPrompt(pub String);
+
+/// Shows the icon of `0`'s glyph on this [`ImageNode`].
+#[derive(Component, Clone, Copy, Debug)]
+#[require(ImageNode)]
+pub struct InputGlyphIcon(pub PromptAction);
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<ActiveDevice>()
+        .insert_resource(GlyphIcons::from_manifest(include_str!(
+            "../assets/images/glyphs/manifest.ron"
+        )))
+        .add_systems(
+            Update,
+            (
+                detect_active_device,
+                (update_prompts, update_icons).run_if(
+                    resource_changed::<ActiveDevice>.or(resource_changed::<InputSettings>),
+                ),
+                (update_new_prompts, update_new_icons),
+            )
+                .chain(),
+        );
+}
+
+/// How far a stick has to move before the gamepad counts as used.
+const STICK_THRESHOLD: f32 = 0.5;
+
+fn detect_active_device(
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut device: ResMut<ActiveDevice>,
+) {
+    let new_device = if keys.get_just_pressed().next().is_some() {
+        ActiveDevice::Keyboard
+    } else if let Some(gamepad) = gamepads.iter().find(|gamepad| {
+        gamepad.get_just_pressed().next().is_some()
+            || gamepad.left_stick().length() > STICK_THRESHOLD
+            || gamepad.right_stick().length() > STICK_THRESHOLD
+    }) {
+        ActiveDevice::Gamepad(GamepadFamily::from_vendor(gamepad.vendor_id()))
+    } else {
+        return;
+    };
+    //only trigger change detection on an actual switch
+    device.set_if_neq(new_device);
+}
+
+fn prompt_text(
+    template: &str,
+    profile: &InputProfile,
+    device: ActiveDevice,
+    icons: &GlyphIcons,
+) -> String {
+    PromptAction::ALL
+        .into_iter()
+        .fold(template.to_string(), |text, action| {
+            let label = glyph(action, profile, device, icons)
+                .map_or("?".to_string(), |glyph| glyph.label);
+            text.replace(action.placeholder(), &format!("[{label}]"))
+        })
+}
+
+fn update_prompts(
+    mut prompts: Query<(&InputPrompt, &mut Text)>,
+    settings: Res<InputSettings>,
+    device: Res<ActiveDevice>,
+    icons: Res<GlyphIcons>,
+) {
+    let profile = settings.profile();
+    for (prompt, mut text) in prompts.iter_mut() {
+        text.0 = prompt_text(&prompt.0, &profile, *device, &icons);
+    }
+}
+
+fn update_new_prompts(
+    mut prompts: Query<(&InputPrompt, &mut Text), Added<InputPrompt>>,
+    settings: Res<InputSettings>,
+    device: Res<ActiveDevice>,
+    icons: Res<GlyphIcons>,
+) {
+    let profile = settings.profile();
+    for (prompt, mut text) in prompts.iter_mut() {
+        text.0 = prompt_text(&prompt.0, &profile, *device, &icons);
+    }
+}
+
+fn set_icon(
+    icon: &InputGlyphIcon,
+    image: &mut ImageNode,
+    profile: &InputProfile,
+    device: ActiveDevice,
+    icons: &GlyphIcons,
+    asset_server: &AssetServer,
+) {
+    image.image = glyph(icon.0, profile, device, icons)
+        .and_then(|glyph| glyph.icon)
+        .map_or_else(Handle::default, |path| asset_server.load(path));
+}
+
+fn update_icons(
+    mut icons: Query<(&InputGlyphIcon, &mut ImageNode)>,
+    settings: Res<InputSettings>,
+    device: Res<ActiveDevice>,
+    glyph_icons: Res<GlyphIcons>,
+    asset_server: Res<AssetServer>,
+) {
+    let profile = settings.profile();
+    for (icon, mut image) in icons.iter_mut() {
+        set_icon(icon, &mut image, &profile, *device, &glyph_icons, &asset_server);
+    }
+}
+
+fn update_new_icons(
+    mut icons: Query<(&InputGlyphIcon, &mut ImageNode), Added<InputGlyphIcon>>,
+    settings: Res<InputSettings>,
+    device: Res<ActiveDevice>,
+    glyph_icons: Res<GlyphIcons>,
+    asset_server: Res<AssetServer>,
+) {
+    let profile = settings.profile();
+    for (icon, mut image) in icons.iter_mut() {
+        set_icon(icon, &mut image, &profile, *device, &glyph_icons, &asset_server);
+    }
+}
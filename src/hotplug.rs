@@ -0,0 +1,114 @@
+//! Gamepads plugged in and out while playing.
+//!
+//! Losing the gamepad a player is using pauses the game with a toast, and prompts fall back to the
+//! keyboard. A player bound to that gamepad alone, like a second player from
+//! [`split_screen`](crate::split_screen), waits for the next gamepad that connects and gets bound
+//! to it. The game resumes once a gamepad is back or anything is pressed, only if it was paused
+//! here.
+
+use crate::glyphs::{ActiveDevice, GamepadFamily};
+use crate::mario::Char;
+use crate::split_screen::PlayerSlot;
+use crate::time::{Pause, PauseEvent};
+use crate::ui::Toast;
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+
+/// This player's gamepad was unplugged, the next one plugged in is theirs.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct WaitingForGamepad;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(Update, handle_hotplug);
+}
+
+/// Only resumes what it paused itself, a pause from anywhere else stays.
+fn handle_hotplug(
+    mut commands: Commands,
+    mut connections: MessageReader<GamepadConnectionEvent>,
+    gamepads: Query<(Entity, &Gamepad)>,
+    mut players: Query<(Entity, Option<&PlayerSlot>, Option<&mut GamepadDevice>), With<Char>>,
+    waiting: Query<(Entity, Option<&PlayerSlot>), With<WaitingForGamepad>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut device: ResMut<ActiveDevice>,
+    pause: Res<State<Pause>>,
+    mut next_pause: ResMut<NextState<Pause>>,
+    mut last_used: Local<Option<Entity>>,
+    mut paused: Local<bool>,
+) {
+    if let Some((pad, _)) = gamepads
+        .iter()
+        .find(|(_, gamepad)| gamepad.get_just_pressed().next().is_some())
+    {
+        *last_used = Some(pad);
+    }
+    let mut resume = false;
+    for event in connections.read() {
+        let pad = event.gamepad;
+        match &event.connection {
+            GamepadConnection::Disconnected => {
+                let mut lost = *last_used == Some(pad);
+                for (entity, slot, bound) in players.iter_mut() {
+                    let Some(mut bound) = bound else {
+                        continue;
+                    };
+                    if !matches!(*bound, GamepadDevice::Single(used) if used == pad) {
+                        continue;
+                    }
+                    *bound = GamepadDevice::None;
+                    commands.entity(entity).insert(WaitingForGamepad);
+                    let player = slot.map_or(0, |slot| slot.0) + 1;
+                    commands.trigger(Toast(format!("player {player} lost their gamepad")));
+                    lost = true;
+                }
+                if *last_used == Some(pad) {
+                    *last_used = None;
+                }
+                info!("gamepad {pad} disconnected");
+                if !lost {
+                    continue;
+                }
+                device.set_if_neq(ActiveDevice::Keyboard);
+                if !pause.get().0 && !*paused {
+                    next_pause.set(Pause(true));
+                    commands.trigger(PauseEvent::Enable);
+                    commands.trigger(Toast("gamepad disconnected, paused".to_string()));
+                    *paused = true;
+                }
+            }
+            GamepadConnection::Connected { name, vendor_id, .. } => {
+                info!("gamepad {pad} connected: {name}");
+                match waiting.iter().next() {
+                    Some((entity, slot)) => {
+                        commands
+                            .entity(entity)
+                            .remove::<WaitingForGamepad>()
+                            .insert(GamepadDevice::Single(pad));
+                        let player = slot.map_or(0, |slot| slot.0) + 1;
+                        commands.trigger(Toast(format!("{name} is player {player}'s now")));
+                    }
+                    None => commands.trigger(Toast(format!("{name} connected"))),
+                }
+                *last_used = Some(pad);
+                device.set_if_neq(ActiveDevice::Gamepad(GamepadFamily::from_vendor(*vendor_id)));
+                resume = true;
+            }
+        }
+    }
+    if !*paused {
+        return;
+    }
+    //switching to the keyboard instead is fine too
+    resume |= keys.get_just_pressed().next().is_some()
+        || gamepads
+            .iter()
+            .any(|(_, gamepad)| gamepad.get_just_pressed().next().is_some());
+    if !resume {
+        return;
+    }
+    info!("resuming after gamepad change");
+    next_pause.set(Pause(false));
+    commands.trigger(PauseEvent::Disable);
+    *paused = false;
+}
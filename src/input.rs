@@ -1,42 +1,45 @@
 use crate::mario::Char;
+use crate::storage;
+use crate::ui::Toast;
 use bevy::prelude::*;
 use bevy_enhanced_input::prelude::*;
 use ron::ser::PrettyConfig;
+use serde::de::IgnoredAny;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
-use std::fs;
 
-#[derive(Resource, Debug, Reflect, Clone, Deserialize, Serialize)]
+/// One complete set of bindings, see [`InputSettings::profiles`].
+#[derive(Debug, Reflect, Clone, Deserialize, Serialize)]
 #[serde(default)]
-pub struct InputSettings {
+pub struct InputProfile {
+    /// north, east, south, west
+    pub movement: [Binding; 4],
+    /// x and y axis of the movement stick
+    pub stick: [Binding; 2],
     pub jump: [Binding; 3],
     pub run: [Binding; 3],
     pub respawn: [Binding; 3],
 }
 
-impl InputSettings {
-    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
-        let string = fs::read_to_string(path)?;
-        let settings = ron::from_str(&string)?;
-        Ok(settings)
-    }
-
-    fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
-        let string = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
-        fs::write(path, string)?;
-        Ok(())
-    }
-
+impl InputProfile {
     fn clear(&mut self) {
+        self.movement.fill(Binding::None);
+        self.stick.fill(Binding::None);
         self.respawn.fill(Binding::None);
         self.jump.fill(Binding::None);
         self.run.fill(Binding::None);
     }
-}
 
-impl Default for InputSettings {
-    fn default() -> Self {
+    pub fn wasd() -> Self {
         Self {
+            movement: [
+                KeyCode::KeyW.into(),
+                KeyCode::KeyD.into(),
+                KeyCode::KeyS.into(),
+                KeyCode::KeyA.into(),
+            ],
+            stick: [GamepadAxis::LeftStickX.into(), GamepadAxis::LeftStickY.into()],
             jump: [
                 KeyCode::Space.into(),
                 GamepadButton::South.into(),
@@ -50,7 +53,177 @@ impl Default for InputSettings {
             respawn: [KeyCode::KeyR.into(), Binding::None, Binding::None],
         }
     }
+
+    pub fn arrows() -> Self {
+        Self {
+            movement: [
+                KeyCode::ArrowUp.into(),
+                KeyCode::ArrowRight.into(),
+                KeyCode::ArrowDown.into(),
+                KeyCode::ArrowLeft.into(),
+            ],
+            jump: [KeyCode::KeyZ.into(), GamepadButton::South.into(), Binding::None],
+            run: [
+                KeyCode::KeyX.into(),
+                GamepadButton::West.into(),
+                Binding::None,
+            ],
+            ..Self::wasd()
+        }
+    }
+
+    /// mirrored for left handed players, movement on the right side
+    pub fn southpaw() -> Self {
+        Self {
+            movement: [
+                KeyCode::KeyI.into(),
+                KeyCode::KeyL.into(),
+                KeyCode::KeyK.into(),
+                KeyCode::KeyJ.into(),
+            ],
+            stick: [GamepadAxis::RightStickX.into(), GamepadAxis::RightStickY.into()],
+            jump: [
+                KeyCode::Space.into(),
+                GamepadButton::South.into(),
+                Binding::None,
+            ],
+            run: [
+                KeyCode::ShiftRight.into(),
+                GamepadButton::RightTrigger.into(),
+                Binding::None,
+            ],
+            respawn: [KeyCode::Backspace.into(), Binding::None, Binding::None],
+        }
+    }
+
+    pub fn gamepad() -> Self {
+        Self {
+            movement: [
+                GamepadButton::DPadUp.into(),
+                GamepadButton::DPadRight.into(),
+                GamepadButton::DPadDown.into(),
+                GamepadButton::DPadLeft.into(),
+            ],
+            stick: [GamepadAxis::LeftStickX.into(), GamepadAxis::LeftStickY.into()],
+            jump: [GamepadButton::South.into(), Binding::None, Binding::None],
+            run: [
+                GamepadButton::West.into(),
+                GamepadButton::RightTrigger.into(),
+                Binding::None,
+            ],
+            respawn: [GamepadButton::Select.into(), Binding::None, Binding::None],
+        }
+    }
+
+    pub fn export(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        storage::write(path, &string)?;
+        Ok(())
+    }
+
+    pub fn import(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = storage::read(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
+
+impl Default for InputProfile {
+    fn default() -> Self {
+        Self::wasd()
+    }
+}
+
+#[derive(Resource, Debug, Reflect, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InputSettings {
+    /// key into `profiles`
+    pub active: String,
+    pub profiles: HashMap<String, InputProfile>,
+}
+
+/// Just enough of input.ron to tell whether it was written before profiles existed.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct ProfilesHeader {
+    profiles: Option<IgnoredAny>,
+}
+
+/// Name of the profile holding the bindings of an input.ron from before profiles.
+const MIGRATED_PROFILE: &str = "custom";
+
+impl InputSettings {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = storage::read(path)?;
+        let ProfilesHeader { profiles } = ron::from_str(&string)?;
+        if profiles.is_some() {
+            return Ok(ron::from_str(&string)?);
+        }
+        //the old file had the bindings of one profile at the top level
+        warn!("{path} predates input profiles, moving its bindings to \"{MIGRATED_PROFILE}\"");
+        let mut settings = Self::default();
+        settings
+            .profiles
+            .insert(MIGRATED_PROFILE.to_string(), ron::from_str(&string)?);
+        settings.active = MIGRATED_PROFILE.to_string();
+        settings.write(path)?;
+        Ok(settings)
+    }
+
+    /// The settings as saved right now, for picking up edits made while the game runs.
+    pub fn read_saved() -> Result<Self, Box<dyn Error>> {
+        Self::read(SETTINGS_PATH)
+    }
+
+    fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default())?;
+        storage::write(path, &string)?;
+        Ok(())
+    }
+
+    fn clear(&mut self) {
+        if let Some(profile) = self.profiles.get_mut(&self.active) {
+            profile.clear();
+        }
+    }
+
+    /// The active profile, falling back to the default layout if it doesn't exist.
+    pub fn profile(&self) -> InputProfile {
+        self.profiles.get(&self.active).cloned().unwrap_or_default()
+    }
 }
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        Self {
+            active: "wasd".to_string(),
+            profiles: HashMap::from([
+                ("wasd".to_string(), InputProfile::wasd()),
+                ("arrows".to_string(), InputProfile::arrows()),
+                ("southpaw".to_string(), InputProfile::southpaw()),
+                ("gamepad".to_string(), InputProfile::gamepad()),
+            ]),
+        }
+    }
+}
+
+/// Makes the named profile the active one.
+#[derive(Event, Clone, Debug)]
+pub struct SwitchInputProfile(pub String);
+
+/// Writes the named profile to its own file so it can be shared.
+#[derive(Event, Clone, Debug)]
+pub struct ExportInputProfile {
+    pub name: String,
+    pub path: String,
+}
+
+/// Reads a profile written by [`ExportInputProfile`] and stores it under `name`.
+#[derive(Event, Clone, Debug)]
+pub struct ImportInputProfile {
+    pub name: String,
+    pub path: String,
+}
+
 #[derive(InputAction)]
 #[action_output(Vec2)]
 pub struct Move;
@@ -72,8 +245,11 @@ pub struct Respawn;
 pub(crate) fn plugin(app: &mut App) {
     let input = InputSettings::default();
     app.add_plugins(EnhancedInputPlugin)
-        .add_input_context::<Char>();
-    let res = InputSettings::read("assets/input.ron");
+        .add_input_context::<Char>()
+        .add_observer(switch_profile)
+        .add_observer(export_profile)
+        .add_observer(import_profile);
+    let res = InputSettings::read(SETTINGS_PATH);
     match res {
         Ok(settings) => {
             info!("input found! inserting {settings:?}");
@@ -82,7 +258,7 @@ pub(crate) fn plugin(app: &mut App) {
         Err(e) => match e.downcast_ref::<std::io::Error>() {
             Some(e) if e.kind() == std::io::ErrorKind::NotFound => {
                 info!("file not found, writing default input");
-                let _ = input.write("assets/input.ron");
+                let _ = input.write(SETTINGS_PATH);
                 app.insert_resource(input);
             }
             Some(e) => {
@@ -94,3 +270,68 @@ pub(crate) fn plugin(app: &mut App) {
         },
     }
 }
+
+const SETTINGS_PATH: &str = "assets/input.ron";
+/// Where the menu exports the active profile to and imports one from.
+pub const SHARED_PROFILE_PATH: &str = "shared_input.ron";
+/// Name an imported profile is stored under.
+pub const IMPORTED_PROFILE: &str = "imported";
+
+fn switch_profile(
+    switch: On<SwitchInputProfile>,
+    mut commands: Commands,
+    mut settings: ResMut<InputSettings>,
+) {
+    if !settings.profiles.contains_key(&switch.0) {
+        warn!("no input profile named {}", switch.0);
+        return;
+    }
+    info!("switching to input profile {}", switch.0);
+    commands.trigger(Toast(format!("controls: {}", switch.0)));
+    settings.active = switch.0.clone();
+    if let Err(e) = settings.write(SETTINGS_PATH) {
+        warn!("write error {e}");
+    }
+}
+
+fn export_profile(
+    export: On<ExportInputProfile>,
+    mut commands: Commands,
+    settings: Res<InputSettings>,
+) {
+    let Some(profile) = settings.profiles.get(&export.name) else {
+        warn!("no input profile named {}", export.name);
+        return;
+    };
+    match profile.export(&export.path) {
+        Ok(()) => {
+            info!("exported input profile {} to {}", export.name, export.path);
+            commands.trigger(Toast(format!("controls exported to {}", export.path)));
+        }
+        Err(e) => {
+            warn!("write error {e}");
+            commands.trigger(Toast("could not export the controls".to_string()));
+        }
+    }
+}
+
+fn import_profile(
+    import: On<ImportInputProfile>,
+    mut commands: Commands,
+    mut settings: ResMut<InputSettings>,
+) {
+    match InputProfile::import(&import.path) {
+        Ok(profile) => {
+            info!("imported input profile {} from {}", import.name, import.path);
+            commands.trigger(Toast(format!("controls imported as {}", import.name)));
+            settings.profiles.insert(import.name.clone(), profile);
+            if let Err(e) = settings.write(SETTINGS_PATH) {
+                warn!("write error {e}");
+            }
+        }
+        Err(e) => {
+            warn!("could not import input profile {e}");
+            commands.trigger(Toast(format!("nothing to import at {}", import.path)));
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Jumping and gravity for anything with [`JumpStats`], on top of the [`LocomotionDriver`] it
+//! shares with walking.
+
+use crate::locomotion::{
+    ground_at, half_size, slip_off_edges, Climbing, LocomotionDriver, MoveStats,
+    CROUCH_THRESHOLD,
+};
+use crate::physics::{
+    apply_external_forces, CharacterSystems, Grounded, KinematicController, PlatformVelocity,
+    Submerged, SurfaceVelocity, UpDirection,
+};
+use crate::time::TimeSince;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// State of a charge jump, see [`ChargeJump`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct JumpCharge {
+    /// seconds charged so far, `None` while not charging
+    pub charging: Option<f32>,
+    /// a charged jump is rising, it keeps full height even though jump was let go
+    pub launched: bool,
+}
+
+impl JumpCharge {
+    /// How far along the charge is, from 0 to 1.
+    pub fn fraction(&self, stats: &JumpStats) -> f32 {
+        match (self.charging, &stats.charge) {
+            (Some(time), Some(charge)) => (time / charge.max_time).min(1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+/// Snapshot of the last jump's takeoff.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct JumpTakeoff {
+    pub velocity: Vec2,
+    pub backflip: bool,
+}
+
+#[derive(Component, Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+#[require(JumpCharge, JumpTakeoff)]
+pub struct JumpStats {
+    /// height reached when jump is held until the apex
+    pub max_height: f32,
+    /// height reached when jump is only tapped
+    pub min_height: f32,
+    /// seconds from takeoff to the apex of a full jump
+    pub jump_time: f32,
+    /// seconds from the apex of a full jump back to the takeoff height
+    pub fall_time: f32,
+    /// grace period after leaving the ground where jumping is still allowed
+    pub coyote_time: f32,
+    pub max_fall_speed: f32,
+    /// holding jump while crouched charges a higher jump, off if `None`
+    pub charge: Option<ChargeJump>,
+    /// extra jump height at full run speed, relative to `max_height`
+    pub speed_height_bonus: f32,
+    /// jumping while skidding flips back the way we're now pushing, off if `None`
+    pub backflip: Option<Backflip>,
+    /// late jumps after walking off a ledge, on top of `coyote_time`, off if `None`
+    pub ledge_assist: Option<LedgeAssist>,
+    /// how much of the velocity of the platform we jump off is added to the jump
+    pub platform_inheritance: f32,
+    /// moving through [`Water`](crate::physics::Water), water is like air if `None`
+    pub swim: Option<SwimStats>,
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SwimStats {
+    /// gravity underwater relative to falling
+    pub gravity_scale: f32,
+    /// fraction of the velocity lost per second
+    pub drag: f32,
+    /// upward speed of a stroke, jumping underwater is always allowed
+    pub stroke_speed: f32,
+    pub max_sink_speed: f32,
+}
+
+impl Default for SwimStats {
+    fn default() -> Self {
+        SwimStats {
+            gravity_scale: 0.25,
+            drag: 2.0,
+            stroke_speed: 110.0,
+            max_sink_speed: 60.0,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LedgeAssist {
+    /// seconds after `coyote_time` runs out
+    pub extra_time: f32,
+    /// how far behind us the ledge can be
+    pub max_distance: f32,
+    /// how far below it we can have dropped
+    pub max_drop: f32,
+}
+
+impl Default for LedgeAssist {
+    fn default() -> Self {
+        LedgeAssist {
+            extra_time: 0.1,
+            max_distance: 12.0,
+            max_drop: 8.0,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct Backflip {
+    /// jump height relative to `max_height`
+    pub height_scale: f32,
+    pub horizontal_speed: f32,
+    /// slowest we can be going the other way and still count as skidding
+    pub min_skid_speed: f32,
+}
+
+impl Default for Backflip {
+    fn default() -> Self {
+        Backflip {
+            height_scale: 1.5,
+            horizontal_speed: 40.0,
+            min_skid_speed: 60.0,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ChargeJump {
+    /// seconds to fully charge, the jump goes off by itself after this
+    pub max_time: f32,
+    /// jump height at full charge, relative to `max_height`
+    pub max_height_scale: f32,
+}
+
+impl Default for ChargeJump {
+    fn default() -> Self {
+        ChargeJump {
+            max_time: 1.0,
+            max_height_scale: 2.5,
+        }
+    }
+}
+
+impl JumpStats {
+    pub fn jump_velocity(&self) -> f32 {
+        2.0 * self.max_height / self.jump_time
+    }
+    pub fn rise_gravity(&self) -> f32 {
+        2.0 * self.max_height / self.jump_time.powi(2)
+    }
+    pub fn fall_gravity(&self) -> f32 {
+        2.0 * self.max_height / self.fall_time.powi(2)
+    }
+    /// gravity that cuts a jump short around `min_height` once jump is released
+    pub fn release_gravity(&self) -> f32 {
+        self.jump_velocity().powi(2) / (2.0 * self.min_height)
+    }
+}
+
+impl Default for JumpStats {
+    fn default() -> Self {
+        JumpStats {
+            max_height: 64.0,
+            min_height: 16.0,
+            jump_time: 0.4,
+            fall_time: 0.3,
+            coyote_time: 0.1,
+            max_fall_speed: 400.0,
+            charge: None,
+            speed_height_bonus: 0.0,
+            backflip: None,
+            ledge_assist: None,
+            platform_inheritance: 1.0,
+            swim: None,
+        }
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<JumpCharge>()
+        .register_type::<JumpTakeoff>()
+        .add_systems(
+            FixedUpdate,
+            (
+                jump.after(slip_off_edges)
+                    .after(CharacterSystems::Sense)
+                    .before(CharacterSystems::Forces),
+                apply_gravity
+                    .in_set(CharacterSystems::Forces)
+                    .before(apply_external_forces),
+            ),
+        );
+}
+
+pub fn apply_gravity(
+    mut query: Query<
+        (
+            &mut KinematicController,
+            &JumpStats,
+            Option<&GravityScale>,
+            Option<&LocomotionDriver>,
+            Option<&JumpCharge>,
+            Option<&UpDirection>,
+            Has<Submerged>,
+        ),
+        //climbing holds on against gravity
+        Without<Climbing>,
+    >,
+    time: Res<Time>,
+) {
+    for (mut controller, stats, scale, driver, charge, up, submerged) in query.iter_mut() {
+        let up = up.copied().unwrap_or_default();
+        let delta = time.delta_secs();
+        let scale = scale.map_or(1.0, |scale| scale.0);
+        if let Some(swim) = stats.swim.as_ref().filter(|_| submerged) {
+            controller.velocity *= (1.0 - swim.drag * delta).max(0.0);
+            let gravity = stats.fall_gravity() * swim.gravity_scale * scale;
+            let speed = up.speed(controller.velocity);
+            let speed = (speed - gravity * delta).max(-swim.max_sink_speed);
+            up.set_speed(&mut controller.velocity, speed);
+            continue;
+        }
+        let jump_held = driver.is_some_and(|driver| driver.jump_held)
+            || charge.is_some_and(|charge| charge.launched);
+        let speed = up.speed(controller.velocity);
+        let gravity = match speed {
+            y if y > 0.0 && jump_held => stats.rise_gravity(),
+            y if y > 0.0 => stats.release_gravity(),
+            _ => stats.fall_gravity(),
+        };
+        let speed = (speed - gravity * scale * delta).max(-stats.max_fall_speed);
+        up.set_speed(&mut controller.velocity, speed);
+    }
+}
+
+/// Whether the ground we walked off is still close behind us, see
+/// [`LedgeAssist`].
+fn ledge_behind(
+    spatial_query: &SpatialQuery,
+    entity: Entity,
+    position: Vec2,
+    collider: Option<&Collider>,
+    velocity: Vec2,
+    up: UpDirection,
+    stats: &JumpStats,
+    time_since: f32,
+) -> bool {
+    let (Some(assist), Some(collider)) = (&stats.ledge_assist, collider) else {
+        return false;
+    };
+    if time_since > stats.coyote_time + assist.extra_time || up.speed(velocity) > 0.0 {
+        return false;
+    }
+    let half_size = half_size(collider, up.0);
+    let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+    let reach = half_size.y + assist.max_drop;
+    let sideways = velocity.dot(-up.0.perp());
+    let behind = [-1.0, 1.0]
+        .into_iter()
+        //standing still, it could be on either side
+        .filter(|side| sideways == 0.0 || side * sideways < 0.0);
+    behind.any(|side| {
+        let offset = side * (half_size.x + assist.max_distance);
+        ground_at(spatial_query, &filter, position, up.0, offset, reach)
+    })
+}
+
+fn jump(
+    mut query: Query<(
+        Entity,
+        &Transform,
+        Option<&Collider>,
+        &mut KinematicController,
+        &mut LocomotionDriver,
+        &mut TimeSince<Grounded>,
+        &mut JumpCharge,
+        &mut JumpTakeoff,
+        &JumpStats,
+        Option<&MoveStats>,
+        Option<&ShapeHits>,
+        Option<&UpDirection>,
+        Has<Submerged>,
+    )>,
+    platforms: Query<AnyOf<(&PlatformVelocity, &SurfaceVelocity)>>,
+    spatial_query: SpatialQuery,
+    time: Res<Time>,
+) {
+    for (
+        entity,
+        transform,
+        collider,
+        mut controller,
+        mut driver,
+        mut time_since,
+        mut charge,
+        mut takeoff,
+        stats,
+        move_stats,
+        ground_hits,
+        up,
+        submerged,
+    ) in query.iter_mut()
+    {
+        let up = up.copied().unwrap_or_default();
+        //whatever we're standing on that moves or carries us, for jumps to keep its momentum
+        let platform_velocity = ground_hits
+            .and_then(|hits| hits.iter().find_map(|hit| platforms.get(hit.entity).ok()))
+            .map_or(Vec2::ZERO, |(platform, surface)| {
+                let carried = platform.map_or(Vec2::ZERO, |platform| platform.velocity)
+                    + surface.map_or(Vec2::ZERO, |surface| surface.0);
+                carried * stats.platform_inheritance
+            });
+        if charge.launched && up.speed(controller.velocity) <= 0.0 {
+            charge.launched = false;
+        }
+        if let (Some(charged), Some(charge_stats)) = (charge.charging, &stats.charge) {
+            driver.jump_requested = false;
+            //walked or got knocked off the ground, the charge fizzles
+            if time_since.time > 0.0 {
+                charge.charging = None;
+                continue;
+            }
+            let charged = charged + time.delta_secs();
+            if driver.jump_held && charged < charge_stats.max_time {
+                charge.charging = Some(charged);
+                continue;
+            }
+            //let go or fully charged
+            let fraction = (charged / charge_stats.max_time).min(1.0);
+            let height_scale = 1.0 + (charge_stats.max_height_scale - 1.0) * fraction;
+            let speed = stats.jump_velocity() * height_scale.sqrt();
+            up.set_speed(&mut controller.velocity, speed);
+            controller.velocity += platform_velocity;
+            *takeoff = JumpTakeoff {
+                velocity: controller.velocity,
+                backflip: false,
+            };
+            *charge = JumpCharge {
+                charging: None,
+                launched: true,
+            };
+            time_since.time = f32::INFINITY;
+            continue;
+        }
+        if !driver.jump_requested {
+            continue;
+        }
+        driver.jump_requested = false;
+        //every press underwater is a stroke
+        if let Some(swim) = stats.swim.as_ref().filter(|_| submerged) {
+            up.set_speed(&mut controller.velocity, swim.stroke_speed);
+            continue;
+        }
+        if time_since.time > stats.coyote_time
+            && !ledge_behind(
+                &spatial_query,
+                entity,
+                transform.translation.xy(),
+                collider,
+                controller.velocity,
+                up,
+                stats,
+                time_since.time,
+            )
+        {
+            continue;
+        }
+        let crouched = driver.wish_dir.y < CROUCH_THRESHOLD && time_since.time == 0.0;
+        if stats.charge.is_some() && crouched {
+            charge.charging = Some(0.0);
+            continue;
+        }
+        let velocity_x = controller.velocity.x;
+        let skidding = stats.backflip.as_ref().filter(|backflip| {
+            driver.wish_dir.x * velocity_x < 0.0 && velocity_x.abs() >= backflip.min_skid_speed
+        });
+        let height_scale = match skidding {
+            Some(backflip) => {
+                controller.velocity.x = driver.wish_dir.x.signum() * backflip.horizontal_speed;
+                backflip.height_scale
+            }
+            None => {
+                let run_speed = move_stats.map_or(f32::INFINITY, |move_stats| move_stats.run_speed);
+                1.0 + stats.speed_height_bonus * (velocity_x.abs() / run_speed).min(1.0)
+            }
+        };
+        //height grows with the square of the takeoff speed
+        let speed = stats.jump_velocity() * height_scale.sqrt();
+        up.set_speed(&mut controller.velocity, speed);
+        controller.velocity += platform_velocity;
+        *takeoff = JumpTakeoff {
+            velocity: controller.velocity,
+            backflip: skidding.is_some(),
+        };
+        //no second jump until we land again
+        time_since.time = f32::INFINITY;
+    }
+}
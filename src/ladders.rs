@@ -0,0 +1,28 @@
+//! Ladders and vines placed in LDtk.
+//!
+//! A `Ladder` entity is a [`Climbable`] zone the size of the entity. It isn't solid, players with
+//! [`ClimbStats`](crate::locomotion::ClimbStats) grab on by holding up or down inside it.
+
+use crate::locomotion::Climbable;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+impl From<&EntityInstance> for Climbable {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            half_size: vec2(entity_instance.width as f32, entity_instance.height as f32) / 2.0,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct LadderBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    climbable: Climbable,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<LadderBundle>("Ladder");
+}
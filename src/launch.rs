@@ -0,0 +1,190 @@
+//! Command line arguments, for getting into a level quickly and for automated runs.
+//!
+//! ```text
+//! --level N               start in level N of the campaign, counting from 0
+//! --skip-menu             start with the main menu closed
+//! --speed 2.0             run the game faster or slower
+//! --record-replay out.rpl record the player's input into a replay
+//! --play-replay in.rpl    drive the player from a replay instead of input
+//! --headless-ticks N      run without a window or renderer for N gameplay ticks, then quit
+//! --import-ghost in.ron   race a ghost from a file, see [`race_ghosts`](crate::race_ghosts)
+//! --fetch-ghost URL       race a downloaded ghost, needs the `ghost_fetch` feature
+//! ```
+
+use crate::levels::SelectLevel;
+use crate::race_ghosts::ImportGhost;
+use crate::screens::level_ready;
+use crate::time::GameSpeed;
+use bevy::prelude::*;
+
+#[derive(Resource, Clone, Debug, Default)]
+pub struct LaunchArgs {
+    /// place in the campaign
+    pub level: Option<usize>,
+    pub skip_menu: bool,
+    pub speed: Option<f32>,
+    pub record_replay: Option<String>,
+    pub play_replay: Option<String>,
+    /// fixed ticks with the level ready before quitting
+    pub headless_ticks: Option<u32>,
+    pub import_ghost: Option<String>,
+    pub fetch_ghost: Option<String>,
+}
+
+impl LaunchArgs {
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().ok_or_else(|| format!("{arg} needs a value"));
+            match arg.as_str() {
+                "--level" => parsed.level = Some(parse_value(&arg, &value()?)?),
+                "--skip-menu" => parsed.skip_menu = true,
+                "--speed" => parsed.speed = Some(parse_value(&arg, &value()?)?),
+                "--record-replay" => parsed.record_replay = Some(value()?),
+                "--play-replay" => parsed.play_replay = Some(value()?),
+                "--headless-ticks" => parsed.headless_ticks = Some(parse_value(&arg, &value()?)?),
+                "--import-ghost" => parsed.import_ghost = Some(value()?),
+                "--fetch-ghost" => parsed.fetch_ghost = Some(value()?),
+                _ => return Err(format!("unknown argument {arg}")),
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// The arguments the game was started with, quitting with the usage on bad ones.
+    pub fn from_env() -> Self {
+        Self::parse(std::env::args().skip(1)).unwrap_or_else(|e| {
+            eprintln!("{e}\n\nusage:\n{USAGE}");
+            std::process::exit(2);
+        })
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.headless_ticks.is_some()
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{arg} can't take {value}"))
+}
+
+const USAGE: &str = "  --level N
+  --skip-menu
+  --speed SPEED
+  --record-replay PATH
+  --play-replay PATH
+  --headless-ticks N
+  --import-ghost PATH
+  --fetch-ghost URL";
+
+/// Run condition for the main menu showing up on start.
+pub fn show_menu(args: Option<Res<LaunchArgs>>) -> bool {
+    args.is_none_or(|args| !args.skip_menu)
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let args = app.world().get_resource::<LaunchArgs>().cloned().unwrap_or_default();
+    if let Some(speed) = args.speed {
+        app.insert_resource(GameSpeed(speed));
+    }
+    if let Some(level) = args.level {
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.trigger(SelectLevel(level));
+        });
+    }
+    if let Some(path) = args.import_ghost.clone() {
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.trigger(ImportGhost { path: path.clone() });
+        });
+    }
+    if let Some(url) = args.fetch_ghost.clone() {
+        #[cfg(feature = "ghost_fetch")]
+        app.add_systems(Startup, move |mut commands: Commands| {
+            commands.trigger(crate::race_ghosts::FetchGhost { url: url.clone() });
+        });
+        #[cfg(not(feature = "ghost_fetch"))]
+        warn!("can't fetch the ghost at {url}, the game was built without ghost_fetch");
+    }
+    if args.is_headless() {
+        app.add_systems(FixedUpdate, count_headless_ticks.run_if(level_ready));
+    }
+}
+
+fn count_headless_ticks(
+    args: Res<LaunchArgs>,
+    mut ticks: Local<u32>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    *ticks += 1;
+    if args.headless_ticks.is_some_and(|limit| *ticks >= limit) {
+        info!("ran {} headless ticks, quitting", *ticks);
+        exit.write(AppExit::Success);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<LaunchArgs, String> {
+        LaunchArgs::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn no_arguments() {
+        let args = parse(&[]).unwrap();
+        assert_eq!(args.level, None);
+        assert!(!args.skip_menu);
+        assert!(!args.is_headless());
+    }
+
+    #[test]
+    fn every_argument() {
+        let args = parse(&[
+            "--level",
+            "3",
+            "--skip-menu",
+            "--speed",
+            "2.5",
+            "--record-replay",
+            "out.rpl",
+            "--play-replay",
+            "in.rpl",
+            "--headless-ticks",
+            "600",
+            "--import-ghost",
+            "ghost.ron",
+            "--fetch-ghost",
+            "http://localhost/ghost.ron",
+        ])
+        .unwrap();
+        assert_eq!(args.level, Some(3));
+        assert!(args.skip_menu);
+        assert_eq!(args.speed, Some(2.5));
+        assert_eq!(args.record_replay.as_deref(), Some("out.rpl"));
+        assert_eq!(args.play_replay.as_deref(), Some("in.rpl"));
+        assert_eq!(args.headless_ticks, Some(600));
+        assert!(args.is_headless());
+        assert_eq!(args.import_ghost.as_deref(), Some("ghost.ron"));
+        assert_eq!(args.fetch_ghost.as_deref(), Some("http://localhost/ghost.ron"));
+    }
+
+    #[test]
+    fn missing_value() {
+        assert_eq!(parse(&["--level"]).unwrap_err(), "--level needs a value");
+    }
+
+    #[test]
+    fn bad_value() {
+        assert_eq!(parse(&["--speed", "fast"]).unwrap_err(), "--speed can't take fast");
+        assert!(parse(&["--level", "-1"]).is_err());
+    }
+
+    #[test]
+    fn unknown_argument() {
+        assert_eq!(parse(&["--fly"]).unwrap_err(), "unknown argument --fly");
+    }
+}
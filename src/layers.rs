@@ -0,0 +1,116 @@
+//! Draw order. Everything that cares about being in front of or behind something else gets its `z`
+//! from a [`RenderLayerOrder`] instead of a hand-picked number.
+//!
+//! LDtk layers are bucketed by identifier: layers named `Background*` go behind everything,
+//! `Foreground*` in front of the player (so decoration can hide it), the rest are regular tiles or
+//! entities. Layers in the same bucket keep the order they have in LDtk.
+//!
+//! LDtk entities with a `y_sort` bool field set (the player included) are sorted by their feet
+//! instead, lower on screen draws in front, for semi top-down sections.
+
+use bevy::prelude::*;
+use bevy_ecs_ldtk::ldtk::Type as LdtkLayerType;
+use bevy_ecs_ldtk::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Reflect)]
+pub enum RenderLayerOrder {
+    Background,
+    Tiles,
+    Entities,
+    Ghosts,
+    Player,
+    Hazards,
+    Foreground,
+    Popups,
+    Weather,
+    /// darkness and other full-view effects
+    Overlay,
+}
+
+impl RenderLayerOrder {
+    /// Room left between layers for things inside the same layer to order themselves.
+    const SPACING: f32 = 10.0;
+
+    pub const fn z(self) -> f32 {
+        self as u8 as f32 * Self::SPACING
+    }
+
+    fn of_ldtk_layer(layer: &LayerMetadata) -> Self {
+        if layer.identifier.starts_with("Background") {
+            RenderLayerOrder::Background
+        } else if layer.identifier.starts_with("Foreground") {
+            RenderLayerOrder::Foreground
+        } else if layer.layer_instance_type == LdtkLayerType::Entities {
+            RenderLayerOrder::Entities
+        } else {
+            RenderLayerOrder::Tiles
+        }
+    }
+}
+
+/// Puts an entity's `z` on its layer when added.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct RenderLayer(pub RenderLayerOrder);
+
+/// Sorts the entity within its layer by the height of its feet.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct YSort {
+    pub layer: RenderLayerOrder,
+    /// from the transform down to the feet
+    pub feet_offset: f32,
+}
+
+/// How far the z LDtk gave a layer is kept, so layers in the same bucket don't fight.
+const LDTK_ORDER_SCALE: f32 = 0.01;
+/// How much z a pixel of height is worth, sorting holds within `SPACING / 2 / Y_SORT_SCALE` of the origin.
+const Y_SORT_SCALE: f32 = 0.001;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<RenderLayer>()
+        .register_type::<YSort>()
+        .add_systems(
+            Update,
+            (order_ldtk_layers, apply_render_layers, mark_y_sorted),
+        )
+        .add_systems(PostUpdate, y_sort.before(TransformSystems::Propagate));
+}
+
+fn order_ldtk_layers(mut layers: Query<(&LayerMetadata, &mut Transform), Added<LayerMetadata>>) {
+    for (layer, mut transform) in layers.iter_mut() {
+        let order = RenderLayerOrder::of_ldtk_layer(layer);
+        transform.translation.z = order.z() + transform.translation.z * LDTK_ORDER_SCALE;
+    }
+}
+
+fn apply_render_layers(mut query: Query<(&RenderLayer, &mut Transform), Added<RenderLayer>>) {
+    for (layer, mut transform) in query.iter_mut() {
+        transform.translation.z = layer.0.z();
+    }
+}
+
+fn mark_y_sorted(
+    mut commands: Commands,
+    entities: Query<(Entity, &EntityInstance), Added<EntityInstance>>,
+) {
+    for (entity, instance) in entities.iter() {
+        if instance.get_bool_field("y_sort").copied().unwrap_or(false) {
+            commands.entity(entity).insert(YSort {
+                layer: RenderLayerOrder::Entities,
+                feet_offset: instance.height as f32 / 2.0,
+            });
+        }
+    }
+}
+
+fn y_sort(mut query: Query<(&YSort, &mut Transform, &GlobalTransform)>) {
+    for (sort, mut transform, global) in query.iter_mut() {
+        //parents (LDtk layers) add their own z, take it back out
+        let parent_z = global.translation().z - transform.translation.z;
+        let feet = global.translation().y - sort.feet_offset;
+        let spacing = RenderLayerOrder::SPACING;
+        let depth = (spacing / 2.0 - feet * Y_SORT_SCALE).clamp(0.0, spacing - 0.01);
+        transform.translation.z = sort.layer.z() + depth - parent_z;
+    }
+}
@@ -0,0 +1,344 @@
+//! The campaign: worlds in play order, each its own LDtk project, and what a demo build is
+//! allowed to show.
+//!
+//! `assets/campaign.ron` lists the worlds with the levels of each, in play order. Reaching a
+//! [`Goal`] moves on to the next level, into the next world after the last one. A world can stay
+//! locked until another world is cleared or enough levels are. A demo build (the `demo` feature,
+//! or `demo: true` in the file) only offers the first `demo_levels` of the campaign, and finishing
+//! the last of those shows a teaser for the full version instead of moving on. During a
+//! [race](crate::race) the goal only stops the clock of whoever reaches it.
+//!
+//! Without a campaign file the game is a single world of [`AppConfig::ldtk_path`].
+//!
+//! Worlds drawn at a different pixel scale set their own `length_unit`, and a level can override
+//! it with a `length_unit` float field. The [`PhysicsLengthUnit`] follows whichever level spawned
+//! last, falling back to [`AppConfig::length_unit`].
+
+use crate::app_config::AppConfig;
+use crate::mario::{Char, Goal};
+use crate::physics::perform_move_and_slide;
+use crate::race::racing;
+use crate::screens::level_ready;
+use crate::split_screen::PlayerSlot;
+use crate::ui::{Toast, UiSound};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_cobweb_ui::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
+
+const CAMPAIGN_PATH: &str = "assets/campaign.ron";
+/// How close to a goal counts as reaching it.
+pub const GOAL_REACH: f32 = 12.0;
+
+/// `assets/campaign.ron`
+#[derive(Resource, Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Campaign {
+    pub worlds: Vec<CampaignWorld>,
+    pub demo: bool,
+    /// how many levels from the start a demo has
+    pub demo_levels: usize,
+    /// shown after the last demo level
+    pub teaser: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CampaignWorld {
+    pub name: String,
+    /// LDtk project of the world, relative to the assets
+    pub ldtk: String,
+    /// level identifiers in play order
+    pub levels: Vec<String>,
+    pub unlock: Unlock,
+    /// physics length unit of the world's pixel scale, the app config's if `None`
+    pub length_unit: Option<f32>,
+}
+
+/// What opens up a world.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub enum Unlock {
+    #[default]
+    Always,
+    /// every level of the world with this name cleared
+    World(String),
+    /// this many levels cleared anywhere
+    LevelsCleared(usize),
+}
+
+/// A level by its place in the [`Campaign`].
+#[derive(Debug, Clone, Copy)]
+pub struct CampaignLevel<'a> {
+    pub world: usize,
+    pub identifier: &'a str,
+}
+
+impl Default for Campaign {
+    fn default() -> Self {
+        Self::single_world(&AppConfig::default().ldtk_path)
+    }
+}
+
+impl Campaign {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+
+    fn single_world(ldtk: &str) -> Self {
+        Self {
+            worlds: vec![CampaignWorld {
+                name: "World".to_string(),
+                ldtk: ldtk.to_string(),
+                levels: vec!["Level_0".to_string()],
+                unlock: Unlock::Always,
+                length_unit: None,
+            }],
+            demo: false,
+            demo_levels: 1,
+            teaser: "Thanks for playing the demo!".to_string(),
+        }
+    }
+
+    pub fn is_demo(&self) -> bool {
+        self.demo || cfg!(feature = "demo")
+    }
+
+    /// Every level of every world in play order.
+    pub fn levels(&self) -> impl Iterator<Item = CampaignLevel<'_>> {
+        self.worlds.iter().enumerate().flat_map(|(world, w)| {
+            w.levels.iter().map(move |identifier| CampaignLevel { world, identifier })
+        })
+    }
+
+    /// How many levels this build can play, from the start.
+    pub fn available(&self) -> usize {
+        let all = self.levels().count();
+        if self.is_demo() { self.demo_levels.min(all) } else { all }
+    }
+
+    pub fn level(&self, index: usize) -> Option<CampaignLevel<'_>> {
+        self.levels().take(self.available()).nth(index)
+    }
+
+    pub fn is_unlocked(&self, world: usize, progress: &LevelProgress) -> bool {
+        match self.worlds.get(world).map(|world| &world.unlock) {
+            None | Some(Unlock::Always) => true,
+            Some(Unlock::LevelsCleared(count)) => progress.cleared.len() >= *count,
+            Some(Unlock::World(name)) => {
+                let Some(required) = self.worlds.iter().position(|world| &world.name == name)
+                else {
+                    warn!("no world named {name} to unlock with");
+                    return true;
+                };
+                self.levels()
+                    .enumerate()
+                    .filter(|(_, level)| level.world == required)
+                    .all(|(index, _)| progress.cleared.contains(&index))
+            }
+        }
+    }
+}
+
+/// Where in the [`Campaign`] we are.
+#[derive(Resource, Debug, Default)]
+pub struct LevelProgress {
+    pub current: usize,
+    /// the goal of this level was reached already
+    completed: bool,
+    /// every level whose goal was reached, by place in the campaign
+    pub cleared: HashSet<usize>,
+}
+
+/// Loads a level from the [`Campaign`] by its place in it.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SelectLevel(pub usize);
+
+/// A player reached the goal of the current level.
+#[derive(Event, Clone, Debug)]
+pub struct LevelComplete {
+    /// iid of the level
+    pub level: String,
+    /// [`PlayerSlot`] of whoever got there first
+    pub player: usize,
+}
+
+#[derive(Component)]
+struct Teaser;
+
+pub(crate) fn plugin(app: &mut App) {
+    let ldtk_path = app.world().resource::<AppConfig>().ldtk_path.clone();
+    let campaign = Campaign::read(CAMPAIGN_PATH).unwrap_or_else(|e| {
+        warn!("could not read {CAMPAIGN_PATH}, using {ldtk_path} alone: {e}");
+        Campaign::single_world(&ldtk_path)
+    });
+    if campaign.is_demo() {
+        info!("demo build, {} of {} levels", campaign.available(), campaign.levels().count());
+    }
+    let first = campaign.level(0).map_or(LevelSelection::index(0), |level| {
+        LevelSelection::Identifier(level.identifier.to_string())
+    });
+    app.insert_resource(campaign)
+        .insert_resource(first)
+        .init_resource::<LevelProgress>()
+        .add_systems(Startup, spawn_world)
+        .add_systems(Update, (reset_progress, apply_length_unit))
+        .add_systems(
+            FixedUpdate,
+            reach_goal
+                .after(perform_move_and_slide)
+                .run_if(level_ready.and(not(racing))),
+        )
+        .add_observer(select_level)
+        .add_observer(next_level);
+}
+
+fn spawn_world(mut commands: Commands, asset_server: Res<AssetServer>, campaign: Res<Campaign>) {
+    let Some(world) = campaign.worlds.first() else {
+        warn!("the campaign has no worlds");
+        return;
+    };
+    commands.spawn(LdtkWorldBundle {
+        ldtk_handle: asset_server.load(world.ldtk.clone()).into(),
+        ..Default::default()
+    });
+}
+
+fn select_level(
+    select: On<SelectLevel>,
+    mut commands: Commands,
+    campaign: Res<Campaign>,
+    mut progress: ResMut<LevelProgress>,
+    mut level_selection: ResMut<LevelSelection>,
+    mut projects: Query<&mut LdtkProjectHandle>,
+    asset_server: Res<AssetServer>,
+) {
+    let Some(level) = campaign.level(select.0) else {
+        warn!("level {} is not in this build", select.0);
+        return;
+    };
+    if !campaign.is_unlocked(level.world, &progress) {
+        let name = &campaign.worlds[level.world].name;
+        commands.trigger(UiSound::Error);
+        commands.trigger(Toast(format!("{name} is still locked")));
+        return;
+    }
+    //a different world is a different project
+    let path = &campaign.worlds[level.world].ldtk;
+    let handle = asset_server.load(path.clone());
+    for mut project in projects.iter_mut() {
+        if project.handle != handle {
+            info!("loading world {path}");
+            project.handle = handle.clone();
+        }
+    }
+    info!("selected level {}", level.identifier);
+    progress.current = select.0;
+    *level_selection = LevelSelection::Identifier(level.identifier.to_string());
+}
+
+fn reset_progress(mut level_events: MessageReader<LevelEvent>, mut progress: ResMut<LevelProgress>) {
+    for event in level_events.read() {
+        if let LevelEvent::Spawned(_) = event {
+            progress.completed = false;
+        }
+    }
+}
+
+fn apply_length_unit(
+    mut level_events: MessageReader<LevelEvent>,
+    campaign: Res<Campaign>,
+    progress: Res<LevelProgress>,
+    config: Res<AppConfig>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut length_unit: ResMut<PhysicsLengthUnit>,
+) {
+    for event in level_events.read() {
+        let LevelEvent::Spawned(iid) = event else {
+            continue;
+        };
+        let level_unit = ldtk_projects
+            .single()
+            .ok()
+            .and_then(|handle| ldtk_project_assets.get(handle))
+            .and_then(|project| {
+                project
+                    .as_standalone()
+                    .get_loaded_level_by_iid(&iid.to_string())
+            })
+            .and_then(|level| level.raw().get_float_field("length_unit").ok().copied());
+        let world_unit = campaign
+            .level(progress.current)
+            .and_then(|level| campaign.worlds[level.world].length_unit);
+        let unit = level_unit.or(world_unit).unwrap_or(config.length_unit);
+        if length_unit.0 != unit {
+            info!("physics length unit of level {iid} is {unit}");
+            length_unit.0 = unit;
+        }
+    }
+}
+
+fn reach_goal(
+    mut commands: Commands,
+    mut progress: ResMut<LevelProgress>,
+    mario: Query<(&Transform, Option<&PlayerSlot>), With<Char>>,
+    goals: Query<&GlobalTransform, With<Goal>>,
+    levels: Query<&LevelIid>,
+) {
+    if progress.completed {
+        return;
+    }
+    let reached = mario.iter().find(|(transform, _)| {
+        let position = transform.translation.xy();
+        goals
+            .iter()
+            .any(|goal| goal.translation().xy().distance(position) <= GOAL_REACH)
+    });
+    if let Some((_, slot)) = reached {
+        progress.completed = true;
+        let current = progress.current;
+        progress.cleared.insert(current);
+        let level = levels.iter().next().map(ToString::to_string).unwrap_or_default();
+        commands.trigger(LevelComplete {
+            level,
+            player: slot.map_or(0, |slot| slot.0),
+        });
+    }
+}
+
+fn next_level(
+    _complete: On<LevelComplete>,
+    mut commands: Commands,
+    mut s: SceneBuilder,
+    campaign: Res<Campaign>,
+    progress: Res<LevelProgress>,
+    teaser: Query<(), With<Teaser>>,
+) {
+    let next = progress.current + 1;
+    if next < campaign.available() {
+        commands.trigger(SelectLevel(next));
+    } else if campaign.is_demo() && next < campaign.levels().count() {
+        if !teaser.is_empty() {
+            return;
+        }
+        let text = campaign.teaser.clone();
+        commands
+            .ui_root()
+            .spawn_scene(("ui/main.cob", "teaser"), &mut s, |sc| {
+                sc.insert(Teaser);
+                sc.get("text").update_text(text);
+                let entity = sc.id();
+                sc.on_pressed(move |mut commands: Commands| {
+                    commands.get_entity(entity)?.despawn();
+                    OK
+                });
+            });
+    } else {
+        commands.trigger(Toast("all levels cleared".to_string()));
+    }
+}
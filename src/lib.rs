@@ -0,0 +1,210 @@
+//! The game as a library, so tests, examples and headless runs can build an [`App`] from
+//! [`AppPlugin`] or from single plugins like [`physics::plugin`] and [`camera::plugin`].
+
+// Support configuring Bevy lints within code.
+#![cfg_attr(bevy_lint, feature(register_tool), register_tool(bevy))]
+
+mod animation;
+pub mod app_config;
+mod asset_tracking;
+mod audio;
+mod behavior;
+mod cannons;
+mod conveyors;
+mod cutscene;
+mod definitions;
+mod doors;
+mod effects;
+mod enemies;
+mod focus;
+#[cfg(feature = "dev")]
+mod dev_tools;
+mod glyphs;
+mod hotplug;
+mod input;
+mod jump;
+mod ladders;
+pub mod launch;
+mod layers;
+mod levels;
+mod lighting;
+mod liquid;
+mod locomotion;
+mod mario;
+mod mods;
+pub mod physics;
+mod playtest;
+mod player_state;
+mod popups;
+mod practice;
+mod quality;
+mod race;
+mod race_ghosts;
+mod registry;
+mod replay;
+mod score;
+mod screens;
+mod scripts;
+mod separation;
+mod snapshot;
+mod spawn_point;
+mod split_screen;
+mod storage;
+mod stomp;
+mod switch_blocks;
+mod ui;
+mod walls;
+mod warps;
+mod water;
+mod weather;
+
+pub mod camera;
+mod combat;
+pub mod time;
+
+use crate::app_config::{AppConfig, CONFIG_PATH};
+use crate::launch::LaunchArgs;
+use crate::time::{AppSystems, PausableSystems, Pause};
+use bevy::render::settings::WgpuSettings;
+use bevy::render::RenderPlugin;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitSettings;
+use bevy::{asset::AssetMetaCheck, prelude::*};
+use bevy_cobweb_ui::prelude::*;
+use seldom_state::prelude::*;
+
+pub use char_controller;
+
+#[derive(Copy, Clone, Component)]
+struct RotateComp;
+
+/// Everything that makes up the game. Insert [`LaunchArgs`] first to start it other than from
+/// the main menu.
+pub struct AppPlugin;
+
+impl Plugin for AppPlugin {
+    fn build(&self, app: &mut App) {
+        // Mods can replace assets, their source has to exist before the `AssetPlugin`.
+        mods::register_asset_source(app);
+
+        // Headless runs get no window and no GPU, for automated runs on machines without either.
+        let headless = app
+            .world()
+            .get_resource::<LaunchArgs>()
+            .is_some_and(LaunchArgs::is_headless);
+        // Read before there is a window to title, reported once logging is up.
+        let config_read = AppConfig::read(CONFIG_PATH);
+        let config = config_read.as_ref().ok().cloned().unwrap_or_default();
+
+        let mut wgpu = WgpuSettings::default();
+        if headless {
+            wgpu.backends = None;
+        }
+
+        // Add Bevy plugins.
+        app.add_plugins(
+            DefaultPlugins
+                .set(AssetPlugin {
+                    // Wasm builds will check for meta files (that don't exist) if this isn't set.
+                    // This causes errors and even panics on web build on itch.
+                    // See https://github.com/bevyengine/bevy_github_ci_template/issues/48.
+                    meta_check: AssetMetaCheck::Never,
+                    ..default()
+                })
+                .set(if headless {
+                    WindowPlugin {
+                        primary_window: None,
+                        exit_condition: ExitCondition::DontExit,
+                        ..default()
+                    }
+                } else {
+                    WindowPlugin {
+                        primary_window: Window {
+                            title: config.title.clone(),
+                            fit_canvas_to_parent: true,
+                            ..default()
+                        }
+                        .into(),
+                        ..default()
+                    }
+                })
+                .set(RenderPlugin {
+                    render_creation: wgpu.into(),
+                    ..default()
+                })
+                .set(ImagePlugin::default_nearest()),
+        );
+        if let Err(e) = &config_read {
+            warn!("couldn't read {CONFIG_PATH}, using the defaults: {e}");
+        }
+        app.register_type::<AppConfig>().insert_resource(config);
+        if headless {
+            // Without a focused window winit would only wake up now and then.
+            app.insert_resource(WinitSettings::continuous());
+        }
+
+        // Add other plugins.
+        app.add_plugins((
+            asset_tracking::plugin,
+            audio::plugin,
+            screens::plugin,
+            ui::plugin,
+            input::plugin,
+            glyphs::plugin,
+            locomotion::plugin,
+            jump::plugin,
+            mario::plugin,
+            physics::plugin,
+            (
+                combat::plugin,
+                score::plugin,
+                stomp::plugin,
+                popups::plugin,
+                separation::plugin,
+                snapshot::plugin,
+                practice::plugin,
+                race_ghosts::plugin,
+                mods::plugin,
+                scripts::plugin,
+                behavior::plugin,
+                levels::plugin,
+                quality::plugin,
+                focus::plugin,
+                playtest::plugin,
+            ),
+            (
+                launch::plugin,
+                replay::plugin,
+                cutscene::plugin,
+                split_screen::plugin,
+                race::plugin,
+                animation::plugin,
+                water::plugin,
+                ladders::plugin,
+                effects::plugin,
+                hotplug::plugin,
+            ),
+            #[cfg(feature = "dev")]
+            dev_tools::plugin,
+            CobwebUiPlugin,
+            StateMachinePlugin::default(),
+            time::plugin,
+        ))
+        .load("ui/main.cob");
+
+        // Order new `AppSystems` variants by adding them here:
+        app.configure_sets(
+            Update,
+            (
+                AppSystems::TickTimers,
+                AppSystems::RecordInput,
+                AppSystems::Update,
+            )
+                .chain(),
+        );
+
+        // Set up the `Pause` state.
+        app.init_state::<Pause>();
+        app.configure_sets(Update, PausableSystems.run_if(in_state(Pause(false))));
+    }
+}
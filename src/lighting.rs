@@ -0,0 +1,231 @@
+//! Dark levels with light sources cut out of the darkness.
+//!
+//! A level's `darkness` float field (0 is fully lit, 1 is pitch black) sets how dark it is.
+//! `LitZone` entities override that with their own `darkness` while the player is inside them, and
+//! the overlay fades between the two instead of popping. The player always carries a lamp, `Torch`
+//! entities add more lights.
+//!
+//! The overlay is a small image covering the camera view, redrawn on the CPU every frame.
+
+use crate::camera::FollowTargets;
+use crate::layers::RenderLayerOrder;
+use crate::mario::{nearest_player, Char};
+use bevy::asset::RenderAssetUsages;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy_ecs_ldtk::prelude::*;
+
+/// Something that pushes back the darkness around it.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct LightSource {
+    pub radius: f32,
+}
+
+impl Default for LightSource {
+    fn default() -> Self {
+        Self { radius: 64.0 }
+    }
+}
+
+impl From<&EntityInstance> for LightSource {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            radius: entity_instance
+                .get_float_field("radius")
+                .copied()
+                .unwrap_or(LightSource::default().radius),
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct TorchBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    light: LightSource,
+}
+
+#[derive(Component, Clone, Debug, Default)]
+pub struct LitZone {
+    pub darkness: f32,
+    pub half_size: Vec2,
+}
+
+impl From<&EntityInstance> for LitZone {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            darkness: entity_instance
+                .get_float_field("darkness")
+                .copied()
+                .unwrap_or(0.0),
+            half_size: vec2(entity_instance.width as f32, entity_instance.height as f32) / 2.0,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct LitZoneBundle {
+    #[from_entity_instance]
+    zone: LitZone,
+}
+
+#[derive(Resource, Debug, Default)]
+pub struct Darkness {
+    /// darkness of the current level
+    pub level: f32,
+    /// what's actually drawn, eases towards the level or zone darkness
+    pub current: f32,
+}
+
+#[derive(Component)]
+struct DarknessOverlay;
+
+/// Overlay resolution, the image gets stretched over the whole view.
+const OVERLAY_SIZE: UVec2 = UVec2::new(160, 90);
+/// How fast the darkness eases towards its target, per second.
+const FADE_RATE: f32 = 3.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<LightSource>()
+        .init_resource::<Darkness>()
+        .register_ldtk_entity::<TorchBundle>("Torch")
+        .register_ldtk_entity::<LitZoneBundle>("LitZone")
+        .add_systems(
+            Update,
+            (read_level_darkness, fade_darkness, spawn_overlay, draw_overlay).chain(),
+        )
+        .add_observer(give_player_lamp);
+}
+
+fn give_player_lamp(add: On<Add, Char>, mut commands: Commands) {
+    commands.entity(add.entity).insert(LightSource::default());
+}
+
+fn read_level_darkness(
+    mut level_events: MessageReader<LevelEvent>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut darkness: ResMut<Darkness>,
+) {
+    for event in level_events.read() {
+        let LevelEvent::Spawned(iid) = event else {
+            continue;
+        };
+        let Some(level) = ldtk_projects
+            .single()
+            .ok()
+            .and_then(|handle| ldtk_project_assets.get(handle))
+            .and_then(|project| {
+                project
+                    .as_standalone()
+                    .get_loaded_level_by_iid(&iid.to_string())
+            })
+        else {
+            continue;
+        };
+        darkness.level = level
+            .raw()
+            .get_float_field("darkness")
+            .copied()
+            .unwrap_or(0.0)
+            .clamp(0.0, 1.0);
+    }
+}
+
+fn fade_darkness(
+    mario: Query<&Transform, With<Char>>,
+    zones: Query<(&LitZone, &GlobalTransform)>,
+    mut darkness: ResMut<Darkness>,
+    time: Res<Time>,
+) {
+    //a zone counts once the player nearest to it is inside, the brightest one wins
+    let target = zones
+        .iter()
+        .filter(|(zone, xf)| {
+            let center = xf.translation().xy();
+            nearest_player(&mario, center).is_some_and(|position| {
+                let offset = (position - center).abs();
+                offset.x <= zone.half_size.x && offset.y <= zone.half_size.y
+            })
+        })
+        .map(|(zone, _)| zone.darkness)
+        .reduce(f32::min)
+        .unwrap_or(darkness.level);
+    let step = FADE_RATE * time.delta_secs();
+    darkness.current += (target - darkness.current).clamp(-step, step);
+}
+
+fn spawn_overlay(
+    mut commands: Commands,
+    camera: Query<Entity, Added<FollowTargets>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    for camera in camera.iter() {
+        let image = Image::new_fill(
+            Extent3d {
+                width: OVERLAY_SIZE.x,
+                height: OVERLAY_SIZE.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 0],
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        commands.entity(camera).with_child((
+            Name::new("Darkness"),
+            DarknessOverlay,
+            Sprite::from_image(images.add(image)),
+            Transform::from_xyz(0.0, 0.0, RenderLayerOrder::Overlay.z()),
+        ));
+    }
+}
+
+fn draw_overlay(
+    camera: Query<(&Transform, &Projection), With<FollowTargets>>,
+    mut overlay: Query<(&mut Sprite, &mut Visibility), With<DarknessOverlay>>,
+    lights: Query<(&LightSource, &GlobalTransform)>,
+    darkness: Res<Darkness>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Some((camera, Projection::Orthographic(projection))) = camera.iter().next() else {
+        return;
+    };
+    for (mut sprite, mut visibility) in overlay.iter_mut() {
+        if darkness.current <= 0.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+        *visibility = Visibility::Inherited;
+        let area = projection.area;
+        sprite.custom_size = Some(area.size());
+        let Some(data) = images
+            .get_mut(&sprite.image)
+            .and_then(|image| image.data.as_mut())
+        else {
+            continue;
+        };
+        let origin = camera.translation.xy() + area.min;
+        let texel = area.size() / OVERLAY_SIZE.as_vec2();
+        let lights: Vec<_> = lights
+            .iter()
+            .map(|(light, xf)| (xf.translation().xy(), light.radius))
+            .collect();
+        for y in 0..OVERLAY_SIZE.y {
+            for x in 0..OVERLAY_SIZE.x {
+                //image rows go top to bottom
+                let world = origin + vec2(x as f32 + 0.5, (OVERLAY_SIZE.y - y) as f32 - 0.5) * texel;
+                let light = lights
+                    .iter()
+                    .map(|&(position, radius)| {
+                        1.0 - (world.distance_squared(position) / (radius * radius)).min(1.0)
+                    })
+                    .fold(0.0, f32::max);
+                let alpha = darkness.current * (1.0 - light);
+                data[((y * OVERLAY_SIZE.x + x) * 4 + 3) as usize] = (alpha * 255.0) as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Level-wide rising (or falling) lava and water.
+//!
+//! A level gets a liquid when its `liquid` string field is `lava` or `water`. The surface starts at
+//! `liquid_height` pixels above the bottom of the level and moves at `liquid_speed` pixels per
+//! second towards `liquid_target` (defaults to the top of the level, or the bottom when the speed is
+//! negative). `LiquidTrigger` entities retarget the liquid when the player walks into them.
+//!
+//! Touching the liquid kills the player, and the camera is kept above the surface.
+
+use crate::camera::{ClampFlags, ClampPosition, FollowTargets};
+use crate::layers::RenderLayerOrder;
+use crate::mario::{Char, PlayerDied};
+use crate::time::PausableSystems;
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy_ecs_ldtk::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+pub enum LiquidKind {
+    #[default]
+    Lava,
+    Water,
+}
+
+impl LiquidKind {
+    fn color(self) -> Color {
+        match self {
+            LiquidKind::Lava => Color::srgba(1.0, 0.35, 0.05, 0.85),
+            LiquidKind::Water => Color::srgba(0.1, 0.35, 1.0, 0.6),
+        }
+    }
+}
+
+/// The hazard plane of a level, its transform sits on the surface.
+#[derive(Component, Clone, Debug, Reflect)]
+pub struct Liquid {
+    pub kind: LiquidKind,
+    /// surface height relative to the bottom of the level
+    pub height: f32,
+    pub target: f32,
+    /// pixels per second, always positive
+    pub speed: f32,
+}
+
+/// Moves every liquid towards `target` at `speed`.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct MoveLiquid {
+    pub target: f32,
+    pub speed: f32,
+}
+
+#[derive(Component, Clone, Debug, Default)]
+pub struct LiquidTrigger {
+    pub target: f32,
+    pub speed: f32,
+    pub half_size: Vec2,
+    used: bool,
+}
+
+impl From<&EntityInstance> for LiquidTrigger {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            target: entity_instance
+                .get_float_field("target")
+                .copied()
+                .unwrap_or(0.0),
+            speed: entity_instance
+                .get_float_field("speed")
+                .copied()
+                .unwrap_or(0.0),
+            half_size: vec2(entity_instance.width as f32, entity_instance.height as f32) / 2.0,
+            used: false,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct LiquidTriggerBundle {
+    #[from_entity_instance]
+    trigger: LiquidTrigger,
+}
+
+/// How far the liquid sprite reaches below its surface.
+const LIQUID_DEPTH: f32 = 4096.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Liquid>()
+        .register_ldtk_entity::<LiquidTriggerBundle>("LiquidTrigger")
+        .add_systems(
+            Update,
+            (
+                spawn_liquid,
+                (fire_liquid_triggers, move_liquid, drown_player)
+                    .chain()
+                    .in_set(PausableSystems),
+                lock_camera_above_liquid,
+            )
+                .chain(),
+        )
+        .add_observer(retarget_liquid);
+}
+
+fn spawn_liquid(
+    mut commands: Commands,
+    mut level_events: MessageReader<LevelEvent>,
+    levels: Query<(Entity, &LevelIid)>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    for event in level_events.read() {
+        let LevelEvent::Spawned(iid) = event else {
+            continue;
+        };
+        let Some((level_entity, _)) = levels.iter().find(|(_, level_iid)| level_iid == &iid) else {
+            continue;
+        };
+        let Some(project) = ldtk_projects
+            .single()
+            .ok()
+            .and_then(|handle| ldtk_project_assets.get(handle))
+        else {
+            continue;
+        };
+        let Some(level) = project
+            .as_standalone()
+            .get_loaded_level_by_iid(&iid.to_string())
+        else {
+            continue;
+        };
+        let level = level.raw();
+        let kind = match level.get_string_field("liquid") {
+            Ok(Some(kind)) if kind == "lava" => LiquidKind::Lava,
+            Ok(Some(kind)) if kind == "water" => LiquidKind::Water,
+            _ => continue,
+        };
+        let height = level.get_float_field("liquid_height").copied().unwrap_or(0.0);
+        let speed = level.get_float_field("liquid_speed").copied().unwrap_or(0.0);
+        let target = level
+            .get_float_field("liquid_target")
+            .copied()
+            .unwrap_or(if speed < 0.0 { 0.0 } else { level.px_hei as f32 });
+        let width = level.px_wid as f32;
+        info!("level {iid} has {kind:?} at {height}, moving to {target} at {speed}px/s");
+
+        commands.entity(level_entity).with_children(|level| {
+            level.spawn((
+                Name::new("Liquid"),
+                Liquid {
+                    kind,
+                    height,
+                    target,
+                    speed: speed.abs(),
+                },
+                Sprite {
+                    color: kind.color(),
+                    custom_size: Some(vec2(width, LIQUID_DEPTH)),
+                    ..default()
+                },
+                Anchor::TOP_CENTER,
+                Transform::from_xyz(width / 2.0, height, RenderLayerOrder::Hazards.z()),
+            ));
+        });
+    }
+}
+
+fn fire_liquid_triggers(
+    mut commands: Commands,
+    players: Query<&Transform, With<Char>>,
+    mut triggers: Query<(&mut LiquidTrigger, &GlobalTransform)>,
+) {
+    for (mut trigger, xf) in triggers.iter_mut() {
+        let inside = players.iter().any(|transform| {
+            let offset = (transform.translation.xy() - xf.translation().xy()).abs();
+            offset.x <= trigger.half_size.x && offset.y <= trigger.half_size.y
+        });
+        if trigger.used || !inside {
+            continue;
+        }
+        trigger.used = true;
+        commands.trigger(MoveLiquid {
+            target: trigger.target,
+            speed: trigger.speed,
+        });
+    }
+}
+
+fn retarget_liquid(event: On<MoveLiquid>, mut liquids: Query<&mut Liquid>) {
+    for mut liquid in liquids.iter_mut() {
+        liquid.target = event.target;
+        liquid.speed = event.speed.abs();
+    }
+}
+
+fn move_liquid(mut liquids: Query<(&mut Liquid, &mut Transform)>, time: Res<Time>) {
+    for (mut liquid, mut transform) in liquids.iter_mut() {
+        let step = liquid.speed * time.delta_secs();
+        let offset = liquid.target - liquid.height;
+        liquid.height += offset.clamp(-step, step);
+        transform.translation.y = liquid.height;
+    }
+}
+
+fn drown_player(
+    mut commands: Commands,
+    players: Query<&Transform, With<Char>>,
+    liquids: Query<(Entity, &GlobalTransform), With<Liquid>>,
+) {
+    for (entity, xf) in liquids.iter() {
+        let drowned = players
+            .iter()
+            .map(|transform| transform.translation.xy())
+            .find(|position| position.y < xf.translation().y);
+        if let Some(position) = drowned {
+            //the level respawn brings it back, this stops us from dying every frame until then
+            commands.entity(entity).despawn();
+            commands.trigger(PlayerDied { position });
+        }
+    }
+}
+
+fn lock_camera_above_liquid(
+    liquids: Query<&GlobalTransform, With<Liquid>>,
+    mut camera: Query<(&mut ClampPosition, &mut ClampFlags), With<FollowTargets>>,
+    mut locked: Local<bool>,
+) {
+    let surface = liquids
+        .iter()
+        .map(|xf| xf.translation().y)
+        .reduce(f32::max);
+    for (mut clamp, mut flags) in camera.iter_mut() {
+        match surface {
+            Some(surface) => {
+                clamp.min.y = surface;
+                flags.0 |= ClampFlags::MIN_Y;
+            }
+            None if *locked => flags.0 &= !ClampFlags::MIN_Y,
+            None => {}
+        }
+    }
+    *locked = surface.is_some();
+}
@@ -0,0 +1,431 @@
+//! Shared movement plumbing for anything that moves with a [`KinematicController`].
+//!
+//! Whatever decides where an entity wants to go (player input, AI, replay playback) only writes
+//! into its [`LocomotionDriver`]. The systems here turn that into velocity the same way for everyone.
+
+use crate::jump::{JumpStats, JumpTakeoff};
+use crate::physics::{
+    CharacterSystems, Grounded, KinematicController, UpDirection, GROUND_NORMAL_Y,
+};
+use crate::time::TimeSince;
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// What an entity wants to do this tick.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct LocomotionDriver {
+    /// Desired movement direction, only `x` is used by anything with gravity.
+    pub wish_dir: Vec2,
+    pub running: bool,
+    /// Whether jump is being held, used for variable jump height.
+    pub jump_held: bool,
+    /// Set until the next fixed tick consumes it, so short presses between ticks aren't lost.
+    pub jump_requested: bool,
+}
+
+impl LocomotionDriver {
+    pub fn set_wish_dir(&mut self, dir: Vec2) {
+        self.wish_dir = dir.clamp_length_max(1.0);
+    }
+
+    pub fn set_running(&mut self, running: bool) {
+        self.running = running;
+    }
+
+    pub fn hold_jump(&mut self, held: bool) {
+        self.jump_held = held;
+    }
+
+    pub fn request_jump(&mut self) {
+        self.jump_requested = true;
+    }
+}
+
+/// Scales how quickly grounded entities can change speed, below 1 is slippery.
+#[derive(Component, Reflect, Clone, Copy, Debug)]
+#[reflect(Component)]
+pub struct Traction(pub f32);
+
+impl Default for Traction {
+    fn default() -> Self {
+        Traction(1.0)
+    }
+}
+
+/// How the ground feels to walk on, read off whatever a grounded entity stands on. Walls get
+/// theirs by int grid value from [`SurfaceMaterials`](crate::walls::SurfaceMaterials).
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Deserialize)]
+#[reflect(Component)]
+#[serde(default)]
+pub struct SurfaceMaterial {
+    /// how quickly it slows down with no direction held, below 1 slides like ice
+    pub friction: f32,
+    /// how quickly it speeds up or turns around, below 1 drags like mud
+    pub acceleration_scale: f32,
+}
+
+impl Default for SurfaceMaterial {
+    fn default() -> Self {
+        Self {
+            friction: 1.0,
+            acceleration_scale: 1.0,
+        }
+    }
+}
+
+/// A ladder or vine centered on the entity. Holding up or down with the center inside grabs on,
+/// see [`ClimbStats`].
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct Climbable {
+    pub half_size: Vec2,
+}
+
+/// Holding on to a [`Climbable`], gravity is off and the move axis moves us instead. Jumping or
+/// leaving the zone lets go.
+#[derive(Component, Reflect, Clone, Copy, Debug, Default)]
+#[reflect(Component)]
+pub struct Climbing;
+
+#[derive(Component, Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct MoveStats {
+    pub move_speed: f32,
+    pub run_speed: f32,
+    /// speeding up or turning around on the ground while a direction is held
+    pub ground_acceleration: f32,
+    /// slowing down on the ground with no direction held
+    pub ground_deceleration: f32,
+    /// speeding up in the air while a direction is held
+    pub air_acceleration: f32,
+    /// slowing down in the air with no direction held
+    pub air_deceleration: f32,
+    /// air control can't push past this, momentum carried from the ground is kept
+    pub air_max_speed: f32,
+    /// standing past the edge of a platform, the whole collider counts if `None`
+    pub edge_balance: Option<EdgeBalance>,
+    /// ducking into a shorter collider, off if `None`
+    pub crouch: Option<CrouchStats>,
+    /// holding down or up while standing still pans the camera that way, off if `None`
+    pub look: Option<LookStats>,
+    /// climbing [`Climbable`](crate::locomotion::Climbable) zones, off if `None`
+    pub climb: Option<ClimbStats>,
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ClimbStats {
+    /// up and down along the move axis
+    pub speed: f32,
+    /// sideways while holding on
+    pub side_speed: f32,
+}
+
+impl Default for ClimbStats {
+    fn default() -> Self {
+        ClimbStats {
+            speed: 60.0,
+            side_speed: 40.0,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CrouchStats {
+    /// crouched collider height relative to standing
+    pub height_scale: f32,
+    /// move speed relative to standing
+    pub speed_scale: f32,
+}
+
+impl Default for CrouchStats {
+    fn default() -> Self {
+        CrouchStats {
+            height_scale: 0.5,
+            speed_scale: 0.4,
+        }
+    }
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct LookStats {
+    /// seconds of holding before the camera starts to move
+    pub delay: f32,
+    /// how far the camera pans down
+    pub down: f32,
+    /// how far the camera pans up
+    pub up: f32,
+    /// how quickly the camera gets there and back, per second
+    pub speed: f32,
+}
+
+impl Default for LookStats {
+    fn default() -> Self {
+        LookStats {
+            delay: 0.6,
+            down: 64.0,
+            up: 48.0,
+            speed: 4.0,
+        }
+    }
+}
+
+/// Crouched right now, keeps the collider to stand back up with.
+#[derive(Component, Clone, Debug)]
+pub struct Crouching {
+    pub standing: Collider,
+    pub speed_scale: f32,
+}
+
+#[derive(Reflect, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct EdgeBalance {
+    /// how far the center can hang past an edge before we slip off
+    pub max_overhang: f32,
+    pub slip_speed: f32,
+}
+
+impl Default for EdgeBalance {
+    fn default() -> Self {
+        EdgeBalance {
+            max_overhang: 4.0,
+            slip_speed: 30.0,
+        }
+    }
+}
+impl Default for MoveStats {
+    fn default() -> Self {
+        MoveStats {
+            move_speed: 75.0,
+            run_speed: 135.0,
+            ground_acceleration: 350.0,
+            ground_deceleration: 650.0,
+            air_acceleration: 350.0,
+            air_deceleration: 0.0,
+            air_max_speed: 135.0,
+            edge_balance: None,
+            crouch: None,
+            look: None,
+            climb: None,
+        }
+    }
+}
+
+/// How far up or down the move axis has to be held to grab on to a [`Climbable`].
+const CLIMB_THRESHOLD: f32 = 0.5;
+/// How far down the move axis has to be held to count as crouching.
+pub const CROUCH_THRESHOLD: f32 = -0.5;
+/// How far below the collider the ground probes reach.
+const GROUND_PROBE: f32 = 2.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<LocomotionDriver>()
+        .register_type::<Traction>()
+        .register_type::<SurfaceMaterial>()
+        .register_type::<Climbable>()
+        .register_type::<Climbing>()
+        .add_systems(
+            FixedUpdate,
+            (climb, accelerate, slip_off_edges)
+                .chain()
+                .after(CharacterSystems::Sense)
+                .before(CharacterSystems::Forces),
+        );
+}
+
+fn move_towards(from: f32, to: f32, max_delta: f32) -> f32 {
+    from + (to - from).clamp(-max_delta, max_delta)
+}
+
+fn climb(
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &Transform,
+        &mut KinematicController,
+        &LocomotionDriver,
+        &MoveStats,
+        Option<&mut TimeSince<Grounded>>,
+        Has<Grounded>,
+        Has<Climbing>,
+    )>,
+    zones: Query<(&Climbable, &GlobalTransform)>,
+) {
+    for (entity, transform, mut controller, driver, stats, time_since, grounded, climbing) in
+        query.iter_mut()
+    {
+        let Some(climb) = &stats.climb else {
+            continue;
+        };
+        let position = transform.translation.xy();
+        let inside = zones.iter().any(|(zone, xf)| {
+            let offset = (position - xf.translation().xy()).abs();
+            offset.x <= zone.half_size.x && offset.y <= zone.half_size.y
+        });
+        if climbing && (!inside || driver.jump_requested) {
+            commands.entity(entity).remove::<Climbing>();
+            //jumping off jumps like off the ground
+            if let Some(mut time_since) = time_since.filter(|_| driver.jump_requested) {
+                time_since.time = 0.0;
+            }
+            continue;
+        }
+        //down on the ground is crouching, not climbing
+        let grab = driver.wish_dir.y > CLIMB_THRESHOLD
+            || (driver.wish_dir.y < -CLIMB_THRESHOLD && !grounded);
+        if !climbing && !(inside && grab && !driver.jump_requested) {
+            continue;
+        }
+        if !climbing {
+            commands.entity(entity).insert(Climbing);
+        }
+        controller.velocity = driver.wish_dir * vec2(climb.side_speed, climb.speed);
+    }
+}
+
+fn accelerate(
+    mut query: Query<
+        (
+            &mut KinematicController,
+            &LocomotionDriver,
+            &MoveStats,
+            Option<&Grounded>,
+            Option<&Traction>,
+            Option<&JumpTakeoff>,
+            Option<&Crouching>,
+            Has<JumpStats>,
+        ),
+        Without<Climbing>,
+    >,
+    materials: Query<&SurfaceMaterial>,
+    time: Res<Time>,
+) {
+    for (mut controller, driver, stats, grounded, traction, takeoff, crouching, falls) in
+        query.iter_mut()
+    {
+        let speed = if driver.running {
+            stats.run_speed
+        } else {
+            stats.move_speed
+        } * crouching.map_or(1.0, |crouching| crouching.speed_scale);
+        //nothing pulls it down, so it steers in every direction
+        if !falls {
+            let accel = if driver.wish_dir == Vec2::ZERO {
+                stats.ground_deceleration
+            } else {
+                stats.ground_acceleration
+            };
+            controller.velocity = controller
+                .velocity
+                .move_towards(driver.wish_dir * speed, time.delta_secs() * accel);
+            continue;
+        }
+        let Some(grounded) = grounded else {
+            let takeoff_speed = takeoff.map_or(0.0, |takeoff| takeoff.velocity.x.abs());
+            let max_speed = stats.air_max_speed.max(takeoff_speed);
+            let speed = speed.min(max_speed);
+            air_control(&mut controller, driver, stats, speed, time.delta_secs());
+            continue;
+        };
+        let material = materials.get(grounded.entity).copied().unwrap_or_default();
+        let mut accel = stats.ground_deceleration * material.friction;
+        if driver.wish_dir.x != 0.0 {
+            accel = stats.ground_acceleration * material.acceleration_scale;
+        }
+        accel *= traction.map_or(1.0, |traction| traction.0);
+        controller.velocity.x = move_towards(
+            controller.velocity.x,
+            driver.wish_dir.x * speed,
+            time.delta_secs() * accel,
+        );
+    }
+}
+
+//`speed` is already capped to what air control allows
+fn air_control(
+    controller: &mut KinematicController,
+    driver: &LocomotionDriver,
+    stats: &MoveStats,
+    speed: f32,
+    delta: f32,
+) {
+    let velocity = controller.velocity.x;
+    if driver.wish_dir.x == 0.0 {
+        controller.velocity.x = move_towards(velocity, 0.0, stats.air_deceleration * delta);
+        return;
+    }
+    let target = driver.wish_dir.x * speed;
+    //already going faster that way, don't eat into it
+    if velocity * target > 0.0 && velocity.abs() > target.abs() {
+        return;
+    }
+    controller.velocity.x = move_towards(velocity, target, stats.air_acceleration * delta);
+}
+
+/// Half the size of `collider`, across `up` in `x` and along it in `y`.
+pub(crate) fn half_size(collider: &Collider, up: Dir2) -> Vec2 {
+    let half_size = collider.aabb(Vec2::ZERO, 0.0).size() / 2.0;
+    vec2(half_size.dot(up.perp().abs()), half_size.dot(up.abs()))
+}
+
+/// Whether there's ground `offset` to the right of `position`, right under the collider.
+pub(crate) fn ground_at(
+    spatial_query: &SpatialQuery,
+    filter: &SpatialQueryFilter,
+    position: Vec2,
+    up: Dir2,
+    offset: f32,
+    reach: f32,
+) -> bool {
+    let right = -up.perp();
+    spatial_query
+        .cast_ray(position + right * offset, -up, reach, true, filter)
+        .is_some_and(|hit| hit.distance > 0.0 && hit.normal.dot(*up) >= GROUND_NORMAL_Y)
+}
+
+pub(crate) fn slip_off_edges(
+    mut query: Query<
+        (
+            Entity,
+            &Transform,
+            &Collider,
+            &MoveStats,
+            &mut KinematicController,
+            Option<&UpDirection>,
+        ),
+        With<Grounded>,
+    >,
+    spatial_query: SpatialQuery,
+) {
+    for (entity, transform, collider, stats, mut controller, up) in query.iter_mut() {
+        let Some(balance) = &stats.edge_balance else {
+            continue;
+        };
+        let up = up.copied().unwrap_or_default().0;
+        let right = -up.perp();
+        let position = transform.translation.xy();
+        let half_size = half_size(collider, up);
+        let reach = half_size.y + GROUND_PROBE;
+        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+        let ground = |offset| ground_at(&spatial_query, &filter, position, up, offset, reach);
+        if ground(-balance.max_overhang) || ground(balance.max_overhang) {
+            continue;
+        }
+        //hanging too far over, slide away from the side that's still on the ground
+        let side = if ground(-half_size.x) {
+            1.0
+        } else if ground(half_size.x) {
+            -1.0
+        } else {
+            continue;
+        };
+        let speed = controller.velocity.dot(right);
+        if speed * side < balance.slip_speed {
+            controller.velocity += right * (side * balance.slip_speed - speed);
+        }
+    }
+}
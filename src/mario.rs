@@ -1,20 +1,44 @@
-use crate::camera::{CameraReset, ClampFlags, ClampPosition, FollowAxes, FollowWeight, FollowerOf};
-use crate::input::{Crouch, InputSettings, Jump, Move, Run};
+use crate::audio::PlaySound;
+use crate::camera::{
+    CameraOffsets, CameraReset, ClampPosition, FollowAxes, FollowerOf, OffsetChannel,
+};
+use crate::combat::{HitLanded, Hurtbox, Team};
+use crate::definitions::{load_definition, reject_invalid_definition, Definition, Migrate};
+use crate::effects::{Effect, EffectRequest};
+use crate::input::{Crouch, InputProfile, InputSettings, Jump, Move, Run};
+use crate::jump::{JumpCharge, JumpStats};
+use crate::layers::{RenderLayer, RenderLayerOrder};
+use crate::locomotion::{half_size, Crouching, LocomotionDriver, MoveStats, CROUCH_THRESHOLD};
 use crate::physics::{
-    ColliderShape, Grounded, IgnoreGrounded, KinematicController, SlideController,
+    perform_move_and_slide, CeilingHit, ColliderShape, Crushed, Grounded, IgnoreGrounded,
+    KinematicController, MoveAndSlideSettings, SlideController, UpDirection, Walled,
 };
+use crate::player_state::Health;
+use crate::quality::Quality;
+use crate::screens::Screen;
+use crate::spawn_point::ReturnToStart;
+use crate::split_screen::PlayerSlot;
+use crate::stomp::is_stomp;
 use crate::time::{update_time_since, PausableSystems, TimeSince};
 use avian2d::prelude::*;
 use bevy::asset::io::Writer;
 use bevy::prelude::*;
 use bevy_ecs_ldtk::prelude::*;
 use bevy_enhanced_input::prelude::*;
+use char_controller::prelude::{ContactCache, MoveAndSlide};
 use ron::ser::PrettyConfig;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs::{read_to_string, File, OpenOptions};
 use std::io::Write;
 use std::time::Duration;
 
+/// The player was killed by a hazard, the level restarts.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PlayerDied {
+    pub position: Vec2,
+}
+
 #[derive(Component, Reflect)]
 pub struct Ghost {
     time: f32,
@@ -42,16 +66,36 @@ impl Default for Char {
     }
 }
 
+/// Position of the player closest to `position`, with co-op there's one per player slot.
+pub fn nearest_player<'a>(
+    players: impl IntoIterator<Item = &'a Transform>,
+    position: Vec2,
+) -> Option<Vec2> {
+    players
+        .into_iter()
+        .map(|transform| transform.translation.xy())
+        .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+}
+
 #[derive(Component, Reflect, Deserialize, Clone, Debug)]
-pub struct MoveStats {
-    pub move_speed: f32,
-    pub run_speed: f32,
+#[serde(default)]
+pub struct StompStats {
+    /// upwards velocity after stomping an enemy
+    pub bounce_velocity: f32,
+    /// used instead while jump is held
+    pub held_bounce_velocity: f32,
+    /// points for the first stomp of a combo, doubled for every stomp after it
+    pub base_score: u64,
+    pub max_score: u64,
 }
-impl Default for MoveStats {
+
+impl Default for StompStats {
     fn default() -> Self {
-        MoveStats {
-            move_speed: 75.0,
-            run_speed: 135.0,
+        StompStats {
+            bounce_velocity: 180.0,
+            held_bounce_velocity: 320.0,
+            base_score: 100,
+            max_score: 8000,
         }
     }
 }
@@ -72,49 +116,58 @@ pub struct PlayerBundle {
 }
 
 #[derive(Bundle, Default, Deserialize)]
+#[serde(default)]
 pub struct CharBundle {
-    #[serde(default)]
     pub char: Char,
     pub move_stats: MoveStats,
-    #[serde(default)]
+    pub jump_stats: JumpStats,
+    pub stomp_stats: StompStats,
     pub time_since: TimeSince<Grounded>,
+    #[serde(skip)]
+    pub time_since_walled: TimeSince<Walled>,
     pub ghost_config: GhostConfig,
     #[serde(skip)]
     pub slide: SlideController,
+    #[serde(skip)]
+    pub driver: LocomotionDriver,
+    #[serde(skip)]
+    pub definition: Definition<CharBundle>,
+}
+impl Migrate for CharBundle {
+    //1: added `version`, every field became optional
+    const VERSION: u32 = 1;
+}
+impl CharBundle {
+    /// Loads `entities/<identifier>/entity.ron`.
+    pub fn load(identifier: &str) -> Self {
+        let (bundle, definition) = load_definition::<Self, Self>(identifier, "entity.ron");
+        Self {
+            definition,
+            ..bundle
+        }
+    }
 }
 impl From<&EntityInstance> for CharBundle {
     fn from(entity_instance: &EntityInstance) -> Self {
-        let path = format!(
-            "assets/entities/{}/entity.ron",
-            entity_instance.identifier.to_lowercase()
-        );
-        info!("Looking at path: {path}");
-        let Some(str) = read_to_string(path).ok() else {
-            warn!("did not find an entity file for the identifier");
-            return Self::default();
-        };
-        //str -> Result<ColliderBuilder> -> ColliderBuilder -> ColliderBundle
-        ron::de::from_str::<_>(&str)
-            .map_err(|e| {
-                warn!("could not parse {e}");
-                e
-            })
-            .unwrap_or_default()
+        Self::load(&entity_instance.identifier)
     }
 }
 //extra step to convert
 #[derive(Clone, Default, Deserialize)]
+#[serde(default)]
 pub struct ColliderBuilder {
     pub collider: ColliderShape,
     pub rb: RigidBody,
     pub shape_caster: ShapeCasterBuilder,
-    #[serde(default)]
     pub rotation_constraints: LockedAxes,
     //get_gravity * scale
-    #[serde(default)]
     pub gravity_scale: GravityScale,
-    #[serde(default)]
     pub friction: Friction,
+    pub move_and_slide: MoveAndSlideSettings,
+}
+impl Migrate for ColliderBuilder {
+    //1: added `version`, every field became optional
+    const VERSION: u32 = 1;
 }
 #[derive(Bundle, Clone, Default, LdtkIntCell)]
 pub struct ColliderBundle {
@@ -125,6 +178,8 @@ pub struct ColliderBundle {
     pub gravity_scale: GravityScale,
     pub friction: Friction,
     pub grounded: Grounded,
+    pub move_and_slide: MoveAndSlideSettings,
+    pub definition: Definition<ColliderBundle>,
 }
 #[derive(Deserialize, Clone)]
 pub struct ShapeCasterBuilder {
@@ -156,6 +211,7 @@ impl From<ColliderBuilder> for ColliderBundle {
             rotation_constraints,
             gravity_scale,
             friction,
+            move_and_slide,
         }: ColliderBuilder,
     ) -> Self {
         let collider = collider.into();
@@ -167,94 +223,163 @@ impl From<ColliderBuilder> for ColliderBundle {
             rotation_constraints,
             gravity_scale,
             friction,
+            move_and_slide,
             ..default()
         }
     }
 }
+impl ColliderBundle {
+    /// Loads `entities/<identifier>/collider.ron`.
+    pub fn load(identifier: &str) -> Self {
+        //str -> Result<ColliderBuilder> -> ColliderBuilder -> ColliderBundle
+        let (builder, definition) =
+            load_definition::<ColliderBuilder, Self>(identifier, "collider.ron");
+        Self {
+            definition,
+            ..builder.into()
+        }
+    }
+}
 impl From<&EntityInstance> for ColliderBundle {
     fn from(entity_instance: &EntityInstance) -> Self {
-        let path = format!(
-            "assets/entities/{}/collider.ron",
-            entity_instance.identifier.to_lowercase()
-        );
-        info!("Looking at path: {path}");
-        let Some(str) = read_to_string(path).ok() else {
-            warn!("did not find an entity file for the identifier");
-            return Self::default();
-        };
-        //str -> Result<ColliderBuilder> -> ColliderBuilder -> ColliderBundle
-        ron::de::from_str::<ColliderBuilder>(&str)
-            .map_err(|e| {
-                warn!("could not parse {e}");
-                e
-            })
-            .unwrap_or_default()
-            .into()
+        Self::load(&entity_instance.identifier)
     }
 }
+pub const PLAYER_IMAGE: &str = "images/mario.png";
+const BONK_SOUND: &str = "bonk";
+/// Surviving a hit flashes red.
+const HURT_EFFECT: Effect = Effect::Flash {
+    color: Color::srgb(1.0, 0.2, 0.2),
+    duration: 0.3,
+};
+
+/// The player prefab, registered as `Player` so [`SpawnPoint`](crate::spawn_point::SpawnPoint)s
+/// don't need a full player entity in every level.
+pub fn spawn_player(commands: &mut Commands, position: Vec3) -> Entity {
+    let entity = commands
+        .spawn((
+            Name::new("Player"),
+            CharBundle::load("Char"),
+            ColliderBundle::load("Char"),
+            KinematicController::default(),
+            Transform::from_translation(position),
+        ))
+        .id();
+    commands.entity(entity).queue(|mut entity: EntityWorldMut| {
+        let image = entity.world().resource::<AssetServer>().load(PLAYER_IMAGE);
+        entity.insert(Sprite {
+            image,
+            rect: Some(Rect::new(0.0, 0.0, 16.0, 16.0)),
+            ..default()
+        });
+    });
+    entity
+}
+
+/// The end of a level.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+pub struct Goal;
+
 #[derive(Default, Bundle, LdtkEntity)]
 pub struct GoalBundle {
     #[sprite_sheet]
     sprite_sheet: Sprite,
+    goal: Goal,
 }
 
 pub(crate) fn plugin(app: &mut App) {
     app.add_plugins(LdtkPlugin)
-        .add_plugins((super::walls::WallPlugin, crate::camera::plugin))
-        .insert_resource(LevelSelection::index(0))
+        .add_plugins((
+            super::walls::WallPlugin,
+            crate::camera::plugin,
+            crate::warps::plugin,
+            crate::switch_blocks::plugin,
+            crate::liquid::plugin,
+            crate::cannons::plugin,
+            crate::conveyors::plugin,
+            crate::lighting::plugin,
+            crate::weather::plugin,
+            crate::doors::plugin,
+            crate::registry::plugin,
+            crate::enemies::plugin,
+            crate::layers::plugin,
+            crate::spawn_point::plugin,
+            crate::player_state::plugin,
+        ))
         .register_ldtk_entity::<PlayerBundle>("Char")
         .register_ldtk_entity::<GoalBundle>("Goal")
-        .add_systems(Startup, setup)
         .add_systems(
             Update,
-            (
-                move_mario,
-                update_mario_gravity,
-                spawn_ghosts,
-                manage_ghosts,
-            )
+            (drive_mario, spawn_ghosts, manage_ghosts, show_jump_charge, look_around)
                 .chain()
                 .in_set(PausableSystems),
         )
+        .add_systems(
+            Update,
+            rebind_mario.run_if(resource_changed::<InputSettings>),
+        )
+        .add_systems(FixedUpdate, crouch_mario.before(perform_move_and_slide))
         //.add_observer(friction)
+        .add_observer(reject_invalid_definition::<CharBundle>)
+        .add_observer(reject_invalid_definition::<ColliderBundle>)
         .add_observer(handle_mario_startup)
+        .add_observer(request_mario_jump)
         .add_observer(reset_camera_limits)
-        .add_observer(respawn_level);
+        .add_observer(respawn_level)
+        .add_observer(handle_player_death)
+        .add_observer(bonk_mario)
+        .add_observer(hurt_mario)
+        .add_observer(crush_mario);
 }
 
 fn reset_camera_limits(
     _trigger: On<CameraReset>,
-    mario: Single<&Transform, With<Char>>,
-    mut clamp_pos: Single<&mut ClampPosition>,
+    mario: Query<(&Transform, &FollowerOf), With<Char>>,
+    mut clamps: Query<&mut ClampPosition>,
 ) {
     info!("Resetting camera limits");
-    clamp_pos.min = mario.translation.xy();
+    for (transform, &FollowerOf(camera)) in mario.iter() {
+        if let Ok(mut clamp_pos) = clamps.get_mut(camera) {
+            clamp_pos.min = transform.translation.xy();
+        }
+    }
 }
 fn spawn_ghosts(
-    mario_query: Single<
-        (&Transform, &Sprite, &GhostConfig, &KinematicController),
+    mario_query: Query<
+        (&Transform, &GhostConfig, &KinematicController),
         (With<Char>, Without<Grounded>),
     >,
     mut commands: Commands,
     time: Res<Time>,
+    quality: Res<Quality>,
     mut timer: Local<f32>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let (xf, sprite, &GhostConfig(val), KinematicController { velocity: vel }) =
-        mario_query.into_inner();
-    let (xf, _sprite) = (xf.clone(), sprite.clone());
-    if *timer > val && vel.length() > 100.0 {
-        let shape = meshes.add(Annulus::new(30.0, 33.0));
-        let color = Color::WHITE;
-        let time = rand::random_range(0.5..3.0);
-        commands.spawn((
-            Mesh2d(shape),
-            MeshMaterial2d(materials.add(color)),
-            xf,
-            Ghost { time, start: time },
-            Name::new("Ghost"),
-        ));
+    if mario_query.is_empty() {
+        return;
+    }
+    let mut spawned = false;
+    for (xf, &GhostConfig(val), KinematicController { velocity: vel }) in mario_query.iter() {
+        //fewer ghosts on slow machines
+        let interval = val / quality.scale();
+        if *timer > interval && vel.length() > 100.0 {
+            let shape = meshes.add(Annulus::new(30.0, 33.0));
+            let color = Color::WHITE;
+            let time = rand::random_range(0.5..3.0);
+            commands.spawn((
+                Mesh2d(shape),
+                MeshMaterial2d(materials.add(color)),
+                *xf,
+                RenderLayer(RenderLayerOrder::Ghosts),
+                Ghost { time, start: time },
+                Name::new("Ghost"),
+                DespawnOnExit(Screen::Game),
+            ));
+            spawned = true;
+        }
+    }
+    if spawned {
         *timer = 0.0;
     }
     *timer += time.delta_secs();
@@ -292,115 +417,347 @@ fn manage_ghosts(
     }
 }
 
-fn update_mario_gravity(
-    mut query: Query<(&mut GravityScale, &KinematicController), (With<Char>, Without<Grounded>)>,
-    jump_query: Query<&mut ActionState, With<Action<Jump>>>,
-) {
-    let jump_pressed = jump_query.iter().any(|&jump| jump == ActionState::Fired);
-    for (mut scale, controller) in query.iter_mut() {
-        if !jump_pressed && controller.velocity.y > 0.0 {
-            scale.0 = 2.0;
-        } else {
-            scale.0 = 1.0;
-        }
-    }
-}
-
 fn respawn_level(
     _trigger: On<Start<crate::input::Respawn>>,
     mut commands: Commands,
-    level: Single<Entity, (With<LevelIid>, Without<Char>)>,
+    levels: Query<Entity, (With<LevelIid>, Without<Char>)>,
 ) {
-    commands.entity(level.into_inner()).insert(Respawn);
+    commands.trigger(ReturnToStart);
+    for level in levels.iter() {
+        commands.entity(level).insert(Respawn);
+    }
     info!("respawning level");
     commands.trigger(CameraReset);
 }
-fn move_mario(
-    mario: Single<(&mut KinematicController, &MoveStats, Option<&Grounded>), With<Char>>,
-    inputs: Single<&ActionValue, With<Action<Move>>>,
-    run: Single<&ActionState, With<Action<Run>>>,
-    time: Res<Time>,
+
+fn hurt_mario(
+    hit: On<HitLanded>,
+    mut commands: Commands,
+    mut mario: Query<(&Transform, &KinematicController, Option<&mut Health>), With<Char>>,
+    attackers: Query<&GlobalTransform>,
 ) {
-    let (mut vel, stats, grounded) = mario.into_inner();
-    let &ActionValue::Axis2D(axis) = inputs.into_inner() else {
+    let Ok((transform, controller, health)) = mario.get_mut(hit.target) else {
         return;
     };
-    let speed = if *run.into_inner() == ActionState::Fired {
-        stats.run_speed
-    } else {
-        stats.move_speed
-    };
-    let mut accel = 650.0;
-    if grounded.is_none() {
-        accel = 0.0;
+    let position = transform.translation.xy();
+    //landing on top of an enemy is a stomp, not a hit
+    if attackers.get(hit.attacker).is_ok_and(|attacker| {
+        is_stomp(position, controller.velocity, attacker.translation().xy())
+    }) {
+        return;
     }
-    if axis.length() != 0.0 {
-        accel = 350.0;
+    if let Some(mut health) = health {
+        health.current = health.current.saturating_sub(hit.damage);
+        if health.current > 0 {
+            commands.trigger(EffectRequest::new(hit.target, HURT_EFFECT));
+            return;
+        }
     }
-    vel.velocity = vel.velocity.move_towards(
-        axis * speed,
-        time.delta_secs() * accel,
-    );
-}
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.spawn(LdtkWorldBundle {
-        ldtk_handle: asset_server.load("ldtk/mayrio.ldtk").into(),
-        ..Default::default()
-    });
+    commands.trigger(PlayerDied { position });
 }
 
-fn handle_mario_startup(
-    e: On<Add, Char>,
+fn crush_mario(crush: On<Crushed>, mut commands: Commands, mario: Query<(), With<Char>>) {
+    if mario.contains(crush.entity) {
+        commands.trigger(PlayerDied {
+            position: crush.position,
+        });
+    }
+}
+
+fn bonk_mario(hit: On<CeilingHit>, mut commands: Commands, mario: Query<(), With<Char>>) {
+    if mario.contains(hit.controller) {
+        commands.trigger(PlaySound::new(BONK_SOUND));
+    }
+}
+
+fn handle_player_death(
+    death: On<PlayerDied>,
     mut commands: Commands,
-    input_settings: Res<InputSettings>,
+    levels: Query<Entity, (With<LevelIid>, Without<Char>)>,
 ) {
-    commands.entity(e.entity).insert(
-        actions!(
-        Char[
+    info!("player died at {}", death.position);
+    //players are worldly, so respawning the level leaves them where they died
+    commands.trigger(ReturnToStart);
+    for level in levels.iter() {
+        commands.entity(level).insert(Respawn);
+    }
+    commands.trigger(CameraReset);
+}
+/// Whether one player's action of type `A` is firing.
+fn fired<A: InputAction>(
+    actions: &Actions<Char>,
+    states: &Query<&ActionState, With<Action<A>>>,
+) -> bool {
+    actions
+        .iter()
+        .any(|action| states.get(action).is_ok_and(|state| *state == ActionState::Fired))
+}
+
+fn drive_mario(
+    mut mario: Query<(&mut LocomotionDriver, &Actions<Char>), With<Char>>,
+    inputs: Query<&ActionValue, With<Action<Move>>>,
+    run: Query<&ActionState, With<Action<Run>>>,
+    jump: Query<&ActionState, With<Action<Jump>>>,
+) {
+    for (mut driver, actions) in mario.iter_mut() {
+        let Some(&ActionValue::Axis2D(axis)) =
+            actions.iter().find_map(|action| inputs.get(action).ok())
+        else {
+            continue;
+        };
+        driver.set_wish_dir(axis);
+        driver.set_running(fired(actions, &run));
+        driver.hold_jump(fired(actions, &jump));
+    }
+}
+
+/// Yellow copy of the player sprite drawn on top while charging a jump, so the charge doesn't
+/// fight effects over the player's own sprite color.
+#[derive(Component)]
+struct JumpChargeGlow;
+
+//tint towards yellow while charging, flashing once it's full
+fn show_jump_charge(
+    mut commands: Commands,
+    mario: Query<
+        (Entity, &Sprite, &JumpCharge, &JumpStats, Option<&Children>),
+        (With<Char>, Without<JumpChargeGlow>),
+    >,
+    mut glows: Query<(&mut Sprite, &mut Visibility), With<JumpChargeGlow>>,
+    time: Res<Time>,
+) {
+    for (entity, sprite, charge, stats, children) in &mario {
+        let fraction = charge.fraction(stats);
+        let glow = children
+            .and_then(|children| children.iter().find(|child| glows.contains(*child)));
+        let Some((mut glow_sprite, mut visibility)) = glow.and_then(|glow| glows.get_mut(glow).ok())
+        else {
+            if fraction > 0.0 {
+                commands.entity(entity).with_child((
+                    Name::new("Jump charge glow"),
+                    JumpChargeGlow,
+                    Sprite::default(),
+                    Transform::from_xyz(0.0, 0.0, 0.1),
+                    Visibility::Hidden,
+                ));
+            }
+            continue;
+        };
+        let full = fraction >= 1.0 && time.elapsed_secs().fract() < 0.5;
+        *visibility = if fraction > 0.0 && !full {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+        //follow the animation frame and facing of the player
+        *glow_sprite = Sprite {
+            color: Color::srgba(1.0, 0.85, 0.1, 0.6 * fraction),
+            ..sprite.clone()
+        };
+    }
+}
+
+fn crouch_mario(
+    mut commands: Commands,
+    mut mario: Query<
         (
-            Action::<Run>::new(),
-            Bindings::spawn(SpawnIter(input_settings.run.into_iter()))
+            Entity,
+            &MoveStats,
+            &LocomotionDriver,
+            &mut Collider,
+            &mut ShapeCaster,
+            &mut Transform,
+            &mut ContactCache,
+            Option<&Crouching>,
+            Option<&UpDirection>,
+            Has<Grounded>,
+            &Actions<Char>,
         ),
-        (
-            Action::<Move>::new(),
-            DeadZone::default(),
-            Bindings::spawn((
-            Cardinal::wasd_keys(),
-            Axial::left_stick()
-            )),
+        With<Char>,
+    >,
+    crouch: Query<&ActionState, With<Action<Crouch>>>,
+    move_and_slide: MoveAndSlide,
+) {
+    for (
+        entity,
+        stats,
+        driver,
+        mut collider,
+        mut caster,
+        mut transform,
+        mut cache,
+        crouching,
+        up,
+        grounded,
+        actions,
+    ) in mario.iter_mut()
+    {
+        let Some(crouch_stats) = &stats.crouch else {
+            continue;
+        };
+        let up = up.copied().unwrap_or_default().0;
+        let wants = fired(actions, &crouch) || driver.wish_dir.y < CROUCH_THRESHOLD;
+        let height = half_size(&collider, up).y * 2.0;
+        let shape = match crouching {
+            None if wants && grounded => {
+                //only shrink along up
+                let size = collider.aabb(Vec2::ZERO, 0.0).size();
+                let size = size - size * up.abs() * (1.0 - crouch_stats.height_scale);
+                let crouched = Collider::rectangle(size.x, size.y);
+                commands.entity(entity).insert(Crouching {
+                    standing: collider.clone(),
+                    speed_scale: crouch_stats.speed_scale,
+                });
+                crouched
+            }
+            Some(crouching) if !wants => {
+                let standing_height = half_size(&crouching.standing, up).y * 2.0;
+                let position = transform.translation.xy() + *up * (standing_height - height) / 2.0;
+                //a ceiling in the way keeps us down
+                let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+                if !move_and_slide.fits_at(&crouching.standing, position, 0.0, &filter) {
+                    continue;
+                }
+                commands.entity(entity).remove::<Crouching>();
+                crouching.standing.clone()
+            }
+            _ => continue,
+        };
+        //keep the feet where they are
+        let new_height = half_size(&shape, up).y * 2.0;
+        transform.translation += (*up * (new_height - height) / 2.0).extend(0.0);
+        caster.shape = shape.clone();
+        *collider = shape;
+        //contacts were computed for the old shape
+        cache.clear();
+    }
+}
 
-        ),
+/// Pans the camera down while crouching and up while holding up, once we've stood still for
+/// [`LookStats::delay`](crate::locomotion::LookStats::delay), and back as soon as we let go or
+/// move.
+fn look_around(
+    time: Res<Time>,
+    mario: Query<
         (
-            Action::<crate::input::Respawn>::new(),
-            Bindings::spawn(SpawnIter(input_settings.respawn.into_iter()))
+            &MoveStats,
+            &LocomotionDriver,
+            &KinematicController,
+            &FollowerOf,
+            &Actions<Char>,
+            Has<Grounded>,
         ),
+        With<Char>,
+    >,
+    crouch: Query<&ActionState, With<Action<Crouch>>>,
+    mut cameras: Query<&mut CameraOffsets>,
+    mut held: Local<HashMap<Entity, f32>>,
+) {
+    for (stats, driver, controller, &FollowerOf(camera), actions, grounded) in mario.iter() {
+        let (Some(look), Ok(mut offsets)) = (&stats.look, cameras.get_mut(camera)) else {
+            continue;
+        };
+        let crouching = fired(actions, &crouch);
+        let held_for = held.entry(camera).or_default();
+        let still = grounded && driver.wish_dir.x == 0.0 && controller.velocity == Vec2::ZERO;
+        let wanted = if !still {
+            0.0
+        } else if crouching || driver.wish_dir.y < CROUCH_THRESHOLD {
+            -look.down
+        } else if driver.wish_dir.y > -CROUCH_THRESHOLD {
+            look.up
+        } else {
+            0.0
+        };
+        if wanted == 0.0 {
+            *held_for = 0.0;
+        } else {
+            *held_for += time.delta_secs();
+        }
+        let target = if *held_for >= look.delay { wanted } else { 0.0 };
+        let t = 1.0 - (-look.speed * time.delta_secs()).exp();
+        let peek = offsets.get(OffsetChannel::Peek).offset.y.lerp(target, t);
+        offsets.set(OffsetChannel::Peek, Vec2::Y * peek, 1.0);
+    }
+}
 
-    ]
-    ));
+fn request_mario_jump(
+    jump: On<Start<Jump>>,
+    mut drivers: Query<&mut LocomotionDriver, With<Char>>,
+) {
+    if let Ok(mut driver) = drivers.get_mut(jump.context) {
+        driver.request_jump();
+    }
+}
+
+fn char_actions(profile: &InputProfile) -> impl Bundle {
+    let [north, east, south, west] = profile.movement;
+    let [x, y] = profile.stick;
+    actions!(
+        Char[
+            (
+                Action::<Run>::new(),
+                Bindings::spawn(SpawnIter(profile.run.into_iter()))
+            ),
+            (
+                Action::<Jump>::new(),
+                Bindings::spawn(SpawnIter(profile.jump.into_iter()))
+            ),
+            (
+                Action::<Move>::new(),
+                DeadZone::default(),
+                Bindings::spawn((
+                    Cardinal {
+                        north,
+                        east,
+                        south,
+                        west
+                    },
+                    Axial { x, y }
+                )),
+            ),
+            (
+                Action::<Crouch>::new(),
+                Bindings::spawn(SpawnIter(std::iter::once(south)))
+            ),
+            (
+                Action::<crate::input::Respawn>::new(),
+                Bindings::spawn(SpawnIter(profile.respawn.into_iter()))
+            ),
+        ]
+    )
+}
+
+//settings only change through profile switches and imports, so rebuilding everything is fine
+fn rebind_mario(
+    mut commands: Commands,
+    mario: Query<(Entity, Option<&PlayerSlot>), With<Char>>,
+    input_settings: Res<InputSettings>,
+) {
+    for (entity, slot) in mario.iter() {
+        let profile = PlayerSlot::profile(slot, &input_settings);
+        commands
+            .entity(entity)
+            .despawn_related::<Actions<Char>>()
+            .insert(char_actions(&profile));
+    }
+}
+
+fn handle_mario_startup(
+    e: On<Add, Char>,
+    mut commands: Commands,
+    input_settings: Res<InputSettings>,
+    slots: Query<&PlayerSlot>,
+) {
+    let profile = PlayerSlot::profile(slots.get(e.entity).ok(), &input_settings);
+    commands
+        .entity(e.entity)
+        .insert((
+            char_actions(&profile),
+            Hurtbox::new(Team::Player, Vec2::splat(8.0)),
+            RenderLayer(RenderLayerOrder::Player),
+        ));
     commands
         .entity(e.entity)
         .insert(FollowAxes::new(FollowAxes::HORIZONTAL | FollowAxes::VERTICAL));
-    let cam = commands.spawn((
-        Camera2d,
-        Projection::Orthographic(OrthographicProjection {
-            scale: 0.35,
-            scaling_mode: bevy::camera::ScalingMode::FixedVertical {
-                viewport_height: 720.0,
-            },
-            ..OrthographicProjection::default_2d()
-        }),
-        //TODO spawn at char location instead?
-        Transform::from_xyz(1280.0 / 4.0, 238.0, 0.0),
-        //per level camera
-        ClampFlags(0),
-        //TODO this really should use Option<T> for clamping
-        ClampPosition {
-            min: vec2(f32::NEG_INFINITY, f32::NEG_INFINITY),
-            max: vec2(10000000.0, 10000000.0),
-        },
-        TransformInterpolation,
-    )).id();
-
-    commands.entity(e.entity).insert((FollowerOf(cam), FollowWeight(1)));
-    info!("camera spawned");
 }
@@ -0,0 +1,171 @@
+//! Mods: folders in `mods/` that add to or replace parts of the game without recompiling.
+//!
+//! Mods are applied in name order, later ones win. Each mod folder can have
+//! - `mod.ron`, a [`ModManifest`] with extra names for registry prefabs
+//! - `entities/<identifier>/*.ron`, entity definitions used instead of the ones in `assets/`
+//! - `input/*.ron`, input profiles added as `<mod>/<file name>`
+//! - `images/**`, sprite sheets replacing the image at the same path under `assets/`
+
+use crate::input::{InputProfile, InputSettings};
+use crate::registry::EntityRegistry;
+use bevy::asset::io::AssetSourceBuilder;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const MODS_DIR: &str = "mods";
+const MOD_SOURCE: &str = "mods";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ModManifest {
+    /// new registry name to the existing prefab it spawns
+    pub prefabs: HashMap<String, String>,
+}
+
+/// Images replaced by a mod, the original and the one replacing it.
+#[derive(Resource, Debug, Default)]
+struct ModImages(Vec<(Handle<Image>, Handle<Image>)>);
+
+static MODS: OnceLock<Vec<PathBuf>> = OnceLock::new();
+
+/// The mod folders in the order they apply.
+pub fn mod_dirs() -> &'static [PathBuf] {
+    MODS.get_or_init(|| {
+        let Ok(entries) = fs::read_dir(MODS_DIR) else {
+            return Vec::new();
+        };
+        let mut dirs = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        dirs.sort();
+        dirs
+    })
+}
+
+/// The last mod's copy of a file under `assets/`, if any mod has one.
+pub fn mod_override(asset_path: &str) -> Option<PathBuf> {
+    mod_dirs()
+        .iter()
+        .rev()
+        .map(|dir| dir.join(asset_path))
+        .find(|path| path.is_file())
+}
+
+fn mod_name(dir: &Path) -> String {
+    dir.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn read_ron<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, Box<dyn Error>> {
+    let string = fs::read_to_string(path)?;
+    Ok(ron::from_str(&string)?)
+}
+
+/// Every file under `dir`, relative to it.
+fn files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut found = Vec::new();
+    for path in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+        if path.is_dir() {
+            let prefix = path.strip_prefix(dir).unwrap_or(&path).to_path_buf();
+            found.extend(files(&path).into_iter().map(|file| prefix.join(file)));
+        } else if let Ok(file) = path.strip_prefix(dir) {
+            found.push(file.to_path_buf());
+        }
+    }
+    found
+}
+
+/// Has to be added before the `AssetPlugin`, so before `DefaultPlugins`.
+pub(crate) fn register_asset_source(app: &mut App) {
+    app.register_asset_source(MOD_SOURCE, AssetSourceBuilder::platform_default(MODS_DIR, None));
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<ModImages>()
+        .add_systems(Startup, load_mod_images)
+        .add_systems(Update, replace_images);
+    for dir in mod_dirs() {
+        let name = mod_name(dir);
+        info!("loading mod {name}");
+        let manifest_path = dir.join("mod.ron");
+        let manifest = if manifest_path.is_file() {
+            read_ron::<ModManifest>(&manifest_path).unwrap_or_else(|e| {
+                warn!("could not read {}: {e}", manifest_path.display());
+                ModManifest::default()
+            })
+        } else {
+            ModManifest::default()
+        };
+        let mut registry = app.world_mut().get_resource_or_init::<EntityRegistry>();
+        for (new, existing) in &manifest.prefabs {
+            registry.alias(new.clone(), existing);
+        }
+
+        let input_dir = dir.join("input");
+        let profiles = files(&input_dir)
+            .into_iter()
+            .filter(|file| file.extension().is_some_and(|extension| extension == "ron"))
+            .filter_map(|file| match read_ron::<InputProfile>(&input_dir.join(&file)) {
+                Ok(profile) => {
+                    let stem = file.with_extension("");
+                    Some((format!("{name}/{}", stem.to_string_lossy()), profile))
+                }
+                Err(e) => {
+                    warn!("could not read input profile {}: {e}", file.display());
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        if let Some(mut settings) = app.world_mut().get_resource_mut::<InputSettings>() {
+            settings.profiles.extend(profiles);
+        }
+    }
+}
+
+fn load_mod_images(mut mod_images: ResMut<ModImages>, asset_server: Res<AssetServer>) {
+    for dir in mod_dirs() {
+        let name = mod_name(dir);
+        for file in files(&dir.join("images")) {
+            let path = Path::new("images").join(&file);
+            let path = path.to_string_lossy().replace('\\', "/");
+            info!("mod {name} replaces {path}");
+            let original = asset_server.load::<Image>(path.clone());
+            let replacement = asset_server.load(format!("{MOD_SOURCE}://{name}/{path}"));
+            //a later mod replacing the same image wins
+            mod_images.0.retain(|(existing, _)| *existing != original);
+            mod_images.0.push((original, replacement));
+        }
+    }
+}
+
+fn replace_images(
+    mut events: MessageReader<AssetEvent<Image>>,
+    mut images: ResMut<Assets<Image>>,
+    mod_images: Res<ModImages>,
+) {
+    for event in events.read() {
+        //copying into the original sends a modified event, only react to loads
+        let AssetEvent::LoadedWithDependencies { id } = event else {
+            continue;
+        };
+        for (original, replacement) in &mod_images.0 {
+            if *id != original.id() && *id != replacement.id() {
+                continue;
+            }
+            if let Some(image) = images.get(replacement).cloned() {
+                let _ = images.insert(original, image);
+            }
+        }
+    }
+}
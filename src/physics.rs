@@ -1,19 +1,66 @@
-use crate::char_controller::prelude::*;
+use crate::app_config::AppConfig;
+use char_controller::broadphase::merge_aabbs;
+use char_controller::prelude::*;
+use crate::screens::level_ready;
+use crate::time::update_time_since;
 use avian2d::math::{AdjustPrecision, AsF32};
 use avian2d::prelude::*;
 use bevy::color::palettes::tailwind;
 use bevy::ecs::schedule::LogLevel::Ignore;
 use bevy::prelude::*;
-use bevy_ecs_tilemap::prelude::*;
+use bevy::utils::Parallel;
 use serde::{Deserialize, Serialize};
+use std::f32::consts::{PI, TAU};
 use std::marker::PhantomData;
 
-#[derive(Component, Default, Clone, Copy, Reflect)]
-pub struct Grounded;
+/// Standing on something walkable, kept up to date by [`check_grounded`].
+#[derive(Component, Clone, Copy, Debug, PartialEq, Reflect)]
+pub struct Grounded {
+    /// points away from the ground, along a ramp's slope
+    pub normal: Dir2,
+    /// the collider stood on
+    pub entity: Entity,
+}
+
+impl Default for Grounded {
+    fn default() -> Self {
+        Self {
+            normal: Dir2::Y,
+            entity: Entity::PLACEHOLDER,
+        }
+    }
+}
+
+/// Filter for [`Grounded`] entities, for queries that don't care what they stand on.
+pub type OnGround = With<Grounded>;
+
+/// Touching a wall to either side, kept up to date like [`Grounded`] for wall slides and jumps.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Walled {
+    /// points away from the wall
+    pub normal: Dir2,
+}
+
+impl Default for Walled {
+    fn default() -> Self {
+        Self { normal: Dir2::X }
+    }
+}
+
+/// Gizmos for the contacts of [`perform_move_and_slide`], toggled from the dev settings.
+#[cfg(feature = "dev")]
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct ContactGizmos;
 //separate control logics by type of controller
 #[derive(Component, Default, Clone, Copy, Reflect)]
+#[require(LastMoveOutput, ContactCache)]
 pub struct SlideController;
 
+/// The result of the last [`MoveAndSlide`] solve, `None` until the controller first moves.
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+pub struct LastMoveOutput(pub Option<MoveAndSlideOutput>);
+
 #[derive(Clone, Copy, Deserialize, Serialize)]
 pub enum ColliderShape {
     Ball(f32),
@@ -23,13 +70,500 @@ pub enum ColliderShape {
 #[derive(Clone, Copy, Component)]
 pub struct IgnoreGrounded;
 
+/// Triggered for every contact a [`SlideController`] makes while moving, on the controller.
+/// Global observers see every hit, ones on a controller only its own. Only carries the raw
+/// contact, what it means (damage, sound, a tile reacting) is left to the observers.
+#[derive(EntityEvent, Copy, Clone, Debug)]
+pub struct ControllerHit {
+    /// the controller that moved
+    pub entity: Entity,
+    /// the collider it hit
+    pub other: Entity,
+    pub point: Vec2,
+    pub normal: Dir2,
+    /// velocity of the controller going into the hit
+    pub velocity: Vec2,
+}
+
+/// How fast a moving platform is going, measured from how far it moved and turned since the last
+/// fixed tick. Anything that moves its transform by hand and can be stood on should have one, and
+/// should move in `FixedUpdate` before [`CharacterSystems::Sense`], moving it in `Update` makes
+/// the measured velocity jump between frames with and without a fixed tick.
+/// Controllers standing on it are carried along, around its pivot when it turns.
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PlatformVelocity {
+    pub velocity: Vec2,
+    /// radians per second, counterclockwise
+    pub angular_velocity: f32,
+    last_position: Option<Vec2>,
+    last_angle: Option<f32>,
+}
+
+/// A body of water centered on the entity. Controllers with their center inside are
+/// [`Submerged`] and swim by their [`SwimStats`](crate::jump::SwimStats).
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Water {
+    pub half_size: Vec2,
+}
+
+/// In [`Water`], kept up to date like [`Grounded`].
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Submerged;
+
+/// Triggered when a controller goes into [`Water`], for splashes.
+#[derive(EntityEvent, Copy, Clone, Debug)]
+pub struct EnteredWater {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+/// Triggered when a controller comes out of [`Water`].
+#[derive(EntityEvent, Copy, Clone, Debug)]
+pub struct LeftWater {
+    pub entity: Entity,
+    pub position: Vec2,
+}
+
+/// Carries whatever stands on it along without moving itself, for conveyor belts and treadmills.
+#[derive(Component, Default, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct SurfaceVelocity(pub Vec2);
+
+/// Triggered when a [`SlideController`] moving up hits its head on a ceiling. With
+/// [`CeilingMode::Bonk`] whatever upward speed it had is gone by then, with [`CeilingMode::Slide`]
+/// it keeps sliding along a slanted ceiling.
+#[derive(Event, Copy, Clone, Debug)]
+pub struct CeilingHit {
+    pub controller: Entity,
+    pub collider: Entity,
+    pub point: Vec2,
+    /// upward speed going into the ceiling
+    pub speed: f32,
+}
+
+/// Triggered when a [`SlideController`] gets squeezed between colliders it can't push free of,
+/// see [`MoveAndSlideOutput::crushed`].
+#[derive(EntityEvent, Copy, Clone, Debug)]
+pub struct Crushed {
+    pub entity: Entity,
+    /// where it was stuck
+    pub position: Vec2,
+}
+
+/// Per-entity tuning of the [`MoveAndSlide`] solve, entities without it use [`MoveAndSlideConfig::default`].
+#[derive(Component, Clone, Debug, Default, Reflect, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct MoveAndSlideSettings(pub MoveAndSlideConfig);
+
 #[derive(Component, Copy, Clone, Debug, Reflect, Default)]
+#[reflect(Component)]
+#[require(ExternalKinematicForces)]
 pub struct KinematicController {
     pub velocity: Vec2,
 }
-pub(crate) fn plugin(app: &mut App) {
-    app.add_plugins(PhysicsPlugins::default().with_length_unit(10.0))
-        .add_systems(FixedUpdate, perform_move_and_slide);
+
+impl KinematicController {
+    /// Changes the velocity right away. Systems pushing a controller around should go through
+    /// [`ExternalKinematicForces`] instead, so they add up rather than overwrite each other.
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.velocity += impulse;
+    }
+}
+
+/// Pushes on a [`KinematicController`] from outside its own movement, like knockback, wind and
+/// explosions. They pile up over a tick and go into the velocity together right before
+/// [`MoveAndSlide`], then start over.
+#[derive(Component, Copy, Clone, Debug, Reflect, Default)]
+#[reflect(Component)]
+pub struct ExternalKinematicForces {
+    /// velocity change, applied as is
+    pub impulse: Vec2,
+    /// acceleration, applied for the length of the tick
+    pub force: Vec2,
+}
+
+impl ExternalKinematicForces {
+    pub fn apply_impulse(&mut self, impulse: Vec2) {
+        self.impulse += impulse;
+    }
+
+    /// Pushes for this tick only, keep applying it for a steady push.
+    pub fn apply_force(&mut self, force: Vec2) {
+        self.force += force;
+    }
+}
+
+/// Which way is up for a controller, for gravity flips and turned sections of a level.
+/// Gravity pulls against it, the ground is looked for along it and [`MoveAndSlide`] sorts floors
+/// from walls and ceilings by it. Controllers without one have [`Dir2::Y`] as up.
+#[derive(Component, Copy, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct UpDirection(pub Dir2);
+
+impl Default for UpDirection {
+    fn default() -> Self {
+        Self(Dir2::Y)
+    }
+}
+
+impl UpDirection {
+    /// How fast `velocity` goes up.
+    pub fn speed(&self, velocity: Vec2) -> f32 {
+        velocity.dot(*self.0)
+    }
+
+    /// Replaces the upward part of `velocity`, leaving the sideways part as is.
+    pub fn set_speed(&self, velocity: &mut Vec2, speed: f32) {
+        *velocity += *self.0 * (speed - self.speed(*velocity));
+    }
+}
+pub fn plugin(app: &mut App) {
+    let length_unit = app.world().resource::<AppConfig>().length_unit;
+    app.add_plugins((
+        PhysicsPlugins::default().with_length_unit(length_unit),
+        KinematicCharacterPlugin,
+    ))
+    //sensing keeps running while the level loads, moving anything has to wait for it
+    .configure_sets(
+        FixedUpdate,
+        (
+            CharacterSystems::Carry,
+            CharacterSystems::Forces,
+            CharacterSystems::Move,
+        )
+            .run_if(level_ready),
+    );
+    #[cfg(feature = "dev")]
+    app.init_gizmo_group::<ContactGizmos>();
+}
+
+/// The steps every [`KinematicController`] goes through each fixed tick, in this order. Anything
+/// steering a controller (input, AI, knockback) goes between [`Sense`](Self::Sense) and
+/// [`Forces`](Self::Forces).
+#[derive(SystemSet, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum CharacterSystems {
+    /// finding out what the controller touches: ground, walls, water and platforms
+    Sense,
+    /// being carried along by platforms and conveyors
+    Carry,
+    /// gravity and outside pushes
+    Forces,
+    /// moving and sliding along whatever is hit
+    Move,
+}
+
+/// Moves anything with a [`CharacterControllerBundle`]. The movement itself is up to whatever
+/// writes the velocity, see [`locomotion`](crate::locomotion) for the players.
+///
+/// Doesn't know about the game: gravity and when characters may move are added to its
+/// [`CharacterSystems`] by whoever adds it, see [`plugin`].
+///
+/// Insert a [`BroadphaseSnapshot`] to share one broadphase query between crowds of characters.
+pub struct KinematicCharacterPlugin;
+
+impl Plugin for KinematicCharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<UpDirection>()
+            .register_type::<KinematicController>()
+            .register_type::<ExternalKinematicForces>()
+            .register_type::<SurfaceVelocity>()
+            .register_type::<Water>()
+            .register_type::<Submerged>()
+            .configure_sets(
+                FixedUpdate,
+                (
+                    CharacterSystems::Sense,
+                    CharacterSystems::Carry,
+                    CharacterSystems::Forces,
+                    CharacterSystems::Move,
+                )
+                    .chain(),
+            )
+            .add_systems(
+                FixedUpdate,
+                (
+                    (
+                        track_platform_velocity,
+                        orient_ground_casters,
+                        check_grounded,
+                        update_time_since::<Grounded>,
+                        check_walled,
+                        update_time_since::<Walled>,
+                        check_submerged,
+                    )
+                        .chain()
+                        .in_set(CharacterSystems::Sense),
+                    (ride_platforms, ride_surfaces)
+                        .chain()
+                        .in_set(CharacterSystems::Carry),
+                    apply_external_forces.in_set(CharacterSystems::Forces),
+                    (
+                        snapshot_broadphase.run_if(resource_exists::<BroadphaseSnapshot>),
+                        perform_move_and_slide,
+                    )
+                        .chain()
+                        .in_set(CharacterSystems::Move),
+                ),
+            );
+    }
+}
+
+/// What a character needs to be moved by the [`KinematicCharacterPlugin`]: a collider that slides
+/// along walls and a probe for the ground below it. Falling is up to whatever is added next to it,
+/// like [`JumpStats`](crate::jump::JumpStats).
+#[derive(Bundle, Clone)]
+pub struct CharacterControllerBundle {
+    pub controller: KinematicController,
+    pub slide: SlideController,
+    pub collider: Collider,
+    pub ground_caster: ShapeCaster,
+}
+
+impl CharacterControllerBundle {
+    pub fn new(collider: Collider) -> Self {
+        Self {
+            controller: KinematicController::default(),
+            slide: SlideController,
+            ground_caster: ShapeCaster::new(collider.clone(), Vec2::ZERO, 0.0, Dir2::NEG_Y)
+                .with_max_distance(GROUND_CASTER_DISTANCE),
+            collider,
+        }
+    }
+}
+
+/// Smallest dot of a ground normal with up, anything steeper counts as a wall.
+pub const GROUND_NORMAL_Y: f32 = 0.7;
+/// Projected velocities can point slightly upwards while standing, so allow a little slack.
+const GROUND_VELOCITY_EPSILON: f32 = 0.1;
+/// How far to either side a wall still counts as touched.
+const WALL_PROBE_DISTANCE: f32 = 1.0;
+/// How far below a [`CharacterControllerBundle`] the ground still counts as touched.
+const GROUND_CASTER_DISTANCE: f32 = 1.0;
+
+fn track_platform_velocity(
+    mut platforms: Query<(&mut PlatformVelocity, &GlobalTransform)>,
+    time: Res<Time>,
+) {
+    for (mut platform, transform) in platforms.iter_mut() {
+        let position = transform.translation().xy();
+        platform.velocity = platform
+            .last_position
+            .map_or(Vec2::ZERO, |last| (position - last) / time.delta_secs());
+        platform.last_position = Some(position);
+        let angle = transform.rotation().to_euler(EulerRot::XYZ).2;
+        //the shorter way around, a turn past half a circle in one tick is not a thing
+        let turn = platform
+            .last_angle
+            .map_or(0.0, |last| (angle - last + PI).rem_euclid(TAU) - PI);
+        platform.angular_velocity = turn / time.delta_secs();
+        platform.last_angle = Some(angle);
+    }
+}
+
+/// Moves grounded controllers the way the platform they stand on moved this tick, turning them
+/// around its pivot with it, before they move on their own. They stay upright while doing so.
+fn ride_platforms(
+    mut riders: Query<(&mut Transform, &ShapeHits), (With<KinematicController>, With<Grounded>)>,
+    platforms: Query<(&PlatformVelocity, &GlobalTransform)>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_secs();
+    for (mut transform, hits) in riders.iter_mut() {
+        let Some((platform, platform_transform)) =
+            hits.iter().find_map(|hit| platforms.get(hit.entity).ok())
+        else {
+            continue;
+        };
+        let pivot = platform_transform.translation().xy();
+        let last_pivot = pivot - platform.velocity * delta;
+        let position = transform.translation.xy();
+        let turned = Rot2::radians(platform.angular_velocity * delta) * (position - last_pivot);
+        transform.translation += (pivot + turned - position).extend(0.0);
+    }
+}
+
+fn ride_surfaces(
+    mut riders: Query<(&mut Transform, &ShapeHits), (With<KinematicController>, With<Grounded>)>,
+    surfaces: Query<&SurfaceVelocity>,
+    time: Res<Time>,
+) {
+    for (mut transform, hits) in riders.iter_mut() {
+        if let Some(surface) = hits.iter().find_map(|hit| surfaces.get(hit.entity).ok()) {
+            transform.translation += (surface.0 * time.delta_secs()).extend(0.0);
+        }
+    }
+}
+
+/// Points the ground check of turned controllers down their own up.
+fn orient_ground_casters(
+    mut query: Query<(&UpDirection, &mut ShapeCaster), Changed<UpDirection>>,
+) {
+    for (up, mut caster) in query.iter_mut() {
+        caster.direction = -up.0;
+    }
+}
+
+pub fn check_grounded(
+    mut commands: Commands,
+    mut query: Query<
+        (
+            Entity,
+            &ShapeHits,
+            &KinematicController,
+            Option<&MoveAndSlideSettings>,
+            Option<&UpDirection>,
+            Option<&mut Grounded>,
+        ),
+        Without<IgnoreGrounded>,
+    >,
+) {
+    for (entity, hits, controller, settings, up, grounded) in query.iter_mut() {
+        let up = up.copied().unwrap_or_default();
+        //too steep to stand on counts as a wall
+        let walkable = |normal: Vec2| {
+            Dir2::new(normal).is_ok_and(|normal| {
+                settings.is_none_or(|settings| settings.0.is_walkable(normal, up.0))
+            })
+        };
+        let ground = hits
+            .iter()
+            .find(|hit| up.speed(hit.normal1) >= GROUND_NORMAL_Y && walkable(hit.normal1))
+            .filter(|_| up.speed(controller.velocity) <= GROUND_VELOCITY_EPSILON)
+            .map(|hit| Grounded {
+                normal: Dir2::new(hit.normal1).unwrap_or(up.0),
+                entity: hit.entity,
+            });
+        match (ground, grounded) {
+            (Some(ground), Some(mut grounded)) => {
+                grounded.set_if_neq(ground);
+            }
+            (Some(ground), None) => {
+                commands.entity(entity).insert(ground);
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<Grounded>();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Casts the collider a little to both sides, anything hit there that
+/// [`MoveAndSlideConfig::classify`] calls a wall is one.
+pub fn check_walled(
+    mut commands: Commands,
+    query: Query<
+        (
+            Entity,
+            &Collider,
+            &Transform,
+            Option<&MoveAndSlideSettings>,
+            Option<&UpDirection>,
+            Option<&Walled>,
+        ),
+        With<SlideController>,
+    >,
+    spatial_query: SpatialQuery,
+) {
+    let default_config = MoveAndSlideConfig::default();
+    let cast_config = ShapeCastConfig::from_max_distance(WALL_PROBE_DISTANCE.adjust_precision());
+    for (entity, collider, transform, settings, up, walled) in query.iter() {
+        let config = settings.map_or(&default_config, |settings| &settings.0);
+        let up = up.copied().unwrap_or_default().0;
+        let right = Dir2::new_unchecked(-up.perp());
+        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+        let position = transform.translation.xy().adjust_precision();
+        let rotation = transform.rotation.to_euler(EulerRot::XYZ).2.adjust_precision();
+        let wall = [right, -right].into_iter().find_map(|direction| {
+            let hit = spatial_query.cast_shape(
+                collider,
+                position,
+                rotation,
+                direction,
+                &cast_config,
+                &filter,
+            )?;
+            let normal = Dir2::new(hit.normal1.f32()).ok()?;
+            (config.classify(normal, up) == Surface::Wall).then_some(normal)
+        });
+        match (wall, walled) {
+            (Some(normal), Some(walled)) if walled.normal == normal => {}
+            (Some(normal), _) => {
+                commands.entity(entity).insert(Walled { normal });
+            }
+            (None, Some(_)) => {
+                commands.entity(entity).remove::<Walled>();
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn check_submerged(
+    mut commands: Commands,
+    controllers: Query<(Entity, &Transform, Has<Submerged>), With<KinematicController>>,
+    water: Query<(&Water, &GlobalTransform)>,
+) {
+    for (entity, transform, was_submerged) in controllers.iter() {
+        let position = transform.translation.xy();
+        let submerged = water.iter().any(|(water, xf)| {
+            let offset = (position - xf.translation().xy()).abs();
+            offset.x <= water.half_size.x && offset.y <= water.half_size.y
+        });
+        match (submerged, was_submerged) {
+            (true, false) => {
+                commands.entity(entity).insert(Submerged);
+                commands.trigger(EnteredWater { entity, position });
+            }
+            (false, true) => {
+                commands.entity(entity).remove::<Submerged>();
+                commands.trigger(LeftWater { entity, position });
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn apply_external_forces(
+    mut query: Query<(&mut KinematicController, &mut ExternalKinematicForces)>,
+    time: Res<Time>,
+) {
+    for (mut controller, mut forces) in query.iter_mut() {
+        let ExternalKinematicForces { impulse, force } = std::mem::take(&mut *forces);
+        if impulse != Vec2::ZERO || force != Vec2::ZERO {
+            controller.apply_impulse(impulse + force * time.delta_secs());
+        }
+    }
+}
+
+/// Extra room around each controller's swept AABB, so the post-move contact queries still land inside the snapshot.
+const SNAPSHOT_MARGIN: f32 = 4.0;
+
+fn snapshot_broadphase(
+    char: Query<(&Collider, &KinematicController, &Transform), With<SlideController>>,
+    mut snapshot: ResMut<BroadphaseSnapshot>,
+    query_pipeline: Res<SpatialQueryPipeline>,
+    aabbs: Query<&ColliderAabb>,
+    time: Res<Time>,
+) {
+    let region = char
+        .iter()
+        .map(|(collider, controller, transform)| {
+            let position = transform.translation.xy().adjust_precision();
+            let rotation = transform.rotation.to_euler(EulerRot::XYZ).2.adjust_precision();
+            let sweep = controller.velocity * time.delta_secs();
+            let start = collider.aabb(position, rotation);
+            let end = collider.aabb(position + sweep, rotation);
+            merge_aabbs(start, end).grow(Vec2::splat(SNAPSHOT_MARGIN))
+        })
+        .reduce(merge_aabbs);
+    match region {
+        Some(region) => snapshot.rebuild(region, &query_pipeline, &aabbs),
+        None => snapshot.clear(),
+    }
 }
 
 impl From<ColliderShape> for Collider {
@@ -46,82 +580,131 @@ impl Default for ColliderShape {
         ColliderShape::Cuboid(20.0, 20.0)
     }
 }
-fn perform_move_and_slide(
+//each controller solves on its own, so they run in parallel and only the hits are gathered for later
+pub fn perform_move_and_slide(
     mut char: Query<
-        (Entity, &Collider, &mut KinematicController, &mut Transform),
+        (
+            Entity,
+            &Collider,
+            &mut KinematicController,
+            &mut Transform,
+            &mut LastMoveOutput,
+            &mut ContactCache,
+            Option<&MoveAndSlideSettings>,
+            Option<&UpDirection>,
+        ),
         With<SlideController>,
     >,
-    mut tile_q: Query<&mut TileColor>,
-    tilemap_q: Single<(
-        &TilemapSize,
-        &TilemapGridSize,
-        &TilemapTileSize,
-        &TilemapType,
-        &TileStorage,
-        &TilemapAnchor,
-    )>,
+    mut commands: Commands,
+    mut contacts: Local<Parallel<Vec<(ControllerHit, Vec2, f32)>>>,
+    mut ceilings: Local<Parallel<Vec<CeilingHit>>>,
+    mut crushed: Local<Parallel<Vec<Crushed>>>,
     move_and_slide: MoveAndSlide,
     time: Res<Time>,
-    #[cfg(feature = "dev")] mut gizmos: Gizmos,
+    #[cfg(feature = "dev")] mut gizmos: Gizmos<ContactGizmos>,
 ) {
-    let (size, grid_size, tile_size, map_type, storage, anchor) = tilemap_q.into_inner();
-    for (entity, collider, mut controller, mut transform) in char.iter_mut() {
-        let velocity = controller.velocity;
-        let filter = SpatialQueryFilter::from_excluded_entities([entity]);
-        let out = move_and_slide.move_and_slide(
+    let default_config = MoveAndSlideConfig::default();
+    char.par_iter_mut().for_each(
+        |(
+            entity,
             collider,
-            transform.translation.xy().adjust_precision(),
-            transform
-                .rotation
-                .to_euler(EulerRot::XYZ)
-                .2
-                .adjust_precision(),
-            velocity,
-            time.delta(),
-            &MoveAndSlideConfig::default(),
-            &filter,
-            #[cfg(feature = "dev")]
-            |hit| {
-                if let Some(pos) = TilePos::from_world_pos(
-                    &(transform.translation.xy()
-                        + controller.velocity.normalize() * 16.0
-                        + vec2(-8.0, -8.0)),
-                    size,
-                    grid_size,
-                    tile_size,
-                    map_type,
-                    anchor,
-                ) && let Some(entity) = storage.get(&pos)
-                {
-                    info!("hit the tile {entity}",);
-                    if let Ok(mut color) = tile_q.get_mut(entity) {
-                        color.0 = Color::BLACK;
-                    }
+            mut controller,
+            mut transform,
+            mut last_output,
+            mut cache,
+            settings,
+            up,
+        )| {
+            let config = settings.map_or(&default_config, |settings| &settings.0);
+            let turned;
+            let config = match up {
+                Some(up) if up.0 != config.up() => {
+                    turned = config.clone().with_up(up.0);
+                    &turned
                 }
-                if hit.intersects() {
-                    gizmos.circle_2d(
-                        Isometry2d::from_translation(transform.translation.xy()),
-                        33.0,
-                        tailwind::RED_600,
-                    );
-                } else {
-                    gizmos.arrow_2d(
-                        hit.point.f32(),
-                        (hit.point
-                            + hit.normal.adjust_precision() * hit.collision_distance
-                                / time.delta_secs().adjust_precision())
-                        .f32(),
-                        tailwind::EMERALD_400,
-                    );
+                _ => config,
+            };
+            let velocity = controller.velocity;
+            let origin = transform.translation.xy();
+            let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+            let mut touched = Vec::new();
+            let out = move_and_slide.move_and_slide_cached(
+                collider,
+                origin.adjust_precision(),
+                transform
+                    .rotation
+                    .to_euler(EulerRot::XYZ)
+                    .2
+                    .adjust_precision(),
+                velocity,
+                time.delta(),
+                config,
+                &filter,
+                &mut cache,
+                |_, _| true,
+                |hit| {
+                    let touch = ControllerHit {
+                        entity,
+                        other: hit.entity,
+                        point: hit.point.f32(),
+                        normal: *hit.normal,
+                        velocity: hit.velocity.f32(),
+                    };
+                    touched.push((touch, origin, hit.collision_distance.f32()));
+                    true
+                },
+            );
+            transform.translation = out.position.f32().extend(transform.translation.z);
+            controller.velocity = out.projected_velocity;
+            last_output.0 = Some(out);
+            contacts.borrow_local_mut().extend(touched);
+            if out.crushed {
+                crushed.borrow_local_mut().push(Crushed {
+                    entity,
+                    position: out.position.f32(),
+                });
+            }
+            if let Some(hit) = out.ceiling_hit {
+                //sliding keeps going along the ceiling, only a bonk stops dead
+                if !matches!(config.ceiling, CeilingMode::Slide) {
+                    let up = up.copied().unwrap_or_default();
+                    let speed = up.speed(controller.velocity).min(0.0);
+                    up.set_speed(&mut controller.velocity, speed);
                 }
-                info!("{}", hit.entity);
-                true
-            },
-            #[cfg(not(feature = "dev"))]
-            |hit| true,
-        );
-        transform.translation = out.position.f32().extend(transform.translation.z);
-        controller.velocity = out.projected_velocity;
-        //info!("{} is colliding with entities: {:?}", entity, collisions);
+                ceilings.borrow_local_mut().push(CeilingHit {
+                    controller: entity,
+                    collider: hit.entity,
+                    point: hit.point.f32(),
+                    speed: hit.speed.f32(),
+                });
+            }
+        },
+    );
+    for hit in ceilings.drain() {
+        commands.trigger(hit);
+    }
+    for crush in crushed.drain() {
+        commands.trigger(crush);
+    }
+
+    //resolving the actual tile is up to whoever listens
+    for (touch, origin, collision_distance) in contacts.drain() {
+        #[cfg(feature = "dev")]
+        {
+            if collision_distance == 0.0 {
+                gizmos.circle_2d(
+                    Isometry2d::from_translation(origin),
+                    33.0,
+                    tailwind::RED_600,
+                );
+            } else {
+                gizmos.arrow_2d(
+                    touch.point,
+                    touch.point + touch.normal * collision_distance / time.delta_secs(),
+                    tailwind::EMERALD_400,
+                );
+            }
+        }
+        commands.trigger(touch);
     }
 }
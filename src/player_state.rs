@@ -0,0 +1,127 @@
+//! Player progress that has to survive the player entity itself.
+//!
+//! Warps, level switches, hot reloads and spawn points can all end up with a fresh player entity,
+//! so the components here are mirrored into [`PersistentPlayerState`] whenever they change and
+//! copied back onto every new player. Dying resets power and health, [`NewGame`] resets everything.
+
+use crate::mario::{Char, PlayerDied};
+use bevy::prelude::*;
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub enum PowerState {
+    #[default]
+    Small,
+    Big,
+}
+
+/// Hits left before dying. The default is a single hit on purpose, the player dies to anything
+/// like in the original game, tougher players and enemies set their own.
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Self { current: 1, max: 1 }
+    }
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Coins(pub u32);
+
+#[derive(Component, Clone, PartialEq, Eq, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Inventory(pub Vec<String>);
+
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct PersistentPlayerState {
+    pub power: PowerState,
+    pub health: Health,
+    pub coins: Coins,
+    pub inventory: Inventory,
+}
+
+/// Throws away all progress.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct NewGame;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<PersistentPlayerState>()
+        .init_resource::<PersistentPlayerState>()
+        .add_systems(PostUpdate, save_player_state)
+        .add_observer(load_player_state)
+        .add_observer(reset_on_death)
+        .add_observer(reset_on_new_game);
+}
+
+fn load_player_state(add: On<Add, Char>, mut commands: Commands, state: Res<PersistentPlayerState>) {
+    commands.entity(add.entity).insert((
+        state.power,
+        state.health,
+        state.coins,
+        state.inventory.clone(),
+    ));
+}
+
+fn save_player_state(
+    mario: Query<
+        (&PowerState, &Health, &Coins, &Inventory),
+        (
+            With<Char>,
+            Or<(
+                Changed<PowerState>,
+                Changed<Health>,
+                Changed<Coins>,
+                Changed<Inventory>,
+            )>,
+        ),
+    >,
+    mut state: ResMut<PersistentPlayerState>,
+) {
+    for (power, health, coins, inventory) in mario.iter() {
+        state.power = *power;
+        state.health = *health;
+        state.coins = *coins;
+        state.inventory = inventory.clone();
+    }
+}
+
+fn refill(state: &mut PersistentPlayerState) {
+    state.power = PowerState::default();
+    state.health.current = state.health.max;
+}
+
+fn reset_on_death(
+    _death: On<PlayerDied>,
+    mut mario: Query<(&mut PowerState, &mut Health), With<Char>>,
+    mut state: ResMut<PersistentPlayerState>,
+) {
+    refill(&mut state);
+    for (mut power, mut health) in mario.iter_mut() {
+        *power = state.power;
+        *health = state.health;
+    }
+}
+
+fn reset_on_new_game(
+    _new_game: On<NewGame>,
+    mut commands: Commands,
+    mario: Query<Entity, With<Char>>,
+    mut state: ResMut<PersistentPlayerState>,
+) {
+    *state = PersistentPlayerState::default();
+    for entity in mario.iter() {
+        commands.entity(entity).insert((
+            state.power,
+            state.health,
+            state.coins,
+            state.inventory.clone(),
+        ));
+    }
+}
@@ -0,0 +1,268 @@
+//! Opt-in playtest telemetry: where players die or fall, how long levels take and where they quit.
+//!
+//! Nothing is recorded unless `enabled` is set in `assets/playtest.ron`. Records only hold a
+//! random id for the session, the level iid, positions and times, never anything about the
+//! player or their machine. They are appended to a local file one RON record per line, and with
+//! the `playtest_upload` feature also posted to `endpoint`.
+
+use crate::levels::LevelComplete;
+use crate::mario::{Char, PlayerDied};
+use crate::physics::Grounded;
+use crate::split_screen::PlayerSlot;
+use crate::storage;
+use crate::warps::Warping;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+
+const SETTINGS_PATH: &str = "assets/playtest.ron";
+
+/// `assets/playtest.ron`
+#[derive(Resource, Debug, Clone, Reflect, Deserialize)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct PlaytestSettings {
+    pub enabled: bool,
+    /// file the records are appended to
+    pub path: String,
+    /// where records are posted with the `playtest_upload` feature
+    pub endpoint: Option<String>,
+    /// how much lower than it left the ground the player has to land for a fall to be recorded
+    pub fall_height: f32,
+}
+
+impl Default for PlaytestSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: "playtest.ron".to_string(),
+            endpoint: None,
+            fall_height: 96.0,
+        }
+    }
+}
+
+impl PlaytestSettings {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(ron::from_str(&storage::read(path)?)?)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum PlaytestEvent {
+    Death {
+        position: [i32; 2],
+    },
+    /// left the ground at `position` and landed `height` lower
+    Fall {
+        position: [i32; 2],
+        height: i32,
+        /// [`PlayerSlot`] of whoever fell
+        #[serde(default)]
+        player: usize,
+    },
+    LevelComplete,
+    /// the game was closed, one per player
+    Quit {
+        position: Option<[i32; 2]>,
+        #[serde(default)]
+        player: usize,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaytestRecord {
+    /// random per run of the game, ties records of one session together
+    pub session: u64,
+    /// iid of the level
+    pub level: String,
+    /// seconds since the level spawned
+    pub time: f32,
+    pub event: PlaytestEvent,
+}
+
+impl PlaytestRecord {
+    /// Reads every record in a file written by the recorder, skipping lines that don't parse.
+    pub fn read_all(path: &str) -> Result<Vec<Self>, Box<dyn Error>> {
+        let string = storage::read(path)?;
+        Ok(string.lines().filter_map(|line| ron::from_str(line).ok()).collect())
+    }
+}
+
+#[derive(Resource, Debug)]
+struct Session {
+    id: u64,
+    level: String,
+    /// virtual time the level spawned at
+    level_start: f32,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let settings = PlaytestSettings::read(SETTINGS_PATH).unwrap_or_default();
+    if settings.enabled {
+        info!("playtest telemetry on, recording to {}", settings.path);
+    }
+    app.register_type::<PlaytestSettings>()
+        .insert_resource(settings)
+        .insert_resource(Session {
+            id: rand::random(),
+            level: String::new(),
+            level_start: 0.0,
+        })
+        .add_systems(Update, (start_level, record_falls))
+        .add_systems(Last, record_quit)
+        .add_observer(record_death)
+        .add_observer(record_complete);
+}
+
+fn start_level(
+    mut level_events: MessageReader<LevelEvent>,
+    mut session: ResMut<Session>,
+    time: Res<Time>,
+    warping: Res<Warping>,
+) {
+    for event in level_events.read() {
+        //a sub-level is part of the level we warped from
+        if warping.0 {
+            continue;
+        }
+        if let LevelEvent::Spawned(iid) = event {
+            session.level = iid.to_string();
+            session.level_start = time.elapsed_secs();
+        }
+    }
+}
+
+fn rounded(position: Vec2) -> [i32; 2] {
+    position.round().as_ivec2().to_array()
+}
+
+fn record(settings: &PlaytestSettings, session: &Session, time: &Time, event: PlaytestEvent) {
+    if !settings.enabled {
+        return;
+    }
+    let record = PlaytestRecord {
+        session: session.id,
+        level: session.level.clone(),
+        time: time.elapsed_secs() - session.level_start,
+        event,
+    };
+    let line = match ron::to_string(&record) {
+        Ok(line) => line,
+        Err(e) => {
+            warn!("could not write playtest record: {e}");
+            return;
+        }
+    };
+    let mut contents = storage::read(&settings.path).unwrap_or_default();
+    contents.push_str(&line);
+    contents.push('\n');
+    if let Err(e) = storage::write(&settings.path, &contents) {
+        warn!("write error {e}");
+    }
+    #[cfg(feature = "playtest_upload")]
+    if let Some(endpoint) = settings.endpoint.clone() {
+        upload::post(endpoint, line);
+    }
+}
+
+fn record_death(
+    death: On<PlayerDied>,
+    settings: Res<PlaytestSettings>,
+    session: Res<Session>,
+    time: Res<Time>,
+) {
+    let position = rounded(death.position);
+    record(&settings, &session, &time, PlaytestEvent::Death { position });
+}
+
+fn record_falls(
+    settings: Res<PlaytestSettings>,
+    session: Res<Session>,
+    time: Res<Time>,
+    mario: Query<(&Transform, Has<Grounded>, Option<&PlayerSlot>), With<Char>>,
+    mut takeoffs: Local<HashMap<usize, Vec2>>,
+) {
+    //players that left stop falling
+    takeoffs.retain(|player, _| {
+        mario
+            .iter()
+            .any(|(_, _, slot)| slot.map_or(0, |slot| slot.0) == *player)
+    });
+    for (transform, grounded, slot) in mario.iter() {
+        let player = slot.map_or(0, |slot| slot.0);
+        let position = transform.translation.xy();
+        match (grounded, takeoffs.get(&player).copied()) {
+            (false, None) => {
+                takeoffs.insert(player, position);
+            }
+            (true, Some(from)) => {
+                takeoffs.remove(&player);
+                let height = from.y - position.y;
+                if height >= settings.fall_height {
+                    let event = PlaytestEvent::Fall {
+                        position: rounded(from),
+                        height: height.round() as i32,
+                        player,
+                    };
+                    record(&settings, &session, &time, event);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn record_complete(
+    _complete: On<LevelComplete>,
+    settings: Res<PlaytestSettings>,
+    session: Res<Session>,
+    time: Res<Time>,
+) {
+    record(&settings, &session, &time, PlaytestEvent::LevelComplete);
+}
+
+fn record_quit(
+    mut exits: MessageReader<AppExit>,
+    settings: Res<PlaytestSettings>,
+    session: Res<Session>,
+    time: Res<Time>,
+    mario: Query<(&Transform, Option<&PlayerSlot>), With<Char>>,
+) {
+    if exits.read().next().is_none() {
+        return;
+    }
+    if mario.is_empty() {
+        let event = PlaytestEvent::Quit {
+            position: None,
+            player: 0,
+        };
+        record(&settings, &session, &time, event);
+    }
+    for (transform, slot) in mario.iter() {
+        let event = PlaytestEvent::Quit {
+            position: Some(rounded(transform.translation.xy())),
+            player: slot.map_or(0, |slot| slot.0),
+        };
+        record(&settings, &session, &time, event);
+    }
+}
+
+#[cfg(feature = "playtest_upload")]
+mod upload {
+    use bevy::prelude::*;
+    use bevy::tasks::IoTaskPool;
+
+    /// Posts in the background, a record that doesn't make it is only in the local file.
+    pub(super) fn post(endpoint: String, line: String) {
+        IoTaskPool::get()
+            .spawn(async move {
+                if let Err(e) = ureq::post(&endpoint).send(line) {
+                    warn!("could not post playtest record to {endpoint}: {e}");
+                }
+            })
+            .detach();
+    }
+}
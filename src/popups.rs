@@ -0,0 +1,97 @@
+//! Floating text in the world (points from stomps and pickups) that rises and fades out.
+//!
+//! Finished popups are hidden and kept around to be reused instead of despawned, until the game
+//! is left.
+
+use crate::layers::RenderLayerOrder;
+use crate::score::AddScore;
+use crate::screens::Screen;
+use crate::time::PausableSystems;
+use bevy::prelude::*;
+
+/// Shows `text` floating up from `position`.
+#[derive(Event, Clone, Debug)]
+pub struct Popup {
+    pub text: String,
+    pub position: Vec2,
+}
+
+#[derive(Component, Debug)]
+struct PopupText {
+    timer: Timer,
+}
+
+/// Hidden popups waiting to be reused.
+#[derive(Resource, Default, Debug)]
+struct PopupPool(Vec<Entity>);
+
+const POPUP_SECONDS: f32 = 0.8;
+/// How far a popup rises over its lifetime.
+const POPUP_RISE: f32 = 24.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PopupPool>()
+        .add_systems(Update, animate_popups.in_set(PausableSystems))
+        .add_systems(OnExit(Screen::Game), empty_pool)
+        .add_observer(popup_score)
+        .add_observer(show_popup);
+}
+
+fn popup_score(add: On<AddScore>, mut commands: Commands) {
+    commands.trigger(Popup {
+        text: add.amount.to_string(),
+        position: add.position,
+    });
+}
+
+fn show_popup(popup: On<Popup>, mut commands: Commands, mut pool: ResMut<PopupPool>) {
+    let bundle = (
+        PopupText {
+            timer: Timer::from_seconds(POPUP_SECONDS, TimerMode::Once),
+        },
+        Text2d::new(popup.text.clone()),
+        TextColor(Color::WHITE),
+        Transform::from_translation(popup.position.extend(RenderLayerOrder::Popups.z()))
+            .with_scale(Vec3::splat(0.5)),
+        Visibility::Inherited,
+    );
+    match pool.0.pop() {
+        Some(entity) => {
+            commands.entity(entity).insert(bundle);
+        }
+        None => {
+            commands.spawn((
+                Name::new("Popup"),
+                TextFont::from_font_size(24.0),
+                DespawnOnExit(Screen::Game),
+                bundle,
+            ));
+        }
+    }
+}
+
+fn animate_popups(
+    mut popups: Query<(Entity, &mut PopupText, &mut Transform, &mut TextColor, &mut Visibility)>,
+    mut pool: ResMut<PopupPool>,
+    time: Res<Time>,
+) {
+    for (entity, mut popup, mut transform, mut color, mut visibility) in popups.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        popup.timer.tick(time.delta());
+        if popup.timer.just_finished() {
+            *visibility = Visibility::Hidden;
+            pool.0.push(entity);
+            continue;
+        }
+        let step = POPUP_RISE / POPUP_SECONDS * time.delta_secs();
+        transform.translation.y += step;
+        color.0.set_alpha(1.0 - popup.timer.fraction());
+    }
+}
+
+//the popups themselves are gone with the game screen
+fn empty_pool(mut pool: ResMut<PopupPool>) {
+    pool.0.clear();
+}
@@ -0,0 +1,77 @@
+//! Practice mode: quick save the state of the level to memory and jump back to it with a hotkey,
+//! to practice a section over and over. The save is a [`Snapshot`], see the snapshot module for
+//! what is and isn't in it. Practice mode also keeps the rewind history.
+
+use crate::snapshot::{Snapshot, SnapshotHistory};
+use bevy::prelude::*;
+
+/// Whether the practice hotkeys do anything, always on in dev builds.
+#[derive(Resource, Clone, Copy, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct PracticeMode {
+    pub unlocked: bool,
+}
+
+impl Default for PracticeMode {
+    fn default() -> Self {
+        Self {
+            unlocked: cfg!(feature = "dev"),
+        }
+    }
+}
+
+/// The quick save, there is only one slot.
+#[derive(Resource, Default)]
+struct QuickSave(Option<Snapshot>);
+
+const SAVE_KEY: KeyCode = KeyCode::F6;
+const LOAD_KEY: KeyCode = KeyCode::F7;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<PracticeMode>()
+        .init_resource::<PracticeMode>()
+        .init_resource::<QuickSave>()
+        .add_systems(
+            Update,
+            (
+                record_while_practicing.run_if(resource_changed::<PracticeMode>),
+                practice_hotkeys,
+            ),
+        );
+}
+
+fn record_while_practicing(practice: Res<PracticeMode>, mut history: ResMut<SnapshotHistory>) {
+    history.set_recording(practice.unlocked);
+}
+
+fn practice_hotkeys(
+    input: Res<ButtonInput<KeyCode>>,
+    practice: Res<PracticeMode>,
+    mut commands: Commands,
+) {
+    if !practice.unlocked {
+        return;
+    }
+    if input.just_pressed(SAVE_KEY) {
+        commands.queue(quick_save);
+    } else if input.just_pressed(LOAD_KEY) {
+        commands.queue(quick_load);
+    }
+}
+
+fn quick_save(world: &mut World) {
+    let save = Snapshot::capture(world);
+    info!("practice save with {} entities", save.entities.len());
+    world.resource_mut::<QuickSave>().0 = Some(save);
+}
+
+fn quick_load(world: &mut World) {
+    world.resource_scope(|world, save: Mut<QuickSave>| {
+        let Some(save) = &save.0 else {
+            info!("nothing saved to load");
+            return;
+        };
+        save.restore(world);
+        info!("practice load");
+    });
+}
@@ -0,0 +1,67 @@
+//! Drops optional effects when frames take too long, mostly for the web build.
+//!
+//! The frame time is averaged over a second or so. Once it stays above [`Quality::slow_frame`],
+//! weather particles and dash ghosts are cut down until it's back under [`Quality::fast_frame`].
+//! This is on by default only on wasm, where the browser decides how fast we get to run.
+
+use bevy::prelude::*;
+
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct Quality {
+    /// lower quality by itself when frames are slow
+    pub auto: bool,
+    /// seconds per frame that count as too slow
+    pub slow_frame: f32,
+    /// seconds per frame that count as fine again, below `slow_frame` so it doesn't flicker
+    pub fast_frame: f32,
+    /// share of particles and ghosts kept while reduced
+    pub reduced_scale: f32,
+    pub reduced: bool,
+    /// smoothed frame time
+    frame_time: f32,
+}
+
+impl Default for Quality {
+    fn default() -> Self {
+        Self {
+            auto: cfg!(target_arch = "wasm32"),
+            slow_frame: 1.0 / 40.0,
+            fast_frame: 1.0 / 55.0,
+            reduced_scale: 0.3,
+            reduced: false,
+            frame_time: 1.0 / 60.0,
+        }
+    }
+}
+
+impl Quality {
+    /// How much of an optional effect to keep, 1 is all of it.
+    pub fn scale(&self) -> f32 {
+        if self.reduced { self.reduced_scale } else { 1.0 }
+    }
+}
+
+/// How fast the smoothed frame time follows the real one.
+const SMOOTHING: f32 = 0.02;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Quality>()
+        .init_resource::<Quality>()
+        .add_systems(Update, measure_frames);
+}
+
+fn measure_frames(mut quality: ResMut<Quality>, time: Res<Time<Real>>) {
+    if !quality.auto {
+        return;
+    }
+    let frame_time = quality.frame_time + (time.delta_secs() - quality.frame_time) * SMOOTHING;
+    quality.bypass_change_detection().frame_time = frame_time;
+    if !quality.reduced && frame_time > quality.slow_frame {
+        info!("frames are slow ({:.1}ms), reducing effects", frame_time * 1000.0);
+        quality.reduced = true;
+    } else if quality.reduced && frame_time < quality.fast_frame {
+        info!("frames are fast again, restoring effects");
+        quality.reduced = false;
+    }
+}
@@ -0,0 +1,205 @@
+//! Two local players racing each other through a level.
+//!
+//! [`StartRace`] respawns the current level, lines the players up at the start and starts a clock
+//! for every player. Each clock stops when its player reaches a [`Goal`], which doesn't end the
+//! level while racing, and once every player is in the times are compared on a results screen.
+//! Each player follows their own half of the screen, see [`split_screen`](crate::split_screen).
+
+use crate::camera::CameraReset;
+use crate::levels::GOAL_REACH;
+use crate::mario::{Char, Goal};
+use crate::physics::perform_move_and_slide;
+use crate::screens::{level_ready, Screen};
+use crate::spawn_point::ReturnToStart;
+use crate::split_screen::PlayerSlot;
+use crate::ui::{Toast, UiSound};
+use bevy::prelude::*;
+use bevy_cobweb_ui::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use std::collections::BTreeMap;
+
+/// Restarts the level as a race between the players that joined.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct StartRace;
+
+/// The race going on, from [`StartRace`] until its results are closed.
+#[derive(Resource, Debug, Default)]
+pub struct Race {
+    /// seconds of the level being playable since the start
+    pub elapsed: f32,
+    /// time of every player that reached the goal, by slot
+    pub finished: BTreeMap<usize, f32>,
+    /// how many players are racing
+    pub players: usize,
+}
+
+impl Race {
+    pub fn is_over(&self) -> bool {
+        self.finished.len() >= self.players
+    }
+
+    /// The clock of one player, stopped once they're in.
+    pub fn time(&self, slot: usize) -> f32 {
+        self.finished.get(&slot).copied().unwrap_or(self.elapsed)
+    }
+}
+
+#[derive(Component)]
+struct RaceHud;
+
+#[derive(Component)]
+struct RaceTimers;
+
+#[derive(Component)]
+struct RaceResults;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        FixedUpdate,
+        (tick_race, reach_finish)
+            .chain()
+            .after(perform_move_and_slide)
+            .run_if(resource_exists::<Race>.and(level_ready)),
+    )
+    .add_systems(Update, (show_timers, show_results).run_if(resource_exists::<Race>))
+    .add_observer(start_race);
+}
+
+/// Run condition for while a race is going on, the goal doesn't end the level then.
+pub fn racing(race: Option<Res<Race>>) -> bool {
+    race.is_some()
+}
+
+fn start_race(
+    _start: On<StartRace>,
+    mut commands: Commands,
+    mut s: SceneBuilder,
+    players: Query<(), With<Char>>,
+    level: Query<Entity, (With<LevelIid>, Without<Char>)>,
+    hud: Query<Entity, With<RaceHud>>,
+    results: Query<Entity, With<RaceResults>>,
+) {
+    let players = players.iter().count();
+    if players < 2 {
+        commands.trigger(UiSound::Error);
+        commands.trigger(Toast("press start on a gamepad to join the race".to_string()));
+        return;
+    }
+    info!("race between {players} players");
+    //the players are worldly and survive the respawn, so line them up before the clock starts
+    commands.trigger(ReturnToStart);
+    for level in level.iter() {
+        commands.entity(level).insert(Respawn);
+    }
+    commands.trigger(CameraReset);
+    commands.insert_resource(Race {
+        players,
+        ..default()
+    });
+    for results in results.iter() {
+        commands.entity(results).despawn();
+    }
+    if hud.is_empty() {
+        commands
+            .ui_root()
+            .spawn_scene(("ui/main.cob", "race_timers"), &mut s, |sc| {
+                sc.insert((RaceHud, DespawnOnExit(Screen::Game)));
+                sc.get("text").insert(RaceTimers);
+            });
+    }
+}
+
+fn tick_race(time: Res<Time>, mut race: ResMut<Race>) {
+    if !race.is_over() {
+        race.elapsed += time.delta_secs();
+    }
+}
+
+fn reach_finish(
+    mut race: ResMut<Race>,
+    players: Query<(&Transform, Option<&PlayerSlot>), With<Char>>,
+    goals: Query<&GlobalTransform, With<Goal>>,
+) {
+    for (transform, slot) in players.iter() {
+        let slot = slot.map_or(0, |slot| slot.0);
+        if race.finished.contains_key(&slot) {
+            continue;
+        }
+        let position = transform.translation.xy();
+        let reached = goals
+            .iter()
+            .any(|goal| goal.translation().xy().distance(position) <= GOAL_REACH);
+        if reached {
+            let time = race.elapsed;
+            info!("player {} finished in {time:.2}s", slot + 1);
+            race.finished.insert(slot, time);
+        }
+    }
+}
+
+fn show_timers(
+    mut c: Commands,
+    race: Res<Race>,
+    timers: Query<Entity, With<RaceTimers>>,
+    mut shown: Local<String>,
+) {
+    let Ok(timers) = timers.single() else {
+        return;
+    };
+    let text = (0..race.players)
+        .map(|slot| format!("P{} {:.2}", slot + 1, race.time(slot)))
+        .collect::<Vec<_>>()
+        .join("   ");
+    if text == *shown {
+        return;
+    }
+    c.ui_builder(timers).update_text(text.clone());
+    *shown = text;
+}
+
+/// Compares the times once everyone is in, closing it ends the race.
+fn show_results(
+    mut commands: Commands,
+    mut s: SceneBuilder,
+    race: Res<Race>,
+    results: Query<(), With<RaceResults>>,
+    hud: Query<Entity, With<RaceHud>>,
+) {
+    if !race.is_over() || !results.is_empty() {
+        return;
+    }
+    let mut times = race.finished.iter().map(|(&slot, &time)| (slot, time)).collect::<Vec<_>>();
+    times.sort_by(|a, b| a.1.total_cmp(&b.1));
+    let Some(&(winner, best)) = times.first() else {
+        return;
+    };
+    let lines = times
+        .iter()
+        .map(|&(slot, time)| match time - best {
+            behind if behind > 0.0 => format!("Player {}  {time:.2}s  +{behind:.2}s", slot + 1),
+            _ => format!("Player {}  {time:.2}s", slot + 1),
+        })
+        .collect::<Vec<_>>();
+    for hud in hud.iter() {
+        commands.entity(hud).despawn();
+    }
+    commands
+        .ui_root()
+        .spawn_scene(("ui/main.cob", "race_results"), &mut s, |sc| {
+            sc.insert((RaceResults, DespawnOnExit(Screen::Game)));
+            sc.get("winner").update_text(format!("Player {} wins!", winner + 1));
+            for line in lines {
+                sc.edit("times", |sc| {
+                    sc.spawn_scene(("ui/main.cob", "race_time"), |sc| {
+                        sc.get("text").update_text(line);
+                    });
+                });
+            }
+            let entity = sc.id();
+            sc.on_pressed(move |mut commands: Commands| {
+                commands.remove_resource::<Race>();
+                commands.get_entity(entity)?.despawn();
+                OK
+            });
+        });
+}
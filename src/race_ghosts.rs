@@ -0,0 +1,343 @@
+//! Best run ghosts: every run from a level spawning to its goal ([`LevelComplete`]) is recorded, the fastest one
+//! is kept and raced on the next attempts as a see-through player.
+//!
+//! Runs are small RON files of rounded positions. [`ExportGhost`] writes one out and
+//! [`ImportGhost`] reads one back to race instead of your own best, with the `ghost_fetch`
+//! feature [`FetchGhost`] downloads one over HTTP. The menu exports and imports through
+//! [`SHARED_GHOST_PATH`], and `--import-ghost` and `--fetch-ghost` do it on launch.
+//!
+//! Best runs are saved to [`BEST_GHOSTS_PATH`] and survive restarts.
+
+use crate::layers::{RenderLayer, RenderLayerOrder};
+use crate::levels::LevelComplete;
+use crate::mario::{Char, PLAYER_IMAGE};
+use crate::physics::perform_move_and_slide;
+use crate::screens::{level_ready, Screen};
+use crate::split_screen::PlayerSlot;
+use crate::storage;
+use crate::ui::Toast;
+use crate::warps::Warping;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// One run through a level.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct GhostRun {
+    /// iid of the level it was recorded in
+    pub level: String,
+    /// seconds from the level spawning to reaching the goal
+    pub time: f32,
+    /// player position every fixed tick, rounded to whole pixels
+    pub frames: Vec<[i32; 2]>,
+}
+
+impl GhostRun {
+    pub fn export(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string =
+            ron::ser::to_string_pretty(self, PrettyConfig::default().compact_arrays(true))?;
+        storage::write(path, &string)?;
+        Ok(())
+    }
+
+    pub fn import(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = storage::read(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
+
+/// Where the menu exports the best run of the current level to and imports one from.
+pub const SHARED_GHOST_PATH: &str = "shared_ghost.ron";
+/// Where the best run of every level is kept between sessions.
+pub const BEST_GHOSTS_PATH: &str = "ghosts.ron";
+
+#[derive(Resource, Debug, Default)]
+pub struct Ghosts {
+    /// fastest run of each level so far, by level iid
+    pub best: HashMap<String, GhostRun>,
+    /// raced instead of the best run in its level, set by importing
+    pub racing: Option<GhostRun>,
+    /// iid of the level the current runs are in
+    recording_level: Option<String>,
+    /// every player's run so far, by [`PlayerSlot`]
+    recording: HashMap<usize, GhostRun>,
+}
+
+/// Writes the best run of a level to a file.
+#[derive(Event, Clone, Debug)]
+pub struct ExportGhost {
+    pub level: String,
+    pub path: String,
+}
+
+/// Reads a run from a file and races it from the next time its level spawns.
+#[derive(Event, Clone, Debug)]
+pub struct ImportGhost {
+    pub path: String,
+}
+
+/// Downloads a run and races it like an imported one.
+#[cfg(feature = "ghost_fetch")]
+#[derive(Event, Clone, Debug)]
+pub struct FetchGhost {
+    pub url: String,
+}
+
+/// Plays back a run.
+#[derive(Component, Debug)]
+struct RaceGhost {
+    run: GhostRun,
+    frame: usize,
+}
+
+const GHOST_ALPHA: f32 = 0.4;
+
+fn read_best() -> Result<HashMap<String, GhostRun>, Box<dyn Error>> {
+    let string = storage::read(BEST_GHOSTS_PATH)?;
+    Ok(ron::from_str(&string)?)
+}
+
+fn write_best(best: &HashMap<String, GhostRun>) -> Result<(), Box<dyn Error>> {
+    let string = ron::ser::to_string_pretty(best, PrettyConfig::default().compact_arrays(true))?;
+    storage::write(BEST_GHOSTS_PATH, &string)?;
+    Ok(())
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let best = read_best().unwrap_or_else(|e| {
+        info!("no best runs loaded: {e}");
+        HashMap::default()
+    });
+    app.insert_resource(Ghosts { best, ..default() })
+        .add_systems(Update, start_run)
+        .add_systems(
+            FixedUpdate,
+            (record_run, play_ghosts)
+                .chain()
+                .after(perform_move_and_slide)
+                .run_if(level_ready),
+        )
+        .add_observer(finish_run)
+        .add_observer(export_ghost)
+        .add_observer(import_ghost);
+    #[cfg(feature = "ghost_fetch")]
+    app.add_systems(Update, fetch::poll_downloads)
+        .add_observer(fetch::fetch_ghost);
+}
+
+fn start_run(
+    mut level_events: MessageReader<LevelEvent>,
+    mut commands: Commands,
+    mut ghosts: ResMut<Ghosts>,
+    race_ghosts: Query<Entity, With<RaceGhost>>,
+    asset_server: Res<AssetServer>,
+    warping: Res<Warping>,
+) {
+    for event in level_events.read() {
+        let LevelEvent::Spawned(iid) = event else {
+            continue;
+        };
+        //the run keeps going through sub-levels
+        if warping.0 {
+            continue;
+        }
+        let level = iid.to_string();
+        ghosts.recording_level = Some(level.clone());
+        ghosts.recording.clear();
+        for entity in race_ghosts.iter() {
+            commands.entity(entity).despawn();
+        }
+        let run = ghosts
+            .racing
+            .as_ref()
+            .filter(|run| run.level == level)
+            .or_else(|| ghosts.best.get(&level));
+        let Some(run) = run else {
+            continue;
+        };
+        commands.spawn((
+            Name::new("Race ghost"),
+            RaceGhost {
+                run: run.clone(),
+                frame: 0,
+            },
+            Sprite {
+                image: asset_server.load(PLAYER_IMAGE),
+                rect: Some(Rect::new(0.0, 0.0, 16.0, 16.0)),
+                color: Color::WHITE.with_alpha(GHOST_ALPHA),
+                ..default()
+            },
+            RenderLayer(RenderLayerOrder::Ghosts),
+            Transform::default(),
+            Visibility::Hidden,
+            DespawnOnExit(Screen::Game),
+        ));
+    }
+}
+
+fn record_run(
+    mut ghosts: ResMut<Ghosts>,
+    mario: Query<(&Transform, Option<&PlayerSlot>), With<Char>>,
+    time: Res<Time>,
+) {
+    let ghosts = &mut *ghosts;
+    let Some(level) = &ghosts.recording_level else {
+        return;
+    };
+    //someone joining late starts standing where they joined, as far into the run as the others
+    let elapsed = ghosts
+        .recording
+        .values()
+        .next()
+        .map_or((0.0, 0), |run| (run.time, run.frames.len()));
+    for (transform, slot) in mario.iter() {
+        let position = transform.translation.xy().round().as_ivec2().to_array();
+        let run = ghosts
+            .recording
+            .entry(slot.map_or(0, |slot| slot.0))
+            .or_insert_with(|| GhostRun {
+                level: level.clone(),
+                time: elapsed.0,
+                frames: vec![position; elapsed.1],
+            });
+        run.frames.push(position);
+        run.time += time.delta_secs();
+    }
+}
+
+fn play_ghosts(mut race_ghosts: Query<(&mut RaceGhost, &mut Transform, &mut Visibility)>) {
+    for (mut ghost, mut transform, mut visibility) in race_ghosts.iter_mut() {
+        //stays at the goal once its run is over
+        let Some(&[x, y]) = ghost.run.frames.get(ghost.frame).or(ghost.run.frames.last()) else {
+            continue;
+        };
+        ghost.frame += 1;
+        transform.translation = vec3(x as f32, y as f32, transform.translation.z);
+        *visibility = Visibility::Inherited;
+    }
+}
+
+fn finish_run(complete: On<LevelComplete>, mut ghosts: ResMut<Ghosts>) {
+    ghosts.recording_level = None;
+    //the run of whoever reached the goal
+    let run = ghosts.recording.remove(&complete.player);
+    ghosts.recording.clear();
+    let Some(run) = run else {
+        return;
+    };
+    let faster = ghosts
+        .best
+        .get(&run.level)
+        .is_none_or(|best| run.time < best.time);
+    if faster {
+        info!("new best run of {} in {:.2}s", run.level, run.time);
+        ghosts.best.insert(run.level.clone(), run);
+        if let Err(e) = write_best(&ghosts.best) {
+            warn!("write error {e}");
+        }
+    }
+}
+
+fn export_ghost(export: On<ExportGhost>, mut commands: Commands, ghosts: Res<Ghosts>) {
+    let Some(run) = ghosts.best.get(&export.level) else {
+        warn!("no best run of {}", export.level);
+        commands.trigger(Toast("no best run of this level yet".to_string()));
+        return;
+    };
+    match run.export(&export.path) {
+        Ok(()) => {
+            info!("exported ghost of {} to {}", export.level, export.path);
+            commands.trigger(Toast(format!("ghost exported to {}", export.path)));
+        }
+        Err(e) => {
+            warn!("write error {e}");
+            commands.trigger(Toast("could not export the ghost".to_string()));
+        }
+    }
+}
+
+fn import_ghost(import: On<ImportGhost>, mut commands: Commands, mut ghosts: ResMut<Ghosts>) {
+    match GhostRun::import(&import.path) {
+        Ok(run) => {
+            info!("imported ghost of {} from {}", run.level, import.path);
+            commands.trigger(Toast("ghost imported, it races from the next attempt".to_string()));
+            ghosts.racing = Some(run);
+        }
+        Err(e) => {
+            warn!("could not import ghost {e}");
+            commands.trigger(Toast(format!("nothing to import at {}", import.path)));
+        }
+    }
+}
+
+#[cfg(feature = "ghost_fetch")]
+mod fetch {
+    use super::{FetchGhost, GhostRun, Ghosts};
+    use bevy::prelude::*;
+    use std::error::Error;
+    use std::thread::JoinHandle;
+
+    /// A download running on its own thread, ureq blocks and would stall a task pool thread.
+    #[derive(Component)]
+    pub(super) struct GhostDownload {
+        url: String,
+        thread: Option<JoinHandle<Result<GhostRun, String>>>,
+    }
+
+    fn download(url: &str) -> Result<GhostRun, Box<dyn Error>> {
+        let mut response = ureq::get(url).call()?;
+        let string = response.body_mut().read_to_string()?;
+        Ok(ron::from_str(&string)?)
+    }
+
+    pub(super) fn fetch_ghost(fetch: On<FetchGhost>, mut commands: Commands) {
+        let url = fetch.url.clone();
+        let thread = std::thread::Builder::new()
+            .name("ghost download".to_string())
+            .spawn({
+                let url = url.clone();
+                move || download(&url).map_err(|e| e.to_string())
+            });
+        match thread {
+            Ok(thread) => {
+                commands.spawn((
+                    Name::new("Ghost download"),
+                    GhostDownload {
+                        url,
+                        thread: Some(thread),
+                    },
+                ));
+            }
+            Err(e) => warn!("could not start downloading a ghost: {e}"),
+        }
+    }
+
+    pub(super) fn poll_downloads(
+        mut commands: Commands,
+        mut downloads: Query<(Entity, &mut GhostDownload)>,
+        mut ghosts: ResMut<Ghosts>,
+    ) {
+        for (entity, mut download) in downloads.iter_mut() {
+            if !download.thread.as_ref().is_some_and(|thread| thread.is_finished()) {
+                continue;
+            }
+            let Some(thread) = download.thread.take() else {
+                continue;
+            };
+            commands.entity(entity).despawn();
+            let result = thread
+                .join()
+                .unwrap_or_else(|_| Err("the download thread panicked".to_string()));
+            match result {
+                Ok(run) => {
+                    info!("downloaded ghost of {} from {}", run.level, download.url);
+                    ghosts.racing = Some(run);
+                }
+                Err(e) => warn!("could not download ghost from {}: {e}", download.url),
+            }
+        }
+    }
+}
@@ -0,0 +1,46 @@
+//! Named spawn functions, so data (LDtk fields, spawners, scripts) can spawn things by name.
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+/// Spawns an entity at the given world position and returns it.
+pub type SpawnFn = fn(&mut Commands, Vec3) -> Entity;
+
+#[derive(Resource, Default)]
+pub struct EntityRegistry {
+    spawners: HashMap<String, SpawnFn>,
+}
+
+impl EntityRegistry {
+    pub fn register(&mut self, name: impl Into<String>, spawn: SpawnFn) {
+        let name = name.into();
+        if self.spawners.insert(name.clone(), spawn).is_some() {
+            warn!("spawn function for {name} registered twice, keeping the last one");
+        }
+    }
+
+    /// Makes `name` spawn whatever `existing` spawns.
+    pub fn alias(&mut self, name: impl Into<String>, existing: &str) {
+        let Some(&spawn) = self.spawners.get(existing) else {
+            warn!("can't alias nothing registered as {existing}");
+            return;
+        };
+        self.register(name, spawn);
+    }
+
+    pub fn spawn(&self, name: &str, commands: &mut Commands, position: Vec3) -> Option<Entity> {
+        let Some(spawn) = self.spawners.get(name) else {
+            warn!("nothing registered to spawn {name}");
+            return None;
+        };
+        Some(spawn(commands, position))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.spawners.keys().map(String::as_str)
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<EntityRegistry>();
+}
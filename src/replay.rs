@@ -0,0 +1,178 @@
+//! Player input recorded tick by tick, to play a run back exactly.
+//!
+//! A replay is every player's [`LocomotionDriver`] every fixed tick once the level is ready, kept
+//! apart by [`PlayerSlot`]. Playing one back overwrites the drivers before locomotion reads them,
+//! so whatever the input devices do is ignored until the replay runs out. Started with
+//! `--record-replay` and `--play-replay`, see [`LaunchArgs`].
+
+use crate::launch::LaunchArgs;
+use crate::locomotion::LocomotionDriver;
+use crate::mario::Char;
+use crate::physics::check_grounded;
+use crate::screens::level_ready;
+use crate::split_screen::PlayerSlot;
+use crate::storage;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use ron::ser::PrettyConfig;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct Replay {
+    /// iid of the level it was recorded in
+    pub level: String,
+    /// by [`PlayerSlot`], the player from the level is 0
+    pub players: BTreeMap<usize, ReplayTrack>,
+}
+
+/// The input of one player, from the tick they showed up on.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ReplayTrack {
+    pub start: usize,
+    pub frames: Vec<ReplayFrame>,
+}
+
+/// One fixed tick of a [`LocomotionDriver`].
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize)]
+pub struct ReplayFrame {
+    pub wish_dir: [f32; 2],
+    pub running: bool,
+    pub jump_held: bool,
+    pub jump_requested: bool,
+}
+
+impl From<&LocomotionDriver> for ReplayFrame {
+    fn from(driver: &LocomotionDriver) -> Self {
+        Self {
+            wish_dir: driver.wish_dir.to_array(),
+            running: driver.running,
+            jump_held: driver.jump_held,
+            jump_requested: driver.jump_requested,
+        }
+    }
+}
+
+impl ReplayFrame {
+    fn apply(&self, driver: &mut LocomotionDriver) {
+        driver.wish_dir = Vec2::from_array(self.wish_dir);
+        driver.running = self.running;
+        driver.jump_held = self.jump_held;
+        driver.jump_requested = self.jump_requested;
+    }
+}
+
+impl Replay {
+    /// Ticks until the last player's input runs out.
+    pub fn ticks(&self) -> usize {
+        self.players
+            .values()
+            .map(|track| track.start + track.frames.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let string = ron::ser::to_string_pretty(self, PrettyConfig::default().compact_arrays(true))?;
+        storage::write(path, &string)?;
+        Ok(())
+    }
+
+    pub fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = storage::read(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
+
+#[derive(Resource, Debug)]
+pub enum ReplayMode {
+    Recording { path: String, replay: Replay, frame: usize },
+    Playing { replay: Replay, frame: usize },
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let args = app.world().get_resource::<LaunchArgs>().cloned().unwrap_or_default();
+    if let Some(path) = args.play_replay {
+        match Replay::read(&path) {
+            Ok(replay) => {
+                info!("playing replay {path} of {} ({} ticks)", replay.level, replay.ticks());
+                app.insert_resource(ReplayMode::Playing { replay, frame: 0 });
+            }
+            Err(e) => warn!("could not read replay {path}: {e}"),
+        }
+    } else if let Some(path) = args.record_replay {
+        info!("recording a replay to {path}");
+        app.insert_resource(ReplayMode::Recording {
+            path,
+            replay: Replay::default(),
+            frame: 0,
+        });
+    }
+    app.add_systems(
+        FixedUpdate,
+        drive_replay
+            .before(check_grounded)
+            .run_if(resource_exists::<ReplayMode>.and(level_ready)),
+    )
+    .add_systems(Last, save_replay.run_if(resource_exists::<ReplayMode>));
+}
+
+fn drive_replay(
+    mut commands: Commands,
+    mut mode: ResMut<ReplayMode>,
+    mut drivers: Query<(&mut LocomotionDriver, Option<&PlayerSlot>), With<Char>>,
+    level: Query<&LevelIid>,
+) {
+    match &mut *mode {
+        ReplayMode::Recording { replay, frame, .. } => {
+            if *frame == 0 {
+                replay.level = level.iter().next().map(ToString::to_string).unwrap_or_default();
+            }
+            for (driver, slot) in drivers.iter() {
+                let track = replay
+                    .players
+                    .entry(slot.map_or(0, |slot| slot.0))
+                    .or_insert_with(|| ReplayTrack {
+                        start: *frame,
+                        frames: Vec::new(),
+                    });
+                //left and came back, stand still for the ticks they were gone
+                track.frames.resize(*frame - track.start, ReplayFrame::default());
+                track.frames.push(ReplayFrame::from(driver));
+            }
+            *frame += 1;
+        }
+        ReplayMode::Playing { replay, frame } => {
+            if *frame >= replay.ticks() {
+                info!("replay over after {} ticks", *frame);
+                commands.remove_resource::<ReplayMode>();
+                return;
+            }
+            for (mut driver, slot) in drivers.iter_mut() {
+                let Some(track) = replay.players.get(&slot.map_or(0, |slot| slot.0)) else {
+                    continue;
+                };
+                let next = frame
+                    .checked_sub(track.start)
+                    .and_then(|tick| track.frames.get(tick))
+                    .copied()
+                    .unwrap_or_default();
+                next.apply(&mut driver);
+            }
+            *frame += 1;
+        }
+    }
+}
+
+fn save_replay(mut exits: MessageReader<AppExit>, mode: Res<ReplayMode>) {
+    if exits.read().next().is_none() {
+        return;
+    }
+    if let ReplayMode::Recording { path, replay, .. } = &*mode {
+        match replay.write(path) {
+            Ok(()) => info!("wrote replay of {} ticks to {path}", replay.ticks()),
+            Err(e) => warn!("could not write replay: {e}"),
+        }
+    }
+}
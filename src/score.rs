@@ -0,0 +1,24 @@
+//! Score keeping. Anything that awards points triggers [`AddScore`] at the place it happened.
+
+use bevy::prelude::*;
+
+#[derive(Resource, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Score(pub u64);
+
+#[derive(Event, Clone, Copy, Debug)]
+pub struct AddScore {
+    pub amount: u64,
+    /// world position the points came from
+    pub position: Vec2,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Score>()
+        .init_resource::<Score>()
+        .add_observer(add_score);
+}
+
+fn add_score(add: On<AddScore>, mut score: ResMut<Score>) {
+    score.0 += add.amount;
+}
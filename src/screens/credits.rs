@@ -0,0 +1,128 @@
+//! Scrolling credits, read from `assets/credits.ron`.
+//!
+//! The credits scroll up on their own. Holding jump or confirm scrolls faster, escape or the east
+//! gamepad button skips to the end. Once everything has scrolled past, it goes back to the screen
+//! the credits were opened from.
+
+use super::Screen;
+use crate::time::PauseEvent;
+use bevy::prelude::*;
+use bevy_cobweb_ui::prelude::*;
+use serde::Deserialize;
+use std::error::Error;
+use std::fs;
+
+const CREDITS_PATH: &str = "assets/credits.ron";
+/// logical pixels per second
+const SCROLL_SPEED: f32 = 40.0;
+const FAST_SCROLL: f32 = 5.0;
+
+/// `assets/credits.ron`
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Credits {
+    pub sections: Vec<CreditsSection>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CreditsSection {
+    pub title: String,
+    pub lines: Vec<String>,
+}
+
+impl Credits {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
+
+/// Where to go once the credits are over.
+#[derive(Resource, Clone, Copy, Debug)]
+pub struct CreditsReturn(pub Screen);
+
+#[derive(Component)]
+struct CreditsRoot;
+
+#[derive(Component, Default)]
+struct CreditsScroll {
+    scrolled: f32,
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.add_systems(OnEnter(Screen::Credits), spawn_credits)
+        .add_systems(OnExit(Screen::Credits), resume)
+        .add_systems(Update, scroll_credits.run_if(in_state(Screen::Credits)));
+}
+
+/// Opens the credits, coming back to the current screen after.
+pub fn open_credits(
+    mut commands: Commands,
+    screen: Res<State<Screen>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+) {
+    if *screen.get() == Screen::Credits {
+        return;
+    }
+    commands.insert_resource(CreditsReturn(*screen.get()));
+    next_screen.set(Screen::Credits);
+}
+
+fn spawn_credits(mut commands: Commands, mut s: SceneBuilder) {
+    let credits = Credits::read(CREDITS_PATH).unwrap_or_else(|e| {
+        warn!("could not read {CREDITS_PATH}: {e}");
+        Credits::default()
+    });
+    commands.trigger(PauseEvent::Enable);
+    commands
+        .ui_root()
+        .spawn_scene(("ui/main.cob", "credits"), &mut s, |sc| {
+            sc.insert((CreditsRoot, DespawnOnExit(Screen::Credits)));
+            sc.edit("scroll", |sc| {
+                sc.insert(CreditsScroll::default());
+                for section in &credits.sections {
+                    sc.spawn_scene(("ui/main.cob", "credits_heading"), |sc| {
+                        sc.get("text").update_text(section.title.clone());
+                    });
+                    for line in &section.lines {
+                        sc.spawn_scene(("ui/main.cob", "credits_line"), |sc| {
+                            sc.get("text").update_text(line.clone());
+                        });
+                    }
+                }
+            });
+        });
+}
+
+fn scroll_credits(
+    mut scroll: Single<(&mut CreditsScroll, &mut Node, &ComputedNode)>,
+    root: Single<&ComputedNode, (With<CreditsRoot>, Without<CreditsScroll>)>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    back: Option<Res<CreditsReturn>>,
+    mut next_screen: ResMut<NextState<Screen>>,
+    //the game is paused underneath
+    time: Res<Time<Real>>,
+) {
+    let (credits, node, computed) = &mut *scroll;
+    let screen_height = root.size().y * root.inverse_scale_factor();
+    let content_height = computed.size().y * computed.inverse_scale_factor();
+    let skip = keys.just_pressed(KeyCode::Escape)
+        || gamepads.iter().any(|pad| pad.just_pressed(GamepadButton::East));
+    let fast = keys.any_pressed([KeyCode::Space, KeyCode::Enter])
+        || gamepads.iter().any(|pad| pad.pressed(GamepadButton::South));
+    let speed = if fast { SCROLL_SPEED * FAST_SCROLL } else { SCROLL_SPEED };
+    credits.scrolled += speed * time.delta_secs();
+    node.top = Val::Px(screen_height - credits.scrolled);
+    //computed sizes are zero until the first layout
+    let done = content_height > 0.0 && credits.scrolled >= screen_height + content_height;
+    if skip || done {
+        next_screen.set(back.map_or(Screen::Menu, |back| back.0));
+    }
+}
+
+fn resume(mut commands: Commands) {
+    commands.trigger(PauseEvent::Disable);
+    commands.remove_resource::<CreditsReturn>();
+}
@@ -0,0 +1,113 @@
+//! Holds the switch to [`Screen::Game`] until the level is actually playable.
+//!
+//! Physics running before the wall colliders exist drops the player straight through the floor,
+//! so the level counts as ready only once it has been placed, its walls are built and the player
+//! has spawned. A level without any walls is ready as soon as it's placed.
+
+use super::Screen;
+use crate::mario::Char;
+use crate::walls::{Wall, WallCollider};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+/// What's left before one level is playable.
+#[derive(Clone, Copy, Debug, Default, Reflect)]
+pub struct LevelLoad {
+    pub placed: bool,
+    /// also set for levels that finished spawning without any walls
+    pub walls_built: bool,
+}
+
+/// What's left before the loaded levels are playable. Each level is tracked from when it starts
+/// spawning until it despawns, so neighbours loading in don't hold up the level being played.
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct LevelReadiness {
+    /// by level iid
+    pub levels: HashMap<String, LevelLoad>,
+    pub player_spawned: bool,
+}
+
+impl LevelReadiness {
+    pub fn is_ready(&self) -> bool {
+        !self.levels.is_empty()
+            && self
+                .levels
+                .values()
+                .all(|level| level.placed && level.walls_built)
+            && self.player_spawned
+    }
+}
+
+/// Run condition for anything that moves kinematic entities, so nothing falls while the level
+/// is still being built.
+pub fn level_ready(readiness: Res<LevelReadiness>) -> bool {
+    readiness.is_ready()
+}
+
+pub(super) fn plugin(app: &mut App) {
+    app.register_type::<LevelReadiness>()
+        .init_resource::<LevelReadiness>()
+        .add_systems(
+            Update,
+            (
+                track_readiness,
+                enter_game.run_if(in_state(Screen::Load)),
+            )
+                .chain(),
+        );
+}
+
+fn track_readiness(
+    mut level_events: MessageReader<LevelEvent>,
+    new_walls: Query<&ChildOf, Added<WallCollider>>,
+    wall_tiles: Query<&ChildOf, With<Wall>>,
+    layers: Query<&ChildOf, Without<Wall>>,
+    levels: Query<(Entity, &LevelIid)>,
+    player: Query<(), With<Char>>,
+    mut readiness: ResMut<LevelReadiness>,
+) {
+    for event in level_events.read() {
+        match event {
+            LevelEvent::SpawnTriggered(iid) => {
+                readiness.levels.insert(iid.to_string(), LevelLoad::default());
+            }
+            LevelEvent::Transformed(iid) => {
+                //the level is done spawning, one without wall tiles has nothing left to build
+                let has_walls = levels
+                    .iter()
+                    .find(|(_, level_iid)| *level_iid == iid)
+                    .is_some_and(|(level, _)| {
+                        wall_tiles.iter().any(|tile| {
+                            layers
+                                .get(tile.parent())
+                                .is_ok_and(|layer| layer.parent() == level)
+                        })
+                    });
+                let load = readiness.levels.entry(iid.to_string()).or_default();
+                load.placed = true;
+                load.walls_built |= !has_walls;
+            }
+            //a respawning level keeps its entity and starts over instead
+            LevelEvent::Despawned(iid) if !levels.iter().any(|(_, level_iid)| level_iid == iid) => {
+                readiness.levels.remove(&iid.to_string());
+            }
+            _ => {}
+        }
+    }
+    //wall colliders are children of their level
+    for child_of in new_walls.iter() {
+        if let Ok((_, iid)) = levels.get(child_of.parent()) {
+            readiness.levels.entry(iid.to_string()).or_default().walls_built = true;
+        }
+    }
+    readiness.player_spawned = !player.is_empty();
+}
+
+fn enter_game(readiness: Res<LevelReadiness>, mut next_screen: ResMut<NextState<Screen>>) {
+    if readiness.is_ready() {
+        info!("level ready, starting the game");
+        next_screen.set(Screen::Game);
+    }
+}
@@ -1,14 +1,23 @@
+mod credits;
+mod loading;
+
 use bevy::prelude::*;
 
+pub use credits::{open_credits, Credits, CreditsReturn};
+pub use loading::{level_ready, LevelReadiness};
+
 #[derive(Default, States, Clone, Copy, Ord, PartialOrd, PartialEq, Eq, Hash, Debug)]
 pub enum Screen {
     #[default]
     Load,
 
     Menu,
+    /// Playing. Whatever is spawned for the game alone, like ghosts, particles, projectiles,
+    /// the HUD and the cameras, gets `DespawnOnExit(Screen::Game)` so leaving cleans it all up.
     Game,
+    Credits,
 }
 
 pub(crate) fn plugin(app: &mut App) {
-    app.init_state::<Screen>();
+    app.init_state::<Screen>().add_plugins((credits::plugin, loading::plugin));
 }
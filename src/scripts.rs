@@ -0,0 +1,320 @@
+//! Simple entity behaviors written as RON rule lists instead of Rust systems.
+//!
+//! `Scripted` LDtk entities name a script in their `script` field, read from
+//! `assets/scripts/<name>.ron` (or a mod's copy). A script is a list of rules, every frame each rule
+//! whose condition holds runs its actions:
+//!
+//! ```ron
+//! (
+//!     version: 1,
+//!     rules: [
+//!         (when: Always, then: [Patrol(speed: 30.0, distance: 48.0)]),
+//!         (when: All([Every(2.0), PlayerWithin(160.0)]), then: [Shoot(speed: 120.0, damage: 1)]),
+//!         (when: PlayerWithin(24.0), then: [OpenDoors]),
+//!     ],
+//! )
+//! ```
+//!
+//! Entities with a [`KinematicController`] move through it, anything else moves its transform.
+
+use crate::combat::{Hitbox, Team};
+use crate::definitions::{read_definition, Migrate};
+use crate::layers::{RenderLayer, RenderLayerOrder};
+use crate::mario::{nearest_player, Char};
+use crate::mods::mod_override;
+use crate::physics::KinematicController;
+use crate::registry::EntityRegistry;
+use crate::screens::{level_ready, Screen};
+use crate::switch_blocks::SwitchHit;
+use crate::time::PausableSystems;
+use crate::ui::Toast;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Script {
+    pub version: u32,
+    pub rules: Vec<Rule>,
+}
+
+impl Migrate for Script {
+    const VERSION: u32 = 1;
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Rule {
+    pub when: Condition,
+    pub then: Vec<Action>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum Condition {
+    Always,
+    /// once every this many seconds
+    Every(f32),
+    PlayerWithin(f32),
+    PlayerBeyond(f32),
+    /// the frame after any switch was hit
+    SwitchHit,
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub enum Action {
+    /// horizontal speed in pixels per second, negative is left
+    Walk(f32),
+    /// walks back and forth up to `distance` from where it spawned
+    Patrol { speed: f32, distance: f32 },
+    /// turns a walk or patrol towards the player
+    FacePlayer,
+    /// spawns something from the entity registry
+    Spawn { name: String, offset: Vec2 },
+    /// fires a projectile at the player
+    Shoot { speed: f32, damage: u32 },
+    /// opens speed doors as if a switch was hit
+    OpenDoors,
+    Despawn,
+}
+
+/// Runs a [`Script`].
+#[derive(Component, Debug, Clone, Default)]
+pub struct Scripted {
+    pub name: String,
+    pub script: Script,
+    /// seconds since each `Every` condition last fired, in the order they appear
+    timers: Vec<f32>,
+    /// -1 left, 1 right
+    direction: f32,
+    home: Option<Vec2>,
+    switch_hit: bool,
+}
+
+impl Scripted {
+    pub fn load(name: &str) -> Result<Self, String> {
+        let path = format!("scripts/{name}.ron");
+        let path = mod_override(&path)
+            .map(|modded| modded.to_string_lossy().into_owned())
+            .unwrap_or_else(|| format!("assets/{path}"));
+        let script = read_definition::<Script>(&path)?;
+        Ok(Self {
+            name: name.to_string(),
+            script,
+            direction: -1.0,
+            ..default()
+        })
+    }
+}
+
+impl From<&EntityInstance> for Scripted {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let Ok(Some(name)) = entity_instance.get_string_field("script") else {
+            warn!("scripted entity {} has no script", entity_instance.iid);
+            return Self::default();
+        };
+        Self::load(name).unwrap_or_else(|e| {
+            warn!("could not load script {e}");
+            Self::default()
+        })
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct ScriptedBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    scripted: Scripted,
+}
+
+/// Flies in a straight line until it runs out of time.
+#[derive(Component, Debug, Clone, Copy)]
+struct Projectile {
+    velocity: Vec2,
+    lifetime: f32,
+}
+
+const PROJECTILE_LIFETIME: f32 = 3.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_ldtk_entity::<ScriptedBundle>("Scripted")
+        .add_systems(
+            Update,
+            (run_scripts, fly_projectiles)
+                .in_set(PausableSystems)
+                .run_if(level_ready),
+        )
+        .add_observer(remember_switch_hits)
+        .add_observer(report_broken_scripts);
+}
+
+fn remember_switch_hits(_hit: On<SwitchHit>, mut scripted: Query<&mut Scripted>) {
+    for mut scripted in scripted.iter_mut() {
+        scripted.switch_hit = true;
+    }
+}
+
+/// What conditions get to look at.
+struct Context {
+    player_distance: Option<f32>,
+    switch_hit: bool,
+    delta: f32,
+}
+
+/// Checks a condition, `timer` walks through the `Every` timers in order.
+fn check(condition: &Condition, context: &Context, timers: &mut [f32], timer: &mut usize) -> bool {
+    match condition {
+        Condition::Always => true,
+        Condition::Every(seconds) => {
+            let Some(elapsed) = timers.get_mut(*timer) else {
+                return false;
+            };
+            *timer += 1;
+            *elapsed += context.delta;
+            if *elapsed >= *seconds {
+                *elapsed -= *seconds;
+                true
+            } else {
+                false
+            }
+        }
+        Condition::PlayerWithin(distance) => {
+            context.player_distance.is_some_and(|d| d <= *distance)
+        }
+        Condition::PlayerBeyond(distance) => {
+            context.player_distance.is_none_or(|d| d > *distance)
+        }
+        Condition::SwitchHit => context.switch_hit,
+        //every timer ticks, no short circuiting
+        Condition::All(conditions) => conditions
+            .iter()
+            .map(|condition| check(condition, context, timers, timer))
+            .fold(true, |all, ok| all && ok),
+        Condition::Any(conditions) => conditions
+            .iter()
+            .map(|condition| check(condition, context, timers, timer))
+            .fold(false, |any, ok| any || ok),
+        Condition::Not(condition) => !check(condition, context, timers, timer),
+    }
+}
+
+fn count_timers(condition: &Condition) -> usize {
+    match condition {
+        Condition::Every(_) => 1,
+        Condition::All(conditions) | Condition::Any(conditions) => {
+            conditions.iter().map(count_timers).sum()
+        }
+        Condition::Not(condition) => count_timers(condition),
+        _ => 0,
+    }
+}
+
+fn run_scripts(
+    mut commands: Commands,
+    mut scripted: Query<(
+        Entity,
+        &mut Scripted,
+        &mut Transform,
+        Option<&mut KinematicController>,
+    )>,
+    mario: Query<&Transform, (With<Char>, Without<Scripted>)>,
+    registry: Res<EntityRegistry>,
+    time: Res<Time>,
+) {
+    for (entity, mut scripted, mut transform, mut controller) in scripted.iter_mut() {
+        let scripted = &mut *scripted;
+        let position = transform.translation.xy();
+        let player = nearest_player(&mario, position);
+        let home = *scripted.home.get_or_insert(position);
+        let timer_count = scripted.script.rules.iter().map(|rule| count_timers(&rule.when)).sum();
+        scripted.timers.resize(timer_count, 0.0);
+        let context = Context {
+            player_distance: player.map(|player| player.distance(position)),
+            switch_hit: std::mem::take(&mut scripted.switch_hit),
+            delta: time.delta_secs(),
+        };
+        let mut timer = 0;
+        let mut walk = None;
+        for rule in &scripted.script.rules {
+            if !check(&rule.when, &context, &mut scripted.timers, &mut timer) {
+                continue;
+            }
+            for action in &rule.then {
+                match action {
+                    Action::Walk(speed) => walk = Some(*speed),
+                    Action::Patrol { speed, distance } => {
+                        let offset = position.x - home.x;
+                        if offset.abs() >= *distance && offset.signum() == scripted.direction {
+                            scripted.direction = -scripted.direction;
+                        }
+                        walk = Some(speed.abs() * scripted.direction);
+                    }
+                    Action::FacePlayer => {
+                        if let Some(player) = player {
+                            scripted.direction = (player.x - position.x).signum();
+                            walk = walk.map(|speed: f32| speed.abs() * scripted.direction);
+                        }
+                    }
+                    Action::Spawn { name, offset } => {
+                        let at = transform.translation + offset.extend(0.0);
+                        registry.spawn(name, &mut commands, at);
+                    }
+                    Action::Shoot { speed, damage } => {
+                        let Some(player) = player else {
+                            continue;
+                        };
+                        let direction = (player - position).normalize_or(Vec2::X);
+                        commands.spawn((
+                            Name::new("Projectile"),
+                            Projectile {
+                                velocity: direction * *speed,
+                                lifetime: PROJECTILE_LIFETIME,
+                            },
+                            Hitbox::new(Team::Enemy, *damage, Vec2::splat(3.0)),
+                            Sprite::from_color(Color::srgb(0.9, 0.5, 0.1), Vec2::splat(6.0)),
+                            RenderLayer(RenderLayerOrder::Hazards),
+                            Transform::from_translation(position.extend(0.0)),
+                            DespawnOnExit(Screen::Game),
+                        ));
+                    }
+                    Action::OpenDoors => commands.trigger(SwitchHit { switch: entity }),
+                    Action::Despawn => commands.entity(entity).despawn(),
+                }
+            }
+        }
+        let Some(speed) = walk else {
+            continue;
+        };
+        match controller.as_deref_mut() {
+            Some(controller) => controller.velocity.x = speed,
+            None => transform.translation.x += speed * time.delta_secs(),
+        }
+    }
+}
+
+fn fly_projectiles(
+    mut commands: Commands,
+    mut projectiles: Query<(Entity, &mut Projectile, &mut Transform)>,
+    time: Res<Time>,
+) {
+    for (entity, mut projectile, mut transform) in projectiles.iter_mut() {
+        projectile.lifetime -= time.delta_secs();
+        if projectile.lifetime <= 0.0 {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        transform.translation += (projectile.velocity * time.delta_secs()).extend(0.0);
+    }
+}
+
+/// Scripts that failed to load come out empty, say so once the entity shows up.
+fn report_broken_scripts(add: On<Add, Scripted>, scripted: Query<&Scripted>, mut commands: Commands) {
+    if let Ok(scripted) = scripted.get(add.entity)
+        && scripted.script.rules.is_empty()
+    {
+        commands.trigger(Toast(format!("script {} has no rules", scripted.name)));
+    }
+}
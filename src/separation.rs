@@ -0,0 +1,71 @@
+//! Soft collision between kinematic entities of the same team, so crowding enemies drift apart
+//! instead of stacking up inside each other.
+//!
+//! Overlapping [`SoftSeparation`] boxes only push their entities sideways with a gentle
+//! acceleration, nothing gets depenetrated and walls still win through move and slide.
+
+use crate::combat::Team;
+use crate::jump::apply_gravity;
+use crate::physics::{perform_move_and_slide, KinematicController};
+use bevy::prelude::*;
+
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct SoftSeparation {
+    pub team: Team,
+    /// usually a bit bigger than the collider so neighbours start drifting apart before touching
+    pub half_size: Vec2,
+    /// sideways acceleration at full overlap, in pixels per second squared
+    pub strength: f32,
+}
+
+impl SoftSeparation {
+    pub fn new(team: Team, half_size: Vec2) -> Self {
+        Self {
+            team,
+            half_size,
+            strength: 400.0,
+        }
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<SoftSeparation>().add_systems(
+        FixedUpdate,
+        separate
+            .after(apply_gravity)
+            .before(perform_move_and_slide),
+    );
+}
+
+fn separate(
+    mut query: Query<(Entity, &SoftSeparation, &Transform, &mut KinematicController)>,
+    time: Res<Time>,
+) {
+    let mut pushes = Vec::new();
+    for [(a, sep_a, xf_a, _), (b, sep_b, xf_b, _)] in query.iter_combinations() {
+        if sep_a.team != sep_b.team {
+            continue;
+        }
+        let offset = xf_b.translation.xy() - xf_a.translation.xy();
+        let reach = sep_a.half_size + sep_b.half_size;
+        let overlap = reach - offset.abs();
+        if overlap.x <= 0.0 || overlap.y <= 0.0 {
+            continue;
+        }
+        //exactly on top of each other, split them by spawn order
+        let side = if offset.x == 0.0 {
+            if a < b { 1.0 } else { -1.0 }
+        } else {
+            offset.x.signum()
+        };
+        let amount = overlap.x / reach.x * time.delta_secs();
+        pushes.push((a, -side * amount * sep_a.strength));
+        pushes.push((b, side * amount * sep_b.strength));
+    }
+    for (entity, push) in pushes {
+        if let Ok((_, _, _, mut controller)) = query.get_mut(entity) {
+            controller.velocity.x += push;
+        }
+    }
+}
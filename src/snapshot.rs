@@ -0,0 +1,243 @@
+//! Capturing and restoring the gameplay state of the world through reflection.
+//!
+//! Only entities marked [`Snapshotted`] are captured, and of those only the components listed in
+//! [`SnapshotTypes`], plus the listed resources. Entities are matched up again by their LDtk iid
+//! (the player by being the player), so a snapshot survives the level respawning after a death.
+//! Restoring despawns anything spawned after the capture, anything that died since stays dead.
+//!
+//! While the [`SnapshotHistory`] has a capacity, every fixed tick a snapshot is pushed into it,
+//! which [`rewind`] steps back through. Capturing is exclusive and goes through reflection, so the
+//! history starts out empty and [practice mode](crate::practice::PracticeMode) turns it on.
+
+use crate::combat::Hitbox;
+use crate::doors::SpeedDoor;
+use crate::enemies::Spawner;
+use crate::jump::{JumpCharge, JumpTakeoff};
+use crate::locomotion::LocomotionDriver;
+use crate::mario::Char;
+use crate::physics::{Grounded, KinematicController, LastMoveOutput};
+use crate::player_state::{Coins, Health, Inventory, PowerState};
+use crate::score::Score;
+use crate::time::{StopTimer, TimeSince};
+use bevy::prelude::*;
+use bevy::reflect::{PartialReflect, TypeRegistry};
+use bevy_ecs_ldtk::prelude::*;
+use std::any::TypeId;
+use std::collections::VecDeque;
+
+/// Marks an entity whose state is part of a snapshot.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct Snapshotted;
+
+/// The reflected components and resources a snapshot is made of.
+#[derive(Resource, Clone, Debug, Default)]
+pub struct SnapshotTypes {
+    pub components: Vec<TypeId>,
+    pub resources: Vec<TypeId>,
+}
+
+impl SnapshotTypes {
+    pub fn component<T: Component + Reflect>(&mut self) -> &mut Self {
+        self.components.push(TypeId::of::<T>());
+        self
+    }
+
+    pub fn resource<T: Resource + Reflect>(&mut self) -> &mut Self {
+        self.resources.push(TypeId::of::<T>());
+        self
+    }
+}
+
+/// How an entity is found again when restoring.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SnapshotKey {
+    Player,
+    Iid(String),
+    Entity(Entity),
+}
+
+impl SnapshotKey {
+    fn of(entity: EntityRef) -> Self {
+        if entity.contains::<Char>() {
+            Self::Player
+        } else if let Some(iid) = entity.get::<EntityIid>() {
+            Self::Iid(iid.to_string())
+        } else {
+            Self::Entity(entity.id())
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Snapshot {
+    pub entities: Vec<(SnapshotKey, Vec<Box<dyn PartialReflect>>)>,
+    pub resources: Vec<Box<dyn PartialReflect>>,
+}
+
+/// The last few seconds of fixed ticks, newest at the back.
+#[derive(Resource, Default)]
+pub struct SnapshotHistory {
+    pub frames: VecDeque<Snapshot>,
+    /// how many ticks are kept, zero stops recording
+    pub capacity: usize,
+}
+
+impl SnapshotHistory {
+    /// Five seconds at the default fixed rate.
+    pub const REWIND_TICKS: usize = 320;
+
+    /// Starts or stops recording, dropping what was kept when stopping.
+    pub fn set_recording(&mut self, recording: bool) {
+        self.capacity = if recording { Self::REWIND_TICKS } else { 0 };
+        if !recording {
+            self.frames.clear();
+        }
+    }
+}
+
+/// Run condition for the history having room to record into.
+pub fn recording_history(history: Res<SnapshotHistory>) -> bool {
+    history.capacity > 0
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    let mut types = SnapshotTypes::default();
+    types
+        .component::<Transform>()
+        .component::<KinematicController>()
+        .component::<LocomotionDriver>()
+        .component::<JumpCharge>()
+        .component::<JumpTakeoff>()
+        .component::<TimeSince<Grounded>>()
+        .component::<PowerState>()
+        .component::<Health>()
+        .component::<Coins>()
+        .component::<Inventory>()
+        .component::<Hitbox>()
+        .component::<Spawner>()
+        .component::<SpeedDoor>()
+        .resource::<Score>()
+        .resource::<StopTimer>();
+    app.register_type::<Snapshotted>()
+        .insert_resource(types)
+        .init_resource::<SnapshotHistory>()
+        .add_systems(FixedPostUpdate, record_history.run_if(recording_history))
+        .add_observer(snapshot_player);
+}
+
+fn snapshot_player(add: On<Add, Char>, mut commands: Commands) {
+    commands.entity(add.entity).insert(Snapshotted);
+}
+
+fn record_history(world: &mut World) {
+    let snapshot = Snapshot::capture(world);
+    let mut history = world.resource_mut::<SnapshotHistory>();
+    while history.frames.len() >= history.capacity {
+        history.frames.pop_front();
+    }
+    history.frames.push_back(snapshot);
+}
+
+/// Steps back `ticks` fixed ticks, or as far as the history goes. Returns how far it went.
+pub fn rewind(world: &mut World, ticks: usize) -> usize {
+    world.resource_scope(|world, mut history: Mut<SnapshotHistory>| {
+        //the newest frame is where the world already is
+        let ticks = ticks.min(history.frames.len().saturating_sub(1));
+        if ticks == 0 {
+            return 0;
+        }
+        let keep = history.frames.len() - ticks;
+        history.frames.truncate(keep);
+        if let Some(snapshot) = history.frames.back() {
+            snapshot.restore(world);
+        }
+        ticks
+    })
+}
+
+fn insert_all(
+    entity: &mut EntityWorldMut,
+    components: &[Box<dyn PartialReflect>],
+    registry: &TypeRegistry,
+) {
+    for component in components {
+        let Some(reflect) = component
+            .get_represented_type_info()
+            .and_then(|info| registry.get_type_data::<ReflectComponent>(info.type_id()))
+        else {
+            continue;
+        };
+        reflect.insert(entity, component.as_ref(), registry);
+    }
+}
+
+impl Snapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let types = world.resource::<SnapshotTypes>().clone();
+        let mut snapshotted = world.query_filtered::<EntityRef, With<Snapshotted>>();
+        let mut snapshot = Self::default();
+        for entity in snapshotted.iter(world) {
+            let components = types
+                .components
+                .iter()
+                .filter_map(|type_id| {
+                    let reflect = registry.get_type_data::<ReflectComponent>(*type_id)?;
+                    Some(reflect.reflect(entity)?.to_dynamic())
+                })
+                .collect();
+            snapshot.entities.push((SnapshotKey::of(entity), components));
+        }
+        snapshot.resources = types
+            .resources
+            .iter()
+            .filter_map(|type_id| {
+                let reflect = registry.get_type_data::<ReflectResource>(*type_id)?;
+                Some(reflect.reflect(&*world).ok()?.to_dynamic())
+            })
+            .collect();
+        snapshot
+    }
+
+    pub fn restore(&self, world: &mut World) {
+        let registry = world.resource::<AppTypeRegistry>().clone();
+        let registry = registry.read();
+        let mut snapshotted = world.query_filtered::<EntityRef, With<Snapshotted>>();
+        let current = snapshotted
+            .iter(world)
+            .map(|entity| (SnapshotKey::of(entity), entity.id()))
+            .collect::<Vec<_>>();
+        for (key, entity) in &current {
+            //spawned after the capture, might already be gone with whatever spawned it
+            if !self.entities.iter().any(|(saved, _)| saved == key)
+                && let Ok(entity) = world.get_entity_mut(*entity)
+            {
+                entity.despawn();
+            }
+        }
+        for (key, components) in &self.entities {
+            let Some((_, entity)) = current.iter().find(|(current, _)| current == key) else {
+                continue;
+            };
+            let Ok(mut entity) = world.get_entity_mut(*entity) else {
+                continue;
+            };
+            insert_all(&mut entity, components, &registry);
+            //the last solve is from before the jump back
+            if let Some(mut last_output) = entity.get_mut::<LastMoveOutput>() {
+                last_output.0 = None;
+            }
+        }
+        for resource in &self.resources {
+            let Some(reflect) = resource
+                .get_represented_type_info()
+                .and_then(|info| registry.get_type_data::<ReflectResource>(info.type_id()))
+            else {
+                continue;
+            };
+            reflect.insert(world, resource.as_ref(), &registry);
+        }
+    }
+}
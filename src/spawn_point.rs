@@ -0,0 +1,105 @@
+//! Spawning the player from a `SpawnPoint` entity instead of a player entity in each level.
+//!
+//! The player itself comes from the `Player` prefab in the [`EntityRegistry`], so it's the same
+//! in every level. Levels that still embed a `Char` entity keep working, a spawn point only spawns
+//! a player when there isn't one already.
+//!
+//! Players are `Worldly` and outlive a level respawn, [`ReturnToStart`] puts them back.
+
+use crate::mario::{spawn_player, Char};
+use crate::physics::{KinematicController, LastMoveOutput};
+use crate::registry::EntityRegistry;
+use crate::split_screen::{PlayerSlot, JOIN_OFFSET};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use char_controller::prelude::ContactCache;
+
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+pub struct SpawnPoint;
+
+/// Where a player first appeared, used by [`ReturnToStart`] in levels without a spawn point.
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct PlayerStart(pub Vec2);
+
+/// Moves every player back to the spawn point, or where they first appeared, and stops them.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct ReturnToStart;
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct SpawnPointBundle {
+    spawn_point: SpawnPoint,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.world_mut()
+        .get_resource_or_init::<EntityRegistry>()
+        .register("Player", spawn_player);
+    app.register_ldtk_entity::<SpawnPointBundle>("SpawnPoint")
+        .add_systems(Update, spawn_at_spawn_point)
+        .add_observer(record_start)
+        .add_observer(return_to_start);
+}
+
+fn record_start(add: On<Add, Char>, mut commands: Commands, transforms: Query<&Transform>) {
+    if let Ok(transform) = transforms.get(add.entity) {
+        commands
+            .entity(add.entity)
+            .insert(PlayerStart(transform.translation.xy()));
+    }
+}
+
+fn return_to_start(
+    _return: On<ReturnToStart>,
+    spawn_points: Query<&GlobalTransform, With<SpawnPoint>>,
+    mut players: Query<
+        (
+            &mut Transform,
+            &mut KinematicController,
+            &mut LastMoveOutput,
+            &mut ContactCache,
+            Option<&PlayerSlot>,
+            Option<&PlayerStart>,
+        ),
+        With<Char>,
+    >,
+) {
+    let spawn = spawn_points.iter().next().map(|xf| xf.translation().xy());
+    for (mut transform, mut controller, mut last_output, mut cache, slot, start) in
+        players.iter_mut()
+    {
+        let offset = JOIN_OFFSET * slot.map_or(0, |slot| slot.0) as f32;
+        let Some(position) = spawn.map(|spawn| spawn + offset).or(start.map(|start| start.0))
+        else {
+            continue;
+        };
+        transform.translation = position.extend(transform.translation.z);
+        controller.velocity = Vec2::ZERO;
+        last_output.0 = None;
+        cache.clear();
+    }
+}
+
+fn spawn_at_spawn_point(
+    mut commands: Commands,
+    mut level_events: MessageReader<LevelEvent>,
+    spawn_points: Query<&GlobalTransform, With<SpawnPoint>>,
+    mario: Query<(), With<Char>>,
+    registry: Res<EntityRegistry>,
+) {
+    //spawn points only have their world position once the level is placed
+    if !level_events
+        .read()
+        .any(|event| matches!(event, LevelEvent::Transformed(_)))
+        || !mario.is_empty()
+    {
+        return;
+    }
+    let Some(xf) = spawn_points.iter().next() else {
+        return;
+    };
+    //z comes from the player's render layer
+    let position = xf.translation().xy().extend(0.0);
+    info!("spawning the player at {position}");
+    registry.spawn("Player", &mut commands, position);
+}
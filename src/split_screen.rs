@@ -0,0 +1,141 @@
+//! Local co-op on one screen.
+//!
+//! Pressing start on a gamepad that isn't playing yet spawns a second player next to the first,
+//! driven by that gamepad alone with [`InputProfile::gamepad`]. The first player keeps the
+//! keyboard and any other gamepad. Every player gets a [`PlayerCamera`] of its own with its own
+//! clamps, so once there's more than one they're laid out side by side, and the HUD moves to a
+//! camera of its own covering the whole window so it stays anchored to the window rather than to
+//! one player's half.
+
+use crate::camera::PlayerCamera;
+use crate::input::{InputProfile, InputSettings};
+use crate::mario::{Char, CharBundle, ColliderBundle};
+use crate::physics::KinematicController;
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::Viewport;
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_enhanced_input::prelude::*;
+
+pub const MAX_PLAYERS: usize = 2;
+const JOIN_BUTTON: GamepadButton = GamepadButton::Start;
+/// Where a joining player shows up, relative to the first.
+pub const JOIN_OFFSET: Vec2 = vec2(16.0, 0.0);
+/// Definitions of joining players are read from this entity's files.
+const PLAYER_IDENTIFIER: &str = "Char";
+
+/// Which player this is, counting from 0. The player from the level has none and counts as 0.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub struct PlayerSlot(pub usize);
+
+/// Draws the UI over the whole window while the screen is split.
+#[derive(Component)]
+struct HudCamera;
+
+impl PlayerSlot {
+    /// The bindings of this player, only the first one uses the configured profile.
+    pub fn profile(slot: Option<&Self>, settings: &InputSettings) -> InputProfile {
+        match slot {
+            Some(slot) if slot.0 > 0 => InputProfile::gamepad(),
+            _ => settings.profile(),
+        }
+    }
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<PlayerSlot>()
+        .add_systems(Update, (join_players, layout_viewports).chain());
+}
+
+fn join_players(
+    mut commands: Commands,
+    gamepads: Query<(Entity, &Gamepad)>,
+    players: Query<(Entity, &Transform, &Sprite, Option<&GamepadDevice>), With<Char>>,
+) {
+    let count = players.iter().count();
+    if count >= MAX_PLAYERS {
+        return;
+    }
+    let Some((pad, _)) = gamepads.iter().find(|(_, gamepad)| gamepad.just_pressed(JOIN_BUTTON))
+    else {
+        return;
+    };
+    let playing = players
+        .iter()
+        .any(|(.., device)| matches!(device, Some(GamepadDevice::Single(used)) if *used == pad));
+    if playing {
+        return;
+    }
+    let Some((first, transform, sprite, _)) = players.iter().next() else {
+        return;
+    };
+    //the first player keeps some other pad if there is one, the keyboard either way
+    let other = gamepads.iter().map(|(entity, _)| entity).find(|&entity| entity != pad);
+    commands
+        .entity(first)
+        .insert(other.map_or(GamepadDevice::None, GamepadDevice::Single));
+    info!("player {} joined with gamepad {pad}", count + 1);
+    commands.spawn((
+        Name::new(format!("Player {}", count + 1)),
+        PlayerSlot(count),
+        CharBundle::load(PLAYER_IDENTIFIER),
+        ColliderBundle::load(PLAYER_IDENTIFIER),
+        KinematicController::default(),
+        sprite.clone(),
+        Transform::from_translation(transform.translation + JOIN_OFFSET.extend(0.0)),
+        Worldly::default(),
+        GamepadDevice::Single(pad),
+    ));
+}
+
+/// Splits the window between the player cameras, only touching them when the layout changes.
+fn layout_viewports(
+    mut commands: Commands,
+    window: Single<&Window, With<PrimaryWindow>>,
+    mut cameras: Query<(&PlayerCamera, &mut Camera)>,
+    hud: Query<Entity, With<HudCamera>>,
+) {
+    let mut cameras = cameras.iter_mut().collect::<Vec<_>>();
+    cameras.sort_by_key(|(slot, _)| slot.0);
+    let split = cameras.len() > 1;
+    let size = window.physical_size();
+    let width = size.x / cameras.len().max(1) as u32;
+    for (index, (_, camera)) in cameras.iter_mut().enumerate() {
+        let wanted = split.then(|| (uvec2(width * index as u32, 0), uvec2(width, size.y)));
+        let current = camera
+            .viewport
+            .as_ref()
+            .map(|viewport| (viewport.physical_position, viewport.physical_size));
+        if current != wanted {
+            camera.viewport = wanted.map(|(physical_position, physical_size)| Viewport {
+                physical_position,
+                physical_size,
+                ..default()
+            });
+        }
+        if camera.order != index as isize {
+            camera.order = index as isize;
+        }
+    }
+    match (split, hud.iter().next()) {
+        (true, None) => {
+            commands.spawn((
+                Name::new("Hud camera"),
+                HudCamera,
+                Camera2d,
+                Camera {
+                    order: MAX_PLAYERS as isize,
+                    clear_color: ClearColorConfig::None,
+                    ..default()
+                },
+                //nothing but the UI
+                RenderLayers::none(),
+                IsDefaultUiCamera,
+            ));
+        }
+        (false, Some(hud)) => commands.entity(hud).despawn(),
+        _ => {}
+    }
+}
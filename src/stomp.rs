@@ -0,0 +1,105 @@
+//! Stomping enemies: landing on one kills it and bounces the player back up.
+//!
+//! Stomps chain into a combo until the player touches the ground again, each stomp in a combo is
+//! worth twice the last and plays its sound a little higher.
+
+use crate::audio::PlaySound;
+use crate::combat::{HitLanded, Hitbox, Team};
+use crate::enemies::{DamageEnemy, Enemy};
+use crate::locomotion::LocomotionDriver;
+use crate::mario::{Char, StompStats};
+use crate::physics::{perform_move_and_slide, Grounded, KinematicController};
+use crate::score::AddScore;
+use crate::time::{update_time_since, TimeSince};
+use bevy::prelude::*;
+
+/// Stomps since the player last stood on the ground.
+#[derive(Component, Clone, Copy, Debug, Default, Reflect)]
+#[reflect(Component)]
+pub struct StompCombo(pub u32);
+
+const STOMP_SOUND: &str = "stomp";
+/// How much higher each stomp in a combo sounds.
+const PITCH_STEP: f32 = 0.12;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<StompCombo>()
+        .add_systems(
+            FixedUpdate,
+            reset_combo
+                .after(update_time_since::<Grounded>)
+                .before(perform_move_and_slide),
+        )
+        .add_observer(give_feet)
+        .add_observer(stomp);
+}
+
+/// Whether something falling at `velocity` from `position` lands on top of whatever is at `target`.
+pub fn is_stomp(position: Vec2, velocity: Vec2, target: Vec2) -> bool {
+    velocity.y < 0.0 && position.y > target.y
+}
+
+fn give_feet(add: On<Add, Char>, mut commands: Commands) {
+    commands.entity(add.entity).insert((
+        StompCombo::default(),
+        Hitbox::new(Team::Player, 1, vec2(8.0, 3.0)).with_offset(vec2(0.0, -8.0)),
+    ));
+}
+
+fn reset_combo(mut query: Query<(&mut StompCombo, &TimeSince<Grounded>)>) {
+    for (mut combo, time_since) in query.iter_mut() {
+        if time_since.time == 0.0 && combo.0 != 0 {
+            combo.0 = 0;
+        }
+    }
+}
+
+fn stomp(
+    hit: On<HitLanded>,
+    mut commands: Commands,
+    mut mario: Query<
+        (
+            &Transform,
+            &mut KinematicController,
+            &mut StompCombo,
+            &StompStats,
+            Option<&LocomotionDriver>,
+        ),
+        With<Char>,
+    >,
+    enemies: Query<&GlobalTransform, With<Enemy>>,
+) {
+    let Ok((transform, mut controller, mut combo, stats, driver)) = mario.get_mut(hit.attacker)
+    else {
+        return;
+    };
+    let Ok(enemy) = enemies.get(hit.target) else {
+        return;
+    };
+    let position = transform.translation.xy();
+    if !is_stomp(position, controller.velocity, enemy.translation().xy()) {
+        return;
+    }
+    commands.trigger(DamageEnemy {
+        entity: hit.target,
+        damage: hit.damage,
+    });
+
+    let held = driver.is_some_and(|driver| driver.jump_held);
+    controller.velocity.y = if held {
+        stats.held_bounce_velocity
+    } else {
+        stats.bounce_velocity
+    };
+
+    combo.0 += 1;
+    let amount = (stats.base_score << (combo.0 - 1).min(16)).min(stats.max_score);
+    commands.trigger(AddScore {
+        amount,
+        position: enemy.translation().xy(),
+    });
+    commands.trigger(
+        PlaySound::new(STOMP_SOUND).with_speed(1.0 + PITCH_STEP * (combo.0 - 1) as f32),
+    );
+    info!("stomp x{} for {amount}", combo.0);
+}
@@ -0,0 +1,41 @@
+//! Reading and writing player files: settings, exported ghosts.
+//!
+//! Natively these are plain files. The browser has no file system to write to, so on wasm the
+//! same paths are keys in `localStorage` instead.
+
+use std::error::Error;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn read(path: &str) -> Result<String, Box<dyn Error>> {
+    Ok(std::fs::read_to_string(path)?)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn write(path: &str, contents: &str) -> Result<(), Box<dyn Error>> {
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Result<web_sys::Storage, Box<dyn Error>> {
+    web_sys::window()
+        .ok_or("no window")?
+        .local_storage()
+        .map_err(|e| format!("{e:?}"))?
+        .ok_or_else(|| "local storage is disabled".into())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn read(path: &str) -> Result<String, Box<dyn Error>> {
+    local_storage()?
+        .get_item(path)
+        .map_err(|e| format!("{e:?}"))?
+        .ok_or_else(|| format!("nothing saved at {path}").into())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn write(path: &str, contents: &str) -> Result<(), Box<dyn Error>> {
+    local_storage()?
+        .set_item(path, contents)
+        .map_err(|e| format!("{e:?}").into())
+}
@@ -0,0 +1,226 @@
+//! Red/blue switch blocks: hitting a switch swaps which group of blocks is solid,
+//! optionally swapping back after the switch's duration runs out.
+//!
+//! Blocks are int grid values 3 (red) and 4 (blue), switches are `Switch` entities
+//! with an optional `duration` float field (0 or missing is permanent).
+
+use crate::effects::{Effect, EffectRequest};
+use crate::physics::ControllerHit;
+use crate::time::PausableSystems;
+use crate::walls::{level_grid, merge_tiles, tiles_by_level};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use bevy_ecs_tilemap::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default, Reflect)]
+pub enum SwitchColor {
+    #[default]
+    Red,
+    Blue,
+}
+
+impl SwitchColor {
+    pub fn other(self) -> Self {
+        match self {
+            SwitchColor::Red => SwitchColor::Blue,
+            SwitchColor::Blue => SwitchColor::Red,
+        }
+    }
+}
+
+/// A single switch block tile.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct SwitchBlock(pub SwitchColor);
+
+impl From<IntGridCell> for SwitchBlock {
+    fn from(cell: IntGridCell) -> Self {
+        match cell.value {
+            4 => SwitchBlock(SwitchColor::Blue),
+            _ => SwitchBlock(SwitchColor::Red),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct SwitchBlockBundle {
+    #[from_int_grid_cell]
+    block: SwitchBlock,
+}
+
+/// A merged collider covering switch blocks of one color.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct SwitchBlockCollider(pub SwitchColor);
+
+#[derive(Component, Clone, Debug, Default)]
+pub struct Switch {
+    pub duration: Option<f32>,
+    /// virtual time of the last touch, a switch only flips on a fresh touch
+    last_touched: f32,
+}
+
+impl From<&EntityInstance> for Switch {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            duration: entity_instance
+                .get_float_field("duration")
+                .ok()
+                .copied()
+                .filter(|&duration| duration > 0.0),
+            last_touched: f32::NEG_INFINITY,
+        }
+    }
+}
+
+#[derive(Bundle, Default)]
+pub struct SwitchBody {
+    collider: Collider,
+    rb: RigidBody,
+}
+
+impl From<&EntityInstance> for SwitchBody {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            collider: Collider::rectangle(
+                entity_instance.width as f32,
+                entity_instance.height as f32,
+            ),
+            rb: RigidBody::Static,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct SwitchBundle {
+    #[sprite_sheet]
+    sprite_sheet: Sprite,
+    #[from_entity_instance]
+    switch: Switch,
+    #[from_entity_instance]
+    body: SwitchBody,
+}
+
+/// Triggered whenever a switch flips the blocks.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SwitchHit {
+    pub switch: Entity,
+}
+
+/// Which color is currently solid, and when it swaps back.
+#[derive(Resource, Debug, Default)]
+pub struct SwitchState {
+    pub solid: SwitchColor,
+    pub timer: Option<Timer>,
+}
+
+/// Touches closer together than this count as one continuous touch.
+const RETOUCH_GAP: f32 = 0.2;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<SwitchState>()
+        .register_ldtk_int_cell::<SwitchBlockBundle>(3) //red
+        .register_ldtk_int_cell::<SwitchBlockBundle>(4) //blue
+        .register_ldtk_entity::<SwitchBundle>("Switch")
+        .add_systems(
+            Update,
+            (
+                spawn_switch_block_collision,
+                tick_switch_timer.in_set(PausableSystems),
+                apply_switch_state.run_if(resource_changed::<SwitchState>),
+            )
+                .chain(),
+        )
+        .add_observer(hit_switch);
+}
+
+fn spawn_switch_block_collision(
+    mut commands: Commands,
+    block_query: Query<(&GridCoords, &ChildOf, &SwitchBlock), Added<SwitchBlock>>,
+    parent_query: Query<&ChildOf, Without<SwitchBlock>>,
+    level_query: Query<(Entity, &LevelIid)>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut state: ResMut<SwitchState>,
+) {
+    if block_query.is_empty() {
+        return;
+    }
+    for color in [SwitchColor::Red, SwitchColor::Blue] {
+        let tiles = block_query
+            .iter()
+            .filter(|(_, _, block)| block.0 == color)
+            .map(|(coords, child_of, _)| (coords, child_of));
+        let level_to_blocks = tiles_by_level(tiles, &parent_query);
+        for (level_entity, level_iid) in level_query.iter() {
+            let Some(blocks) = level_to_blocks.get(&level_entity) else {
+                continue;
+            };
+            let grid = level_grid(level_iid, &ldtk_projects, &ldtk_project_assets);
+            commands.entity(level_entity).with_children(|level| {
+                for rect in merge_tiles(blocks, grid.width, grid.height) {
+                    level.spawn((rect.collider(grid.grid_size), SwitchBlockCollider(color)));
+                }
+            });
+        }
+    }
+    //new blocks start out in the right state
+    state.set_changed();
+}
+
+fn hit_switch(
+    hit: On<ControllerHit>,
+    mut commands: Commands,
+    mut switches: Query<&mut Switch>,
+    mut state: ResMut<SwitchState>,
+    time: Res<Time<Virtual>>,
+) {
+    let Ok(mut switch) = switches.get_mut(hit.other) else {
+        return;
+    };
+    let now = time.elapsed_secs();
+    let fresh = now - switch.last_touched > RETOUCH_GAP;
+    switch.last_touched = now;
+    if !fresh {
+        return;
+    }
+    state.solid = state.solid.other();
+    state.timer = switch
+        .duration
+        .map(|duration| Timer::from_seconds(duration, TimerMode::Once));
+    info!("switch hit, {:?} blocks are solid", state.solid);
+    commands.trigger(EffectRequest::new(hit.other, Effect::pulse()));
+    commands.trigger(SwitchHit { switch: hit.other });
+}
+
+fn tick_switch_timer(mut state: ResMut<SwitchState>, time: Res<Time>) {
+    //don't trip change detection every frame
+    let Some(timer) = state.bypass_change_detection().timer.as_mut() else {
+        return;
+    };
+    if timer.tick(time.delta()).just_finished() {
+        state.solid = state.solid.other();
+        state.timer = None;
+    }
+}
+
+fn apply_switch_state(
+    mut commands: Commands,
+    state: Res<SwitchState>,
+    colliders: Query<(Entity, &SwitchBlockCollider)>,
+    mut tiles: Query<(&SwitchBlock, &mut TileColor)>,
+) {
+    for (entity, collider) in colliders.iter() {
+        if collider.0 == state.solid {
+            commands.entity(entity).remove::<ColliderDisabled>();
+        } else {
+            commands.entity(entity).insert(ColliderDisabled);
+        }
+    }
+    for (block, mut color) in tiles.iter_mut() {
+        color.0 = if block.0 == state.solid {
+            Color::WHITE
+        } else {
+            Color::srgba(1.0, 1.0, 1.0, 0.3)
+        };
+    }
+}
@@ -1,4 +1,4 @@
-use crate::physics::Grounded;
+use crate::physics::{Grounded, Walled};
 use bevy::prelude::*;
 use serde::Deserialize;
 use std::marker::PhantomData;
@@ -48,6 +48,11 @@ impl StopTimer {
     pub fn is_finished(&self) -> bool {
         self.timer.is_finished()
     }
+
+    /// The game is frozen by this timer right now, a hitstop.
+    pub fn is_active(&self) -> bool {
+        !self.paused && !self.timer.is_finished()
+    }
     pub fn just_finished(&self) -> bool {
         self.timer.just_finished()
     }
@@ -66,6 +71,17 @@ pub enum TimerEvent {
     Pause,
     Unpause,
 }
+/// How fast the game runs when it isn't paused, 1 is normal speed.
+#[derive(Resource, Clone, Copy, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct GameSpeed(pub f32);
+
+impl Default for GameSpeed {
+    fn default() -> Self {
+        GameSpeed(1.0)
+    }
+}
+
 #[derive(Event)]
 pub enum PauseEvent {
     Toggle,
@@ -77,18 +93,30 @@ pub(crate) fn plugin(app: &mut App) {
         timer: Timer::from_seconds(0.0, TimerMode::Once),
         paused: false,
     })
+    .init_resource::<GameSpeed>()
+    .add_systems(Startup, apply_game_speed)
     .add_systems(Update, tick_pause_timer)
     .add_observer(timer_events)
     .register_type::<StopTimer>()
+    .register_type::<GameSpeed>()
     .register_type::<TimeSince<Grounded>>()
+    .register_type::<TimeSince<Walled>>()
     .add_observer(handle_pause_event);
 }
 
-fn handle_pause_event(obs: On<PauseEvent>, mut virtual_time: ResMut<Time<Virtual>>) {
+fn apply_game_speed(speed: Res<GameSpeed>, mut virtual_time: ResMut<Time<Virtual>>) {
+    virtual_time.set_relative_speed(speed.0);
+}
+
+fn handle_pause_event(
+    obs: On<PauseEvent>,
+    speed: Res<GameSpeed>,
+    mut virtual_time: ResMut<Time<Virtual>>,
+) {
     match obs.event() {
         PauseEvent::Toggle => {
             let speed = if virtual_time.relative_speed() == 0.0 {
-                1.0
+                speed.0
             } else {
                 0.0
             };
@@ -98,7 +126,7 @@ fn handle_pause_event(obs: On<PauseEvent>, mut virtual_time: ResMut<Time<Virtual
             virtual_time.set_relative_speed(0.0);
         }
         PauseEvent::Disable => {
-            virtual_time.set_relative_speed(1.0);
+            virtual_time.set_relative_speed(speed.0);
         }
     }
 }
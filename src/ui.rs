@@ -1,14 +1,132 @@
+use crate::audio::{PlaySound, SoundBus};
+use crate::glyphs::InputPrompt;
+use crate::input::{
+    ExportInputProfile, ImportInputProfile, InputSettings, SwitchInputProfile, IMPORTED_PROFILE,
+    SHARED_PROFILE_PATH,
+};
+use crate::launch::show_menu;
+use crate::levels::{Campaign, LevelProgress, SelectLevel};
+use crate::player_state::NewGame;
+use crate::race::StartRace;
+use crate::race_ghosts::{ExportGhost, ImportGhost, SHARED_GHOST_PATH};
+use crate::screens::open_credits;
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use bevy_cobweb_ui::prelude::*;
+use bevy_ecs_ldtk::prelude::LevelIid;
 
 #[derive(Component, Default, PartialEq, Reflect)]
 struct MainInterface;
 
+/// Shows a short message in the corner of the screen for a few seconds.
+#[derive(Event, Clone, Debug)]
+pub struct Toast(pub String);
+
+/// Menu feedback sounds, played on the UI bus.
+#[derive(Event, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UiSound {
+    Hover,
+    Press,
+    /// leaving a menu
+    Back,
+    /// something can't be done
+    Error,
+}
+
+impl UiSound {
+    /// Name in the sound library.
+    fn sound_name(self) -> &'static str {
+        match self {
+            UiSound::Hover => "ui_hover",
+            UiSound::Press => "ui_press",
+            UiSound::Back => "ui_back",
+            UiSound::Error => "ui_error",
+        }
+    }
+}
+
+#[derive(Component)]
+struct ToastArea;
+
+#[derive(Component)]
+struct ToastTimer(Timer);
+
+const TOAST_SECONDS: f32 = 4.0;
+
 pub(crate) fn plugin(app: &mut App) {
-    app.add_systems(OnEnter(LoadState::Done), build_ui)
+    app.add_systems(
+        OnEnter(LoadState::Done),
+        (
+            build_ui.run_if(show_menu),
+            spawn_respawn_button.run_if(not(show_menu)),
+            spawn_toast_area,
+        ),
+    )
+        .add_systems(Update, (expire_toasts, interaction_sounds))
+        .add_observer(show_toast)
+        .add_observer(play_ui_sound)
         .register_component_type::<MainInterface>();
 }
+
+fn spawn_toast_area(mut c: Commands, mut s: SceneBuilder) {
+    c.ui_root()
+        .spawn_scene(("ui/main.cob", "toast_area"), &mut s, |sc| {
+            sc.insert(ToastArea);
+        });
+}
+
+fn show_toast(
+    toast: On<Toast>,
+    mut c: Commands,
+    mut s: SceneBuilder,
+    area: Single<Entity, With<ToastArea>>,
+) {
+    info!("toast: {}", toast.0);
+    c.ui_builder(*area)
+        .spawn_scene(("ui/main.cob", "toast"), &mut s, |sc| {
+            sc.insert(ToastTimer(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once)));
+            sc.get("text").update_text(toast.0.clone());
+        });
+}
+
+fn expire_toasts(
+    mut commands: Commands,
+    mut toasts: Query<(Entity, &mut ToastTimer)>,
+    time: Res<Time<Real>>,
+) {
+    for (e, mut timer) in toasts.iter_mut() {
+        if timer.0.tick(time.delta()).just_finished() {
+            commands.entity(e).despawn();
+        }
+    }
+}
+fn play_ui_sound(sound: On<UiSound>, mut commands: Commands) {
+    commands.trigger(PlaySound::new(sound.sound_name()).on_bus(SoundBus::Ui));
+}
+
+/// Anything interactive in the UI sounds when hovered and pressed.
+fn interaction_sounds(
+    mut commands: Commands,
+    interactions: Query<(Entity, &Interaction), (Changed<Interaction>, With<Node>)>,
+    mut last: Local<HashMap<Entity, Interaction>>,
+) {
+    for (entity, interaction) in interactions.iter() {
+        let before = match interaction {
+            Interaction::None => last.remove(&entity),
+            _ => last.insert(entity, *interaction),
+        };
+        match interaction {
+            //letting go of a press hovers again, that's not a new hover
+            Interaction::Hovered if before != Some(Interaction::Pressed) => {
+                commands.trigger(UiSound::Hover)
+            }
+            Interaction::Pressed => commands.trigger(UiSound::Press),
+            _ => {}
+        }
+    }
+}
+
 fn spawn_respawn_button(mut c: Commands, mut s: SceneBuilder) {
     c.ui_root()
         .spawn_scene(("ui/main.cob", "respawn_scene"), &mut s, |scene_handle| {
@@ -20,11 +138,24 @@ fn spawn_respawn_button(mut c: Commands, mut s: SceneBuilder) {
             });
         });
 }
-pub fn build_ui(mut commands: Commands, mut s: SceneBuilder) {
+pub fn build_ui(
+    mut commands: Commands,
+    mut s: SceneBuilder,
+    campaign: Res<Campaign>,
+    progress: Res<LevelProgress>,
+    input_settings: Res<InputSettings>,
+) {
+    let mut profiles: Vec<_> = input_settings.profiles.keys().cloned().collect();
+    profiles.sort();
     commands
         .ui_root()
         .spawn_scene(("ui/main.cob", "main_scene"), &mut s, |sc| {
             sc.get("cell::text").update_text("Runtime!");
+            sc.spawn_scene(("ui/main.cob", "controls_hint"), |sc| {
+                sc.get("text").insert(InputPrompt(
+                    "{left} {right} to move, {jump} to jump, {run} to run".to_string(),
+                ));
+            });
 
             for i in 0..=10 {
                 sc.spawn_scene(("ui/main.cob", "number_text"), |sc| {
@@ -34,15 +165,93 @@ pub fn build_ui(mut commands: Commands, mut s: SceneBuilder) {
                     });
                 });
             }
+            sc.spawn_scene(("ui/main.cob", "new_game_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands| {
+                    commands.trigger(NewGame);
+                    commands.trigger(SelectLevel(0));
+                });
+            });
+            //a demo only lists its own levels
+            for (i, level) in campaign.levels().take(campaign.available()).enumerate() {
+                let world = &campaign.worlds[level.world];
+                let mut text = format!("{} - {}", world.name, level.identifier);
+                if !campaign.is_unlocked(level.world, &progress) {
+                    text.push_str(" (locked)");
+                }
+                sc.spawn_scene(("ui/main.cob", "level_button"), |sc| {
+                    sc.get("text").update_text(text);
+                    sc.on_pressed(move |mut commands: Commands| {
+                        commands.trigger(SelectLevel(i));
+                    });
+                });
+            }
             sc.spawn_scene(("ui/main.cob", "despawn_button"), |sc| {
                 sc.on_pressed(
                     |mut commands: Commands, interface: Single<Entity, With<MainInterface>>| {
+                        commands.trigger(UiSound::Back);
                         commands.get_entity(interface.into_inner())?.despawn();
                         commands.run_system_cached(spawn_respawn_button);
                         OK
                     },
                 );
             });
+            sc.spawn_scene(("ui/main.cob", "race_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands| {
+                    commands.trigger(StartRace);
+                });
+            });
+            for name in profiles {
+                let mut text = format!("Controls: {name}");
+                if name == input_settings.active {
+                    text.push_str(" (active)");
+                }
+                sc.spawn_scene(("ui/main.cob", "input_profile_button"), |sc| {
+                    sc.get("text").update_text(text);
+                    sc.on_pressed(move |mut commands: Commands| {
+                        commands.trigger(SwitchInputProfile(name.clone()));
+                    });
+                });
+            }
+            sc.spawn_scene(("ui/main.cob", "export_input_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands, settings: Res<InputSettings>| {
+                    commands.trigger(ExportInputProfile {
+                        name: settings.active.clone(),
+                        path: SHARED_PROFILE_PATH.to_string(),
+                    });
+                });
+            });
+            sc.spawn_scene(("ui/main.cob", "import_input_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands| {
+                    commands.trigger(ImportInputProfile {
+                        name: IMPORTED_PROFILE.to_string(),
+                        path: SHARED_PROFILE_PATH.to_string(),
+                    });
+                });
+            });
+            sc.spawn_scene(("ui/main.cob", "export_ghost_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands, levels: Query<&LevelIid>| {
+                    let Some(level) = levels.iter().next() else {
+                        commands.trigger(UiSound::Error);
+                        return;
+                    };
+                    commands.trigger(ExportGhost {
+                        level: level.to_string(),
+                        path: SHARED_GHOST_PATH.to_string(),
+                    });
+                });
+            });
+            sc.spawn_scene(("ui/main.cob", "import_ghost_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands| {
+                    commands.trigger(ImportGhost {
+                        path: SHARED_GHOST_PATH.to_string(),
+                    });
+                });
+            });
+            sc.spawn_scene(("ui/main.cob", "credits_button"), |sc| {
+                sc.on_pressed(|mut commands: Commands| {
+                    commands.run_system_cached(open_credits);
+                });
+            });
             sc.spawn_scene(("ui/main.cob", "exit_button"), |sc| {
                 sc.on_pressed(
                     |mut commands: Commands, interface: Single<Entity, With<PrimaryWindow>>| {
@@ -1,18 +1,42 @@
 use std::collections::HashSet;
+use std::error::Error;
+use std::fs;
 
-use bevy::{platform::collections::HashMap, prelude::*};
+use bevy::{ecs::query::QueryFilter, platform::collections::HashMap, prelude::*};
 use bevy_ecs_ldtk::prelude::*;
+use serde::Deserialize;
 
 use avian2d::prelude::*;
 
+use crate::locomotion::SurfaceMaterial;
+
+const SURFACES_PATH: &str = "assets/surfaces.ron";
+
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
 pub struct Wall;
 
+/// A merged collider covering wall tiles, see [`spawn_wall_collision`].
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default, Component)]
+pub struct WallCollider;
+
 #[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
 pub struct WallBundle {
     wall: Wall,
 }
 
+/// `assets/surfaces.ron`, the [`SurfaceMaterial`] of walls by int grid value, for ice and mud.
+/// Every value listed is a wall, tiles of values not listed feel like any other ground.
+#[derive(Resource, Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct SurfaceMaterials(pub HashMap<i32, SurfaceMaterial>);
+
+impl SurfaceMaterials {
+    fn read(path: &str) -> Result<Self, Box<dyn Error>> {
+        let string = fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+}
+
 //https://github.com/Trouv/bevy_ecs_ldtk/blob/main/examples/platformer/walls.rs
 
 /// Spawns heron collisions for the walls of a level
@@ -33,28 +57,47 @@ pub struct WallBundle {
 /// 4. spawn colliders for each rectangle
 pub fn spawn_wall_collision(
     mut commands: Commands,
-    wall_query: Query<(&GridCoords, &ChildOf), Added<Wall>>,
+    wall_query: Query<(&GridCoords, &ChildOf, Option<&IntGridCell>), Added<Wall>>,
     parent_query: Query<&ChildOf, Without<Wall>>,
     level_query: Query<(Entity, &LevelIid)>,
     ldtk_projects: Query<&LdtkProjectHandle>,
     ldtk_project_assets: Res<Assets<LdtkProject>>,
+    materials: Res<SurfaceMaterials>,
 ) {
-    /// Represents a wide wall that is 1 tile tall
-    /// Used to spawn wall collisions
-    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
-    struct Plate {
-        left: i32,
-        right: i32,
+    if wall_query.is_empty() {
+        return;
     }
-
-    /// A simple rectangle type representing a wall of any size
-    struct Rect {
-        left: i32,
-        right: i32,
-        top: i32,
-        bottom: i32,
+    //tiles of different materials can't share a collider
+    let mut by_material: HashMap<Option<i32>, Vec<(&GridCoords, &ChildOf)>> = HashMap::new();
+    for (grid_coords, child_of, cell) in wall_query.iter() {
+        let value = cell
+            .map(|cell| cell.value)
+            .filter(|value| materials.0.contains_key(value));
+        by_material.entry(value).or_default().push((grid_coords, child_of));
+    }
+    for (value, tiles) in by_material {
+        let material = value.and_then(|value| materials.0.get(&value)).copied();
+        spawn_merged_walls(
+            &mut commands,
+            tiles.into_iter(),
+            material,
+            &parent_query,
+            &level_query,
+            &ldtk_projects,
+            &ldtk_project_assets,
+        );
     }
+}
 
+fn spawn_merged_walls<'a>(
+    commands: &mut Commands,
+    tiles: impl Iterator<Item = (&'a GridCoords, &'a ChildOf)>,
+    material: Option<SurfaceMaterial>,
+    parent_query: &Query<&ChildOf, Without<Wall>>,
+    level_query: &Query<(Entity, &LevelIid)>,
+    ldtk_projects: &Query<&LdtkProjectHandle>,
+    ldtk_project_assets: &Assets<LdtkProject>,
+) {
     // Consider where the walls are
     // storing them as GridCoords in a HashSet for quick, easy lookup
     //
@@ -62,124 +105,186 @@ pub fn spawn_wall_collision(
     // This has two consequences in the resulting collision entities:
     // 1. it forces the walls to be split along level boundaries
     // 2. it lets us easily add the collision entities as children of the appropriate level entity
-    let mut level_to_wall_locations: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+    let level_to_wall_locations = tiles_by_level(tiles, parent_query);
+
+    level_query.iter().for_each(|(level_entity, level_iid)| {
+        if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
+            let grid = level_grid(level_iid, ldtk_projects, ldtk_project_assets);
+
+            commands.entity(level_entity).with_children(|level| {
+                // Spawn colliders for every rectangle..
+                // Making the collider a child of the level serves two purposes:
+                // 1. Adjusts the transforms to be relative to the level for free
+                // 2. the colliders will be despawned automatically when levels unload
+                for wall_rect in merge_tiles(level_walls, grid.width, grid.height) {
+                    let mut wall = level.spawn(wall_rect.collider(grid.grid_size));
+                    wall.insert((Friction::new(1.0), WallCollider));
+                    if let Some(material) = material {
+                        wall.insert(material);
+                    }
+                }
+            });
+        }
+    });
+}
 
-    wall_query.iter().for_each(|(&grid_coords, child_of)| {
+/// The size of a level's grid, see [`level_grid`].
+#[derive(Clone, Copy, Debug)]
+pub struct LevelGrid {
+    pub width: i32,
+    pub height: i32,
+    pub grid_size: i32,
+}
+
+/// Looks up the grid dimensions of a spawned level.
+pub fn level_grid(
+    level_iid: &LevelIid,
+    ldtk_projects: &Query<&LdtkProjectHandle>,
+    ldtk_project_assets: &Assets<LdtkProject>,
+) -> LevelGrid {
+    let ldtk_project = ldtk_project_assets
+        .get(ldtk_projects.single().unwrap())
+        .expect("Project should be loaded if level has spawned");
+
+    let level = ldtk_project
+        .as_standalone()
+        .get_loaded_level_by_iid(&level_iid.to_string())
+        .expect("Spawned level should exist in LDtk project");
+
+    let LayerInstance {
+        c_wid: width,
+        c_hei: height,
+        grid_size,
+        ..
+    } = level.layer_instances()[0];
+    LevelGrid {
+        width,
+        height,
+        grid_size,
+    }
+}
+
+/// Groups int grid tiles by the level they belong to.
+pub fn tiles_by_level<'a, F: QueryFilter>(
+    tiles: impl Iterator<Item = (&'a GridCoords, &'a ChildOf)>,
+    parent_query: &Query<&ChildOf, F>,
+) -> HashMap<Entity, HashSet<GridCoords>> {
+    let mut level_to_tiles: HashMap<Entity, HashSet<GridCoords>> = HashMap::new();
+    tiles.for_each(|(&grid_coords, child_of)| {
         // An intgrid tile's direct parent will be a layer entity, not the level entity
         // To get the level entity, you need the tile's grandparent.
         // This is where parent_query comes in.
         if let Ok(parent_child_of) = parent_query.get(child_of.parent()) {
-            level_to_wall_locations
+            level_to_tiles
                 .entry(parent_child_of.parent())
                 .or_default()
                 .insert(grid_coords);
         }
     });
+    level_to_tiles
+}
 
-    if !wall_query.is_empty() {
-        level_query.iter().for_each(|(level_entity, level_iid)| {
-            if let Some(level_walls) = level_to_wall_locations.get(&level_entity) {
-                let ldtk_project = ldtk_project_assets
-                    .get(ldtk_projects.single().unwrap())
-                    .expect("Project should be loaded if level has spawned");
-
-                let level = ldtk_project
-                    .as_standalone()
-                    .get_loaded_level_by_iid(&level_iid.to_string())
-                    .expect("Spawned level should exist in LDtk project");
-
-                let LayerInstance {
-                    c_wid: width,
-                    c_hei: height,
-                    grid_size,
-                    ..
-                } = level.layer_instances()[0];
-
-                // combine wall tiles into flat "plates" in each individual row
-                let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
-
-                for y in 0..height {
-                    let mut row_plates: Vec<Plate> = Vec::new();
-                    let mut plate_start = None;
-
-                    // + 1 to the width so the algorithm "terminates" plates that touch the right edge
-                    for x in 0..width + 1 {
-                        match (plate_start, level_walls.contains(&GridCoords { x, y })) {
-                            (Some(s), false) => {
-                                row_plates.push(Plate {
-                                    left: s,
-                                    right: x - 1,
-                                });
-                                plate_start = None;
-                            }
-                            (None, true) => plate_start = Some(x),
-                            _ => (),
-                        }
-                    }
+/// A simple rectangle type representing a wall of any size, in grid coordinates
+#[derive(Clone, Copy, Debug)]
+pub struct TileRect {
+    pub left: i32,
+    pub right: i32,
+    pub top: i32,
+    pub bottom: i32,
+}
 
-                    plate_stack.push(row_plates);
-                }
+impl TileRect {
+    /// The area covered by the rect in pixels, relative to its level.
+    pub fn bounds(&self, grid_size: i32) -> Rect {
+        Rect::new(
+            self.left as f32 * grid_size as f32,
+            self.bottom as f32 * grid_size as f32,
+            (self.right + 1) as f32 * grid_size as f32,
+            (self.top + 1) as f32 * grid_size as f32,
+        )
+    }
 
-                // combine "plates" into rectangles across multiple rows
-                let mut rect_builder: HashMap<Plate, Rect> = HashMap::new();
-                let mut prev_row: Vec<Plate> = Vec::new();
-                let mut wall_rects: Vec<Rect> = Vec::new();
-
-                // an extra empty row so the algorithm "finishes" the rects that touch the top edge
-                plate_stack.push(Vec::new());
-
-                for (y, current_row) in plate_stack.into_iter().enumerate() {
-                    for prev_plate in &prev_row {
-                        if !current_row.contains(prev_plate) {
-                            // remove the finished rect so that the same plate in the future starts a new rect
-                            if let Some(rect) = rect_builder.remove(prev_plate) {
-                                wall_rects.push(rect);
-                            }
-                        }
-                    }
-                    for plate in &current_row {
-                        rect_builder
-                            .entry(plate.clone())
-                            .and_modify(|e| e.top += 1)
-                            .or_insert(Rect {
-                                bottom: y as i32,
-                                top: y as i32,
-                                left: plate.left,
-                                right: plate.right,
-                            });
-                    }
-                    prev_row = current_row;
+    /// A static collider covering the rect, positioned relative to its level.
+    pub fn collider(&self, grid_size: i32) -> (Collider, RigidBody, Transform) {
+        let bounds = self.bounds(grid_size);
+        (
+            Collider::rectangle(bounds.width(), bounds.height()),
+            RigidBody::Static,
+            Transform::from_translation(bounds.center().extend(0.)),
+        )
+    }
+}
+
+/// Merges tiles into as few rectangles as possible.
+///
+/// In basic terms, it will:
+/// 1. combine tiles into flat "plates" in each individual row
+/// 2. combine the plates into rectangles across multiple rows wherever possible
+pub fn merge_tiles(tiles: &HashSet<GridCoords>, width: i32, height: i32) -> Vec<TileRect> {
+    /// Represents a wide wall that is 1 tile tall
+    /// Used to spawn wall collisions
+    #[derive(Clone, Eq, PartialEq, Debug, Default, Hash)]
+    struct Plate {
+        left: i32,
+        right: i32,
+    }
+
+    // combine wall tiles into flat "plates" in each individual row
+    let mut plate_stack: Vec<Vec<Plate>> = Vec::new();
+
+    for y in 0..height {
+        let mut row_plates: Vec<Plate> = Vec::new();
+        let mut plate_start = None;
+
+        // + 1 to the width so the algorithm "terminates" plates that touch the right edge
+        for x in 0..width + 1 {
+            match (plate_start, tiles.contains(&GridCoords { x, y })) {
+                (Some(s), false) => {
+                    row_plates.push(Plate {
+                        left: s,
+                        right: x - 1,
+                    });
+                    plate_start = None;
                 }
+                (None, true) => plate_start = Some(x),
+                _ => (),
+            }
+        }
 
-                commands.entity(level_entity).with_children(|level| {
-                    // Spawn colliders for every rectangle..
-                    // Making the collider a child of the level serves two purposes:
-                    // 1. Adjusts the transforms to be relative to the level for free
-                    // 2. the colliders will be despawned automatically when levels unload
-                    for wall_rect in wall_rects {
-                        level
-                            .spawn_empty()
-                            .insert(Collider::rectangle(
-                                (wall_rect.right as f32 - wall_rect.left as f32 + 1.)
-                                    * grid_size as f32,
-                                (wall_rect.top as f32 - wall_rect.bottom as f32 + 1.)
-                                    * grid_size as f32,
-                            ))
-                            .insert(RigidBody::Static)
-                            .insert(Friction::new(1.0))
-                            .insert(Transform::from_xyz(
-                                (wall_rect.left + wall_rect.right + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                (wall_rect.bottom + wall_rect.top + 1) as f32 * grid_size as f32
-                                    / 2.,
-                                0.,
-                            ))
-                            .insert(GlobalTransform::default());
-                    }
-                });
+        plate_stack.push(row_plates);
+    }
+
+    // combine "plates" into rectangles across multiple rows
+    let mut rect_builder: HashMap<Plate, TileRect> = HashMap::new();
+    let mut prev_row: Vec<Plate> = Vec::new();
+    let mut wall_rects: Vec<TileRect> = Vec::new();
+
+    // an extra empty row so the algorithm "finishes" the rects that touch the top edge
+    plate_stack.push(Vec::new());
+
+    for (y, current_row) in plate_stack.into_iter().enumerate() {
+        for prev_plate in &prev_row {
+            if !current_row.contains(prev_plate) {
+                // remove the finished rect so that the same plate in the future starts a new rect
+                if let Some(rect) = rect_builder.remove(prev_plate) {
+                    wall_rects.push(rect);
+                }
             }
-        });
+        }
+        for plate in &current_row {
+            rect_builder
+                .entry(plate.clone())
+                .and_modify(|e| e.top += 1)
+                .or_insert(TileRect {
+                    bottom: y as i32,
+                    top: y as i32,
+                    left: plate.left,
+                    right: plate.right,
+                });
+        }
+        prev_row = current_row;
     }
+    wall_rects
 }
 
 /// Plugin which spawns walls on appropriate LDtk int cells,
@@ -190,8 +295,16 @@ pub struct WallPlugin;
 
 impl Plugin for WallPlugin {
     fn build(&self, app: &mut App) {
+        let materials = SurfaceMaterials::read(SURFACES_PATH).unwrap_or_else(|e| {
+            info!("no surface materials from {SURFACES_PATH}: {e}");
+            SurfaceMaterials::default()
+        });
         app.add_systems(Update, spawn_wall_collision)
             .register_ldtk_int_cell::<WallBundle>(1) //brick
             .register_ldtk_int_cell::<WallBundle>(2); //interactables
+        for &value in materials.0.keys().filter(|value| ![1, 2].contains(*value)) {
+            app.register_ldtk_int_cell::<WallBundle>(value);
+        }
+        app.insert_resource(materials);
     }
 }
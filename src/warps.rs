@@ -0,0 +1,219 @@
+//! Warp pipes into sub-levels (bonus rooms) and back.
+//!
+//! Entering a warp remembers where the player was, then loads the warp's target level and places
+//! the player on that level's exit warp. Using the exit warp loads the parent level again and puts
+//! the player and camera back where they were. Every player goes through together.
+//!
+//! A warp only takes a player once everyone has stepped off it, so holding down on the exit warp
+//! you arrived at doesn't send you straight back. Level timers keep running over a warp, see
+//! [`Warping`].
+
+use crate::camera::FollowTargets;
+use crate::locomotion::{LocomotionDriver, CROUCH_THRESHOLD};
+use crate::mario::Char;
+use crate::physics::{KinematicController, LastMoveOutput, OnGround};
+use crate::split_screen::{PlayerSlot, JOIN_OFFSET};
+use avian2d::prelude::*;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+use char_controller::prelude::MoveAndSlide;
+
+/// How far a blocked destination is nudged up at a time looking for room, and how many times.
+const NUDGE_STEP: f32 = 4.0;
+const MAX_NUDGES: usize = 8;
+
+#[derive(Component, Clone, Debug, Default, Reflect)]
+pub struct Warp {
+    /// level index to load when entered, unused on exits
+    pub level: Option<usize>,
+    /// whether this warp leads back to the parent level
+    pub exit: bool,
+    pub half_size: Vec2,
+    /// set once no player is standing in the warp, warps spawn unarmed so arriving on one is safe
+    armed: bool,
+}
+
+impl From<&EntityInstance> for Warp {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        Self {
+            level: entity_instance
+                .get_int_field("level")
+                .ok()
+                .map(|&level| level as usize),
+            exit: entity_instance
+                .get_bool_field("exit")
+                .copied()
+                .unwrap_or(false),
+            half_size: vec2(entity_instance.width as f32, entity_instance.height as f32) / 2.0,
+            armed: false,
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct WarpBundle {
+    #[from_entity_instance]
+    warp: Warp,
+}
+
+/// Where to put the player back when leaving a sub-level.
+#[derive(Clone, Debug)]
+pub struct ReturnPoint {
+    pub level: LevelSelection,
+    pub position: Vec2,
+    pub camera: Vec3,
+}
+
+/// Return points of every sub-level we're currently in, innermost last.
+#[derive(Resource, Default, Debug)]
+pub struct WarpStack(pub Vec<ReturnPoint>);
+
+/// Whether a level is loading because of a warp, from entering it until the new level is placed.
+#[derive(Resource, Default, Debug)]
+pub struct Warping(pub bool);
+
+/// What to do with the player once the level we warped to has spawned.
+#[derive(Resource, Default, Debug)]
+enum PendingWarp {
+    #[default]
+    None,
+    ArriveAtExit,
+    Return(ReturnPoint),
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<WarpStack>()
+        .init_resource::<PendingWarp>()
+        .init_resource::<Warping>()
+        .register_ldtk_entity::<WarpBundle>("Warp")
+        .add_systems(Update, (enter_warps, finish_warp).chain())
+        .add_systems(Last, stop_warping);
+}
+
+fn enter_warps(
+    players: Query<(&Transform, &LocomotionDriver, Has<OnGround>), With<Char>>,
+    mut warps: Query<(&mut Warp, &GlobalTransform)>,
+    camera: Query<&Transform, With<FollowTargets>>,
+    mut stack: ResMut<WarpStack>,
+    mut pending: ResMut<PendingWarp>,
+    mut warping: ResMut<Warping>,
+    mut level_selection: ResMut<LevelSelection>,
+) {
+    if !matches!(*pending, PendingWarp::None) {
+        return;
+    }
+    let mut entered = None;
+    for (mut warp, xf) in warps.iter_mut() {
+        let inside = |transform: &Transform| {
+            let offset = (transform.translation.xy() - xf.translation().xy()).abs();
+            offset.x <= warp.half_size.x && offset.y <= warp.half_size.y
+        };
+        let mut occupied = false;
+        for (transform, driver, grounded) in players.iter() {
+            if !inside(transform) {
+                continue;
+            }
+            occupied = true;
+            if warp.armed && grounded && driver.wish_dir.y <= CROUCH_THRESHOLD {
+                entered = entered.or(Some((warp.clone(), transform.translation.xy())));
+            }
+        }
+        warp.armed = !occupied;
+    }
+    let Some((warp, position)) = entered else {
+        return;
+    };
+
+    if warp.exit {
+        let Some(point) = stack.0.pop() else {
+            warn!("exit warp used outside of a sub-level");
+            return;
+        };
+        info!("leaving sub-level back to {:?}", point.level);
+        *level_selection = point.level.clone();
+        *pending = PendingWarp::Return(point);
+        warping.0 = true;
+    } else if let Some(level) = warp.level {
+        info!("entering sub-level {level}");
+        stack.0.push(ReturnPoint {
+            level: level_selection.clone(),
+            position,
+            camera: camera.iter().next().map_or(Vec3::ZERO, |xf| xf.translation),
+        });
+        *level_selection = LevelSelection::index(level);
+        *pending = PendingWarp::ArriveAtExit;
+        warping.0 = true;
+    }
+}
+
+fn stop_warping(mut level_events: MessageReader<LevelEvent>, mut warping: ResMut<Warping>) {
+    //cleared at the end of the frame so everything reading this frame's level events sees it
+    if level_events
+        .read()
+        .any(|event| matches!(event, LevelEvent::Transformed(_)))
+    {
+        warping.0 = false;
+    }
+}
+
+fn finish_warp(
+    mut level_events: MessageReader<LevelEvent>,
+    mut pending: ResMut<PendingWarp>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut KinematicController,
+            &mut LastMoveOutput,
+            Option<&Collider>,
+            Option<&PlayerSlot>,
+        ),
+        With<Char>,
+    >,
+    mut camera: Query<&mut Transform, (With<FollowTargets>, Without<Char>)>,
+    warps: Query<(&Warp, &GlobalTransform)>,
+    move_and_slide: MoveAndSlide,
+) {
+    //warp targets only exist once the level has been placed in the world
+    if !level_events
+        .read()
+        .any(|event| matches!(event, LevelEvent::Transformed(_)))
+    {
+        return;
+    }
+    let destination = match std::mem::take(&mut *pending) {
+        PendingWarp::None => return,
+        PendingWarp::ArriveAtExit => {
+            let Some((_, xf)) = warps.iter().find(|(warp, _)| warp.exit) else {
+                warn!("sub-level has no exit warp, leaving the player where it is");
+                return;
+            };
+            xf.translation().xy()
+        }
+        PendingWarp::Return(point) => {
+            for mut xf in camera.iter_mut() {
+                xf.translation = point.camera;
+            }
+            point.position
+        }
+    };
+    for (entity, mut transform, mut controller, mut last_output, collider, slot) in
+        players.iter_mut()
+    {
+        let destination = destination + JOIN_OFFSET * slot.map_or(0, |slot| slot.0) as f32;
+        //don't drop the player into a wall or a block that moved in the meantime
+        let destination = collider.map_or(destination, |collider| {
+            let filter = SpatialQueryFilter::from_excluded_entities([entity]);
+            (0..=MAX_NUDGES)
+                .map(|nudge| destination + Vec2::Y * NUDGE_STEP * nudge as f32)
+                .find(|&position| move_and_slide.fits_at(collider, position, 0.0, &filter))
+                .unwrap_or_else(|| {
+                    warn!("no room at the warp destination {destination}, placing anyway");
+                    destination
+                })
+        });
+        transform.translation = destination.extend(transform.translation.z);
+        controller.velocity = Vec2::ZERO;
+        last_output.0 = None;
+    }
+}
@@ -0,0 +1,99 @@
+//! Water to swim in, placed in LDtk.
+//!
+//! Int grid value 5 tiles are merged into [`Water`] areas like walls are merged into colliders,
+//! and a `Water` entity is an area the size of the entity. How players swim in it is up to their
+//! [`SwimStats`](crate::jump::SwimStats), going in and out splashes.
+
+use crate::audio::PlaySound;
+use crate::layers::RenderLayerOrder;
+use crate::physics::{EnteredWater, LeftWater, Water};
+use crate::walls::{level_grid, merge_tiles, tiles_by_level};
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+const WATER_INT_CELL: i32 = 5;
+const WATER_COLOR: Color = Color::srgba(0.1, 0.35, 1.0, 0.4);
+
+/// A single water tile.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct WaterTile;
+
+#[derive(Clone, Debug, Default, Bundle, LdtkIntCell)]
+pub struct WaterTileBundle {
+    tile: WaterTile,
+}
+
+#[derive(Bundle, Default)]
+pub struct WaterArea {
+    water: Water,
+    sprite: Sprite,
+}
+
+impl From<&EntityInstance> for WaterArea {
+    fn from(entity_instance: &EntityInstance) -> Self {
+        let size = vec2(entity_instance.width as f32, entity_instance.height as f32);
+        Self {
+            water: Water {
+                half_size: size / 2.0,
+            },
+            sprite: Sprite::from_color(WATER_COLOR, size),
+        }
+    }
+}
+
+#[derive(Default, Bundle, LdtkEntity)]
+pub struct WaterBundle {
+    #[from_entity_instance]
+    area: WaterArea,
+}
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_ldtk_int_cell::<WaterTileBundle>(WATER_INT_CELL)
+        .register_ldtk_entity::<WaterBundle>("Water")
+        .add_systems(Update, spawn_water_areas)
+        .add_observer(splash_in)
+        .add_observer(splash_out);
+}
+
+fn spawn_water_areas(
+    mut commands: Commands,
+    tile_query: Query<(&GridCoords, &ChildOf), Added<WaterTile>>,
+    parent_query: Query<&ChildOf, Without<WaterTile>>,
+    level_query: Query<(Entity, &LevelIid)>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+) {
+    if tile_query.is_empty() {
+        return;
+    }
+    let level_to_tiles = tiles_by_level(tile_query.iter(), &parent_query);
+    for (level_entity, level_iid) in level_query.iter() {
+        let Some(tiles) = level_to_tiles.get(&level_entity) else {
+            continue;
+        };
+        let grid = level_grid(level_iid, &ldtk_projects, &ldtk_project_assets);
+        commands.entity(level_entity).with_children(|level| {
+            for rect in merge_tiles(tiles, grid.width, grid.height) {
+                let bounds = rect.bounds(grid.grid_size);
+                level.spawn((
+                    Name::new("Water"),
+                    Water {
+                        half_size: bounds.half_size(),
+                    },
+                    Sprite::from_color(WATER_COLOR, bounds.size()),
+                    Transform::from_translation(
+                        bounds.center().extend(RenderLayerOrder::Hazards.z()),
+                    ),
+                ));
+            }
+        });
+    }
+}
+
+fn splash_in(_entered: On<EnteredWater>, mut commands: Commands) {
+    commands.trigger(PlaySound::new("splash"));
+}
+
+fn splash_out(_left: On<LeftWater>, mut commands: Commands) {
+    commands.trigger(PlaySound::new("splash"));
+}
@@ -0,0 +1,189 @@
+//! Rain and snow.
+//!
+//! A level's `weather` string field (`rain` or `snow`) and `weather_intensity` float (0 to 1) decide
+//! what falls. Particles live on the camera and lag behind its movement by their depth, so they read
+//! as a layer between the player and the background. With the `slippery` bool field set, snow makes
+//! the ground icy for the whole level.
+
+use crate::camera::FollowTargets;
+use crate::layers::RenderLayerOrder;
+use crate::locomotion::Traction;
+use crate::mario::Char;
+use crate::quality::Quality;
+use crate::time::PausableSystems;
+use bevy::prelude::*;
+use bevy_ecs_ldtk::prelude::*;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Reflect)]
+pub enum WeatherKind {
+    #[default]
+    Clear,
+    Rain,
+    Snow,
+}
+
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Weather {
+    pub kind: WeatherKind,
+    pub intensity: f32,
+    /// snow turns the ground to ice
+    pub slippery: bool,
+}
+
+#[derive(Component, Clone, Copy, Debug)]
+struct WeatherParticle {
+    velocity: Vec2,
+    /// 0 moves with the camera, 1 stays put in the world
+    depth: f32,
+}
+
+/// Particle count at full intensity.
+const MAX_PARTICLES: usize = 300;
+/// Traction on the ground while it's snowing on a slippery level.
+const ICE_TRACTION: f32 = 0.15;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.register_type::<Weather>()
+        .init_resource::<Weather>()
+        .add_systems(
+            Update,
+            (
+                read_level_weather,
+                apply_ice.run_if(resource_changed::<Weather>),
+                (spawn_particles, move_particles)
+                    .chain()
+                    .in_set(PausableSystems),
+            )
+                .chain(),
+        )
+        .add_observer(ice_new_chars);
+}
+
+fn read_level_weather(
+    mut level_events: MessageReader<LevelEvent>,
+    ldtk_projects: Query<&LdtkProjectHandle>,
+    ldtk_project_assets: Res<Assets<LdtkProject>>,
+    mut weather: ResMut<Weather>,
+) {
+    for event in level_events.read() {
+        let LevelEvent::Spawned(iid) = event else {
+            continue;
+        };
+        let Some(level) = ldtk_projects
+            .single()
+            .ok()
+            .and_then(|handle| ldtk_project_assets.get(handle))
+            .and_then(|project| {
+                project
+                    .as_standalone()
+                    .get_loaded_level_by_iid(&iid.to_string())
+            })
+        else {
+            continue;
+        };
+        let level = level.raw();
+        *weather = Weather {
+            kind: match level.get_string_field("weather") {
+                Ok(Some(kind)) if kind == "rain" => WeatherKind::Rain,
+                Ok(Some(kind)) if kind == "snow" => WeatherKind::Snow,
+                _ => WeatherKind::Clear,
+            },
+            intensity: level
+                .get_float_field("weather_intensity")
+                .copied()
+                .unwrap_or(1.0)
+                .clamp(0.0, 1.0),
+            slippery: level.get_bool_field("slippery").copied().unwrap_or(false),
+        };
+    }
+}
+
+fn traction(weather: &Weather) -> Traction {
+    match weather.kind {
+        WeatherKind::Snow if weather.slippery => Traction(ICE_TRACTION),
+        _ => Traction::default(),
+    }
+}
+
+fn apply_ice(mut commands: Commands, weather: Res<Weather>, chars: Query<Entity, With<Char>>) {
+    for entity in chars.iter() {
+        commands.entity(entity).insert(traction(&weather));
+    }
+}
+
+fn ice_new_chars(add: On<Add, Char>, mut commands: Commands, weather: Res<Weather>) {
+    commands.entity(add.entity).insert(traction(&weather));
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    weather: Res<Weather>,
+    quality: Res<Quality>,
+    camera: Query<(Entity, &Projection), With<FollowTargets>>,
+    particles: Query<Entity, With<WeatherParticle>>,
+) {
+    let Some((camera, Projection::Orthographic(projection))) = camera.iter().next() else {
+        return;
+    };
+    let wanted = match weather.kind {
+        WeatherKind::Clear => 0,
+        _ => (weather.intensity * quality.scale() * MAX_PARTICLES as f32) as usize,
+    };
+    let count = particles.iter().count();
+    //weather changed to something lighter
+    for entity in particles.iter().skip(wanted) {
+        commands.entity(entity).despawn();
+    }
+    let area = projection.area;
+    for _ in count..wanted {
+        let depth = rand::random_range(0.2..0.8);
+        let (velocity, size, color) = match weather.kind {
+            WeatherKind::Rain => (
+                vec2(-40.0, -400.0),
+                vec2(1.0, 6.0),
+                Color::srgba(0.7, 0.8, 1.0, 0.5),
+            ),
+            _ => (
+                vec2(rand::random_range(-15.0..15.0), -40.0),
+                Vec2::splat(2.0),
+                Color::srgba(1.0, 1.0, 1.0, 0.8),
+            ),
+        };
+        let position = vec2(
+            rand::random_range(area.min.x..area.max.x),
+            rand::random_range(area.min.y..area.max.y),
+        );
+        commands.entity(camera).with_child((
+            WeatherParticle {
+                velocity: velocity * (1.0 - depth * 0.5),
+                depth,
+            },
+            Sprite::from_color(color, size * (1.0 - depth * 0.5)),
+            Transform::from_translation(position.extend(RenderLayerOrder::Weather.z())),
+        ));
+    }
+}
+
+fn move_particles(
+    camera: Query<(&Transform, &Projection), With<FollowTargets>>,
+    mut particles: Query<(&WeatherParticle, &mut Transform), Without<FollowTargets>>,
+    mut last_camera: Local<Option<Vec2>>,
+    time: Res<Time>,
+) {
+    let Some((camera, Projection::Orthographic(projection))) = camera.iter().next() else {
+        return;
+    };
+    let camera_pos = camera.translation.xy();
+    let camera_delta = last_camera.map_or(Vec2::ZERO, |last| camera_pos - last);
+    *last_camera = Some(camera_pos);
+    let area = projection.area;
+    let size = area.size();
+    for (particle, mut transform) in particles.iter_mut() {
+        let mut position = transform.translation.xy();
+        position += particle.velocity * time.delta_secs() - camera_delta * particle.depth;
+        //wrap around the view so there's always something falling
+        position = (position - area.min).rem_euclid(size) + area.min;
+        transform.translation = position.extend(transform.translation.z);
+    }
+}